@@ -1,59 +1,7 @@
--- Generate random name for pipes
-function generate_random_name(prefix)
-    local random_number = math.random(1, 100000) -- Generate a random number
-    return prefix .. tostring(random_number) -- Combine the prefix with the random number
-end
-
--- Create predefined attributes for physics entity (tried create in Rust but not working)
-function create_physics_attributes(scene_id, entity_id, x, y)
-    --print("Creating predefined physics attributes for entity " .. entity_id .. " in scene " .. scene_id)
-
-    -- Create Vector2 attribute for position
-    create_attribute_vector2(scene_id, entity_id, "position", x, y)
-
-    -- Create Boolean attributes
-    create_attribute_bool(scene_id, entity_id, "is_movable", true)
-    create_attribute_bool(scene_id, entity_id, "has_gravity", false)
-    create_attribute_bool(scene_id, entity_id, "creates_gravity", false)
-    create_attribute_bool(scene_id, entity_id, "has_collision", true)
-    create_attribute_bool(scene_id, entity_id, "can_rotate", true)
-    -- Pipes are script-driven obstacles: they move via set_velocity but must
-    -- not be pushed around by the bird
-    create_attribute_bool(scene_id, entity_id, "is_kinematic", true)
-
-    -- Create Float attributes
-    create_attribute_float(scene_id, entity_id, "friction", 0.5)
-    create_attribute_float(scene_id, entity_id, "restitution", 0.0)
-    create_attribute_float(scene_id, entity_id, "density", 1.0)
-
-    --print("Predefined physics attributes created for entity " .. entity_id)
-end
-
--- Create pipe entity
-function create_pipe(scene_id, pipe_name_prefix, x, y, image_path, script_path)
-    -- Generate a random name for the pipe
-    local pipe_name = generate_random_name(pipe_name_prefix)
-    --print("Creating pipe '" .. pipe_name .. "' in scene " .. scene_id)
-
-    --print("x " .. x, ", y " .. y)
-
-    -- Add the pipe entity to the scene
-    local entity_id = add_entity(scene_id, pipe_name)
-    --print("Created pipe entity with ID: " .. entity_id)
-
-    -- Update attributes for the pipe
-    set_x(scene_id, entity_id, x)
-    set_y(scene_id, entity_id, y)
-    set_z(scene_id, entity_id, 1.0)
-    set_position(scene_id, entity_id, x, y)
-
-    -- Attach image and script
-    add_image(entity_id, image_path)
-    set_script(entity_id, script_path)
-
-    --print("Pipe '" .. pipe_name .. "' created with attributes and assets.")
-    return entity_id
-end
+-- Pipes are spawned from prefabs (assets/prefabs/*.prefab): kinematic,
+-- script-driven obstacles with their image and top_pipe1.lua attached
+local TOP_PIPE_PREFAB = "assets/prefabs/top_pipe.prefab"
+local BOTTOM_PIPE_PREFAB = "assets/prefabs/bottom_pipe.prefab"
 
 -- TODO: due to list_entities_name_x_y is not working, this also not working
 function cleanup_pipes(scene_id)
@@ -105,27 +53,10 @@ function update(scene_id, entity_id)
         local random_top_y = math.random(-100, -50) -- above top
         local random_bottom_y = math.random(150, 200) -- at least below top pipe, otherwise they hit each other and stop outside of the scene
 
-        -- Create top pipe
-        local top_pipe_id = create_pipe(
-            scene_id,
-            "top_pipe_",     -- Prefix for the pipe name
-            random_x,        -- Random x position
-            random_top_y,    -- Random y position
-            "assets/images/top_pipe.png",  -- image path
-            "assets/scripts/top_pipe1.lua" -- script path
-        )
-
-        local bottom_pipe_id = create_pipe(
-                    scene_id,
-                    "top_pipe_",
-                    random_x,
-                    random_bottom_y,
-                    "assets/images/bottom_pipe.png",
-                    "assets/scripts/top_pipe1.lua"
-                )
+        -- Create the pipe pair
+        local top_pipe_id = spawn_prefab(scene_id, TOP_PIPE_PREFAB, random_x, random_top_y)
+        local bottom_pipe_id = spawn_prefab(scene_id, BOTTOM_PIPE_PREFAB, random_x, random_bottom_y)
 
-        create_physics_attributes(scene_id, top_pipe_id, random_x, random_top_y)
-        create_physics_attributes(scene_id, bottom_pipe_id, random_x, random_bottom_y)
         cleanup_pipes(scene_id)
 
         ---- Add entity to physics engine, due to it has different frame rate
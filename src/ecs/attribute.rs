@@ -1,5 +1,9 @@
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::Deref;
+use std::path::PathBuf;
 use uuid::Uuid;
 
 // =============== Attribute Types ===============
@@ -11,13 +15,19 @@ pub struct Attribute {
     pub value: AttributeValue,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum AttributeType {
     Integer,
     Float,
     String,
     Boolean,
     Vector2,
+    Vector3,
+    Color,
+    EntityRef,
+    AssetRef,
+    List,
+    Map,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -27,6 +37,421 @@ pub enum AttributeValue {
     String(String),
     Boolean(bool),
     Vector2(f32, f32),
+    Vector3(f32, f32, f32),
+    Color(f32, f32, f32, f32), // RGBA, each 0.0 - 1.0
+    // Another entity in the same scene (or a shared entity). Cleared to
+    // None when that entity is deleted.
+    EntityRef(Option<Uuid>),
+    // A project file; absolute in memory, stored relative like images/sounds
+    AssetRef(PathBuf),
+    List(Vec<AttributeValue>),
+    Map(IndexMap<String, AttributeValue>),
+}
+
+impl AttributeValue {
+    /// The type this value is an instance of.
+    pub fn data_type(&self) -> AttributeType {
+        match self {
+            AttributeValue::Integer(_) => AttributeType::Integer,
+            AttributeValue::Float(_) => AttributeType::Float,
+            AttributeValue::String(_) => AttributeType::String,
+            AttributeValue::Boolean(_) => AttributeType::Boolean,
+            AttributeValue::Vector2(_, _) => AttributeType::Vector2,
+            AttributeValue::Vector3(_, _, _) => AttributeType::Vector3,
+            AttributeValue::Color(_, _, _, _) => AttributeType::Color,
+            AttributeValue::EntityRef(_) => AttributeType::EntityRef,
+            AttributeValue::AssetRef(_) => AttributeType::AssetRef,
+            AttributeValue::List(_) => AttributeType::List,
+            AttributeValue::Map(_) => AttributeType::Map,
+        }
+    }
+
+    /// Default value for a freshly created attribute of `data_type`.
+    pub fn default_for(data_type: &AttributeType) -> AttributeValue {
+        match data_type {
+            AttributeType::Integer => AttributeValue::Integer(0),
+            AttributeType::Float => AttributeValue::Float(0.0),
+            AttributeType::String => AttributeValue::String(String::new()),
+            AttributeType::Boolean => AttributeValue::Boolean(false),
+            AttributeType::Vector2 => AttributeValue::Vector2(0.0, 0.0),
+            AttributeType::Vector3 => AttributeValue::Vector3(0.0, 0.0, 0.0),
+            AttributeType::Color => AttributeValue::Color(1.0, 1.0, 1.0, 1.0),
+            AttributeType::EntityRef => AttributeValue::EntityRef(None),
+            AttributeType::AssetRef => AttributeValue::AssetRef(PathBuf::new()),
+            AttributeType::List => AttributeValue::List(Vec::new()),
+            AttributeType::Map => AttributeValue::Map(IndexMap::new()),
+        }
+    }
+
+    /// Convert to `target` without losing information. Numbers convert
+    /// as in `coerce_number`, Vector2 -> Vector3 with z = 0. Anything else
+    /// across types is a `TypeMismatch`.
+    pub fn coerce_to(self, target: &AttributeType) -> Result<AttributeValue, AttributeError> {
+        let found = self.data_type();
+        match (self, target) {
+            (value, target) if value.data_type() == *target => Ok(value),
+            (AttributeValue::Integer(i), AttributeType::Float) => {
+                Self::coerce_number(i as f64, target)
+            }
+            (AttributeValue::Float(f), AttributeType::Integer) => {
+                Self::coerce_number(f as f64, target)
+            }
+            (AttributeValue::Vector2(x, y), AttributeType::Vector3) => {
+                Ok(AttributeValue::Vector3(x, y, 0.0))
+            }
+            (_, target) => Err(AttributeError::TypeMismatch {
+                expected: target.clone(),
+                found,
+            }),
+        }
+    }
+
+    /// A number (at full precision, e.g. from Lua) as an Integer or Float
+    /// value. Integer takes whole numbers in range. Float rounds to f32
+    /// precision, but refuses numbers that overflow it and whole numbers
+    /// it can't hold exactly (beyond 2^24 most can't), which would come
+    /// out as a different integer.
+    pub fn coerce_number(n: f64, target: &AttributeType) -> Result<AttributeValue, AttributeError> {
+        let lossy = || AttributeError::Lossy {
+            value: n.to_string(),
+            target: target.clone(),
+        };
+        match target {
+            AttributeType::Integer => {
+                if n.fract() == 0.0 && n >= i32::MIN as f64 && n <= i32::MAX as f64 {
+                    Ok(AttributeValue::Integer(n as i32))
+                } else {
+                    Err(lossy())
+                }
+            }
+            AttributeType::Float => {
+                let f = n as f32;
+                let changed_whole = n.fract() == 0.0 && f as f64 != n;
+                if f.is_finite() == n.is_finite() && !changed_whole {
+                    Ok(AttributeValue::Float(f))
+                } else {
+                    Err(lossy())
+                }
+            }
+            _ => Err(AttributeError::TypeMismatch {
+                expected: target.clone(),
+                found: AttributeType::Float,
+            }),
+        }
+    }
+
+    /// Parse user input (e.g. an inspector text field) as a `data_type`
+    /// value. Numbers go through `coerce_to`, so "3" is a valid Float and
+    /// "3.0" a valid Integer. Vectors are written "x, y[, z]", colors
+    /// "r, g, b[, a]" (0.0 - 1.0) or "#rrggbb[aa]", an empty EntityRef is
+    /// "none", and List/Map use JSON (see `from_json`).
+    pub fn parse(input: &str, data_type: &AttributeType) -> Result<AttributeValue, AttributeError> {
+        let input = input.trim();
+        let parse_error = || AttributeError::Parse {
+            input: input.to_string(),
+            expected: data_type.clone(),
+        };
+        match data_type {
+            AttributeType::Integer | AttributeType::Float => {
+                let number = input.parse::<f64>().map_err(|_| parse_error())?;
+                Self::coerce_number(number, data_type)
+            }
+            AttributeType::String => Ok(AttributeValue::String(input.to_string())),
+            AttributeType::Boolean => input
+                .parse::<bool>()
+                .map(AttributeValue::Boolean)
+                .map_err(|_| parse_error()),
+            AttributeType::Vector2 => match parse_floats(input).as_deref() {
+                Some([x, y]) => Ok(AttributeValue::Vector2(*x, *y)),
+                _ => Err(parse_error()),
+            },
+            AttributeType::Vector3 => match parse_floats(input).as_deref() {
+                Some([x, y, z]) => Ok(AttributeValue::Vector3(*x, *y, *z)),
+                _ => Err(parse_error()),
+            },
+            AttributeType::Color => {
+                let rgba = match input.strip_prefix('#') {
+                    Some(hex) => parse_hex_color(hex),
+                    None => match parse_floats(input).as_deref() {
+                        Some([r, g, b]) => Some((*r, *g, *b, 1.0)),
+                        Some([r, g, b, a]) => Some((*r, *g, *b, *a)),
+                        _ => None,
+                    },
+                };
+                match rgba {
+                    Some((r, g, b, a)) => Ok(AttributeValue::Color(r, g, b, a)),
+                    None => Err(parse_error()),
+                }
+            }
+            AttributeType::EntityRef => {
+                if input.is_empty() || input.eq_ignore_ascii_case("none") {
+                    return Ok(AttributeValue::EntityRef(None));
+                }
+                Uuid::parse_str(input)
+                    .map(|id| AttributeValue::EntityRef(Some(id)))
+                    .map_err(|_| parse_error())
+            }
+            AttributeType::AssetRef => Ok(AttributeValue::AssetRef(PathBuf::from(input))),
+            AttributeType::List | AttributeType::Map => {
+                let json: serde_json::Value =
+                    serde_json::from_str(input).map_err(|_| parse_error())?;
+                Self::from_json(&json).coerce_to(data_type)
+            }
+        }
+    }
+
+    /// `parse` as the type of `current` (the value being edited), with a
+    /// List/Map keeping the types of its items (see `from_json_like`).
+    pub fn parse_like(
+        input: &str,
+        current: &AttributeValue,
+    ) -> Result<AttributeValue, AttributeError> {
+        let data_type = current.data_type();
+        match current {
+            AttributeValue::List(_) | AttributeValue::Map(_) => {
+                let json: serde_json::Value =
+                    serde_json::from_str(input.trim()).map_err(|_| AttributeError::Parse {
+                        input: input.trim().to_string(),
+                        expected: data_type.clone(),
+                    })?;
+                Self::from_json_like(&json, current).coerce_to(&data_type)
+            }
+            _ => Self::parse(input, &data_type),
+        }
+    }
+
+    /// Plain JSON view of the value, used to display and edit List/Map:
+    /// vectors and colors become arrays, refs become strings (or null).
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::{json, Value};
+        match self {
+            AttributeValue::Integer(i) => json!(i),
+            AttributeValue::Float(f) => json!(f),
+            AttributeValue::String(s) => json!(s),
+            AttributeValue::Boolean(b) => json!(b),
+            AttributeValue::Vector2(x, y) => json!([x, y]),
+            AttributeValue::Vector3(x, y, z) => json!([x, y, z]),
+            AttributeValue::Color(r, g, b, a) => json!([r, g, b, a]),
+            AttributeValue::EntityRef(id) => id.map_or(Value::Null, |id| json!(id.to_string())),
+            AttributeValue::AssetRef(path) => json!(path.to_string_lossy()),
+            AttributeValue::List(items) => Value::Array(items.iter().map(Self::to_json).collect()),
+            AttributeValue::Map(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Inverse of `to_json` as far as JSON allows: whole numbers become
+    /// Integer, other numbers Float, arrays List, objects Map, null an
+    /// empty EntityRef. Typed vectors/colors/refs inside a List or Map
+    /// come back as their plain forms.
+    pub fn from_json(json: &serde_json::Value) -> AttributeValue {
+        use serde_json::Value;
+        match json {
+            Value::Null => AttributeValue::EntityRef(None),
+            Value::Bool(b) => AttributeValue::Boolean(*b),
+            Value::Number(n) => match n.as_i64().and_then(|i| i32::try_from(i).ok()) {
+                Some(i) => AttributeValue::Integer(i),
+                None => AttributeValue::Float(n.as_f64().unwrap_or_default() as f32),
+            },
+            Value::String(s) => AttributeValue::String(s.clone()),
+            Value::Array(items) => {
+                AttributeValue::List(items.iter().map(Self::from_json).collect())
+            }
+            Value::Object(entries) => AttributeValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Self::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// `from_json` with `like` as the schema: each item or entry takes the
+    /// type of the one at the same index or key in `like` (new list items
+    /// that of its first item), so vectors, colors and refs keep their
+    /// types. JSON that doesn't fit its schema comes back as `from_json`
+    /// reads it.
+    pub fn from_json_like(json: &serde_json::Value, like: &AttributeValue) -> AttributeValue {
+        use serde_json::Value;
+        let numbers = |items: &[Value]| -> Option<Vec<f32>> {
+            items
+                .iter()
+                .map(|item| item.as_f64().map(|n| n as f32))
+                .collect()
+        };
+        let typed = match (json, like) {
+            (Value::Array(items), AttributeValue::List(schema)) => Some(AttributeValue::List(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| match schema.get(i).or(schema.first()) {
+                        Some(like) => Self::from_json_like(item, like),
+                        None => Self::from_json(item),
+                    })
+                    .collect(),
+            )),
+            (Value::Object(entries), AttributeValue::Map(schema)) => Some(AttributeValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match schema.get(key) {
+                            Some(like) => Self::from_json_like(value, like),
+                            None => Self::from_json(value),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            )),
+            (Value::Number(n), AttributeValue::Integer(_) | AttributeValue::Float(_)) => n
+                .as_f64()
+                .and_then(|n| Self::coerce_number(n, &like.data_type()).ok()),
+            (Value::Array(items), AttributeValue::Vector2(..)) => match numbers(items).as_deref() {
+                Some([x, y]) => Some(AttributeValue::Vector2(*x, *y)),
+                _ => None,
+            },
+            (Value::Array(items), AttributeValue::Vector3(..)) => match numbers(items).as_deref() {
+                Some([x, y, z]) => Some(AttributeValue::Vector3(*x, *y, *z)),
+                _ => None,
+            },
+            (Value::Array(items), AttributeValue::Color(..)) => match numbers(items).as_deref() {
+                Some([r, g, b]) => Some(AttributeValue::Color(*r, *g, *b, 1.0)),
+                Some([r, g, b, a]) => Some(AttributeValue::Color(*r, *g, *b, *a)),
+                _ => None,
+            },
+            (Value::Null, AttributeValue::EntityRef(_)) => Some(AttributeValue::EntityRef(None)),
+            (Value::String(s), AttributeValue::EntityRef(_)) => Uuid::parse_str(s)
+                .ok()
+                .map(|id| AttributeValue::EntityRef(Some(id))),
+            (Value::String(s), AttributeValue::AssetRef(_)) => {
+                Some(AttributeValue::AssetRef(PathBuf::from(s)))
+            }
+            _ => None,
+        };
+        typed.unwrap_or_else(|| Self::from_json(json))
+    }
+
+    /// Rewrite every EntityRef in this value (including inside List/Map)
+    /// with `f`; returning None clears the reference.
+    pub fn remap_entity_refs(&mut self, f: &impl Fn(Uuid) -> Option<Uuid>) {
+        match self {
+            AttributeValue::EntityRef(Some(id)) => *self = AttributeValue::EntityRef(f(*id)),
+            AttributeValue::List(items) => {
+                for item in items {
+                    item.remap_entity_refs(f);
+                }
+            }
+            AttributeValue::Map(entries) => {
+                for value in entries.values_mut() {
+                    value.remap_entity_refs(f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Apply `f` to every AssetRef path in this value (including inside
+    /// List/Map).
+    pub fn map_asset_refs(&mut self, f: &mut impl FnMut(&std::path::Path) -> PathBuf) {
+        match self {
+            AttributeValue::AssetRef(path) if !path.as_os_str().is_empty() => *path = f(path),
+            AttributeValue::List(items) => {
+                for item in items {
+                    item.map_asset_refs(f);
+                }
+            }
+            AttributeValue::Map(entries) => {
+                for value in entries.values_mut() {
+                    value.map_asset_refs(f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Comma-separated floats ("1, 2.5, 3")
+fn parse_floats(input: &str) -> Option<Vec<f32>> {
+    input
+        .split(',')
+        .map(|part| part.trim().parse::<f32>().ok())
+        .collect()
+}
+
+// "rrggbb" or "rrggbbaa" to 0.0 - 1.0 components
+fn parse_hex_color(hex: &str) -> Option<(f32, f32, f32, f32)> {
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .map(|c| c as f32 / 255.0)
+    };
+    let alpha = if hex.len() == 8 { channel(6)? } else { 1.0 };
+    Some((channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+// =============== Attribute Errors ===============
+/// Why an attribute could not be created or changed.
+///
+/// Converts into `String` so callers that report errors as text (most of
+/// the engine) can keep using `?`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeError {
+    NotFound(String),
+    AlreadyExists(String),
+    // Built-in attributes that can't be changed this way (e.g. is_camera)
+    Protected(String),
+    TypeMismatch {
+        expected: AttributeType,
+        found: AttributeType,
+    },
+    // Same kind of value, but converting would lose information
+    Lossy {
+        value: String,
+        target: AttributeType,
+    },
+    Parse {
+        input: String,
+        expected: AttributeType,
+    },
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeError::NotFound(name) => write!(f, "Attribute '{}' not found", name),
+            AttributeError::AlreadyExists(name) => {
+                write!(f, "Attribute '{}' already exists", name)
+            }
+            AttributeError::Protected(name) => write!(f, "Cannot modify {} attribute", name),
+            AttributeError::TypeMismatch { expected, found } => {
+                write!(f, "Expected a {:?} value, got {:?}", expected, found)
+            }
+            AttributeError::Lossy { value, target } => {
+                write!(
+                    f,
+                    "{} can't be stored as {:?} without losing precision",
+                    value, target
+                )
+            }
+            AttributeError::Parse { input, expected } => {
+                write!(f, "'{}' is not a valid {:?}", input, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttributeError {}
+
+impl From<AttributeError> for String {
+    fn from(error: AttributeError) -> Self {
+        error.to_string()
+    }
 }
 
 impl fmt::Display for AttributeValue {
@@ -44,6 +469,157 @@ impl fmt::Display for AttributeValue {
             AttributeValue::String(value) => write!(f, "{}", value),
             AttributeValue::Boolean(value) => write!(f, "{}", value),
             AttributeValue::Vector2(x, y) => write!(f, "{}, {}", x, y),
+            AttributeValue::Vector3(x, y, z) => write!(f, "{}, {}, {}", x, y, z),
+            AttributeValue::Color(r, g, b, a) => write!(f, "{}, {}, {}, {}", r, g, b, a),
+            AttributeValue::EntityRef(Some(id)) => write!(f, "{}", id),
+            AttributeValue::EntityRef(None) => write!(f, "none"),
+            AttributeValue::AssetRef(path) => write!(f, "{}", path.display()),
+            AttributeValue::List(_) | AttributeValue::Map(_) => write!(f, "{}", self.to_json()),
         }
     }
 }
+
+// =============== Attribute Storage ===============
+/// An entity's attributes in insertion order, plus a name → id index so
+/// `get_by_name` doesn't scan (renderer and physics look attributes up by
+/// name for every entity every frame).
+///
+/// Derefs to the `IndexMap` for reading. Writes go through the methods
+/// below, which keep the index in sync: `get_mut` and `values_mut` hand
+/// out an `AttributeMut`, which can't change the name, so renames have to
+/// go through `rename`. Serialized as the plain map, and the index is
+/// rebuilt on deserialization.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeMap {
+    attributes: IndexMap<Uuid, Attribute>,
+    by_name: HashMap<String, Uuid>,
+}
+
+impl AttributeMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Attribute> {
+        let id = self.by_name.get(name)?;
+        self.attributes.get(id).filter(|attr| attr.name == name)
+    }
+
+    /// Insert (or replace) an attribute, keyed by its id.
+    pub fn insert(&mut self, attribute: Attribute) -> Option<Attribute> {
+        let previous = self.attributes.insert(attribute.id, attribute.clone());
+        if let Some(previous) = &previous {
+            self.unindex(previous);
+        }
+        self.by_name.insert(attribute.name, attribute.id);
+        previous
+    }
+
+    /// Remove an attribute, keeping the order of the others.
+    pub fn shift_remove(&mut self, id: &Uuid) -> Option<Attribute> {
+        let removed = self.attributes.shift_remove(id)?;
+        self.unindex(&removed);
+        Some(removed)
+    }
+
+    pub fn rename(&mut self, id: Uuid, new_name: &str) -> Result<(), AttributeError> {
+        let attr = self
+            .attributes
+            .get_mut(&id)
+            .ok_or_else(|| AttributeError::NotFound(id.to_string()))?;
+        let old_name = std::mem::replace(&mut attr.name, new_name.to_string());
+        if self.by_name.get(&old_name) == Some(&id) {
+            self.by_name.remove(&old_name);
+        }
+        self.by_name.insert(new_name.to_string(), id);
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<AttributeMut<'_>> {
+        self.attributes.get_mut(id).map(AttributeMut::new)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = AttributeMut<'_>> {
+        self.attributes.values_mut().map(AttributeMut::new)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&Attribute) -> bool) {
+        self.attributes.retain(|_, attr| keep(attr));
+        self.rebuild_index();
+    }
+
+    fn unindex(&mut self, attribute: &Attribute) {
+        if self.by_name.get(&attribute.name) == Some(&attribute.id) {
+            self.by_name.remove(&attribute.name);
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.by_name = self
+            .attributes
+            .values()
+            .map(|attr| (attr.name.clone(), attr.id))
+            .collect();
+    }
+}
+
+/// Write access to a stored attribute's type and value. The id and name
+/// are read-only: the map is indexed by them.
+pub struct AttributeMut<'a> {
+    pub id: Uuid,
+    pub name: &'a str,
+    pub data_type: &'a mut AttributeType,
+    pub value: &'a mut AttributeValue,
+}
+
+impl<'a> AttributeMut<'a> {
+    fn new(attribute: &'a mut Attribute) -> Self {
+        Self {
+            id: attribute.id,
+            name: &attribute.name,
+            data_type: &mut attribute.data_type,
+            value: &mut attribute.value,
+        }
+    }
+}
+
+impl Deref for AttributeMap {
+    type Target = IndexMap<Uuid, Attribute>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.attributes
+    }
+}
+
+impl<'a> IntoIterator for &'a AttributeMap {
+    type Item = (&'a Uuid, &'a Attribute);
+    type IntoIter = indexmap::map::Iter<'a, Uuid, Attribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.attributes.iter()
+    }
+}
+
+impl FromIterator<(Uuid, Attribute)> for AttributeMap {
+    fn from_iter<I: IntoIterator<Item = (Uuid, Attribute)>>(iter: I) -> Self {
+        let mut map = Self {
+            attributes: iter.into_iter().collect(),
+            by_name: HashMap::new(),
+        };
+        map.rebuild_index();
+        map
+    }
+}
+
+impl Serialize for AttributeMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.attributes.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AttributeMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        IndexMap::<Uuid, Attribute>::deserialize(deserializer)
+            .map(|attributes| attributes.into_iter().collect())
+    }
+}
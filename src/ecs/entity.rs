@@ -1,5 +1,7 @@
-use super::{Attribute, AttributeType, AttributeValue};
-use indexmap::IndexMap;
+use super::{
+    Attribute, AttributeError, AttributeMap, AttributeMut, AttributeType, AttributeValue,
+    PrefabLink, ResourceKind, SceneEvent,
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use uuid::Uuid;
@@ -31,16 +33,41 @@ impl Default for PhysicsProperties {
     }
 }
 
+// Entry in `PrefabLink::overrides` once an instance's tags were edited
+// (not a valid attribute name, so it can't clash with one)
+pub(crate) const TAGS_OVERRIDE: &str = "@tags";
+
 // =============== Entity (Manages Attributes) ===============
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Entity {
     pub id: Uuid,
     pub name: String,
-    pub attributes: IndexMap<Uuid, Attribute>,
+    pub attributes: AttributeMap,
     // Resource paths
     pub images: Vec<PathBuf>,    // Multiple images (sprites, textures)
     pub sounds: Vec<PathBuf>,    // Multiple sounds (effects, music)
     pub script: Option<PathBuf>, // Single script per entity
+    // Parent entity in the same scene; x/y/z, rotation and scale are then
+    // local to the parent (see `Scene::world_transform`)
+    #[serde(default)]
+    pub parent: Option<Uuid>,
+    // Set on prefab instances (see `Scene::instantiate_prefab`)
+    #[serde(default)]
+    pub prefab: Option<PrefabLink>,
+    // Free-form labels for queries ("enemy", "pickup", ...)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Disabled entities (and their descendants, see `Scene::is_active`) are
+    // not rendered, simulated or scripted, but stay in the scene
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Changes not yet drained (see `Scene::drain_events`)
+    #[serde(skip)]
+    pub(super) events: Vec<SceneEvent>,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl Entity {
@@ -49,10 +76,15 @@ impl Entity {
         let mut entity = Self {
             id,
             name: name.to_string(),
-            attributes: IndexMap::new(),
+            attributes: AttributeMap::new(),
             images: Vec::new(),
             sounds: Vec::new(),
             script: None,
+            parent: None,
+            prefab: None,
+            tags: Vec::new(),
+            enabled: true,
+            events: Vec::new(),
         };
 
         // Core position attributes that cannot be deleted
@@ -74,6 +106,7 @@ impl Entity {
     // Resource management methods
     pub fn add_image(&mut self, path: PathBuf) -> Result<(), String> {
         if !self.images.contains(&path) {
+            self.record_resource(true, ResourceKind::Image, &path);
             self.images.push(path);
             Ok(())
         } else {
@@ -86,11 +119,13 @@ impl Entity {
             return Err("Image not found".to_string());
         }
         self.images.retain(|p| p != path);
+        self.record_resource(false, ResourceKind::Image, path);
         Ok(())
     }
 
     pub fn add_sound(&mut self, path: PathBuf) -> Result<(), String> {
         if !self.sounds.contains(&path) {
+            self.record_resource(true, ResourceKind::Sound, &path);
             self.sounds.push(path);
             Ok(())
         } else {
@@ -103,6 +138,7 @@ impl Entity {
             return Err("Sound not found".to_string());
         }
         self.sounds.retain(|p| p != path);
+        self.record_resource(false, ResourceKind::Sound, path);
         Ok(())
     }
 
@@ -110,19 +146,55 @@ impl Entity {
         if self.script.is_some() {
             Err("Script already exists".to_string())
         } else {
+            self.record_resource(true, ResourceKind::Script, &path);
             self.script = Some(path);
             Ok(())
         }
     }
 
     pub fn remove_script(&mut self) -> Result<(), String> {
-        if self.script.is_none() {
+        let Some(path) = self.script.take() else {
             return Err("No script to remove".to_string());
+        };
+        self.record_resource(false, ResourceKind::Script, &path);
+        Ok(())
+    }
+
+    // Tags
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), String> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("Tag cannot be empty".to_string());
+        }
+        if self.has_tag(tag) {
+            return Err(format!("Tag '{}' already exists", tag));
         }
-        self.script = None;
+        self.tags.push(tag.to_string());
+        self.mark_prefab_override(TAGS_OVERRIDE);
         Ok(())
     }
 
+    pub fn remove_tag(&mut self, tag: &str) -> Result<(), String> {
+        if !self.has_tag(tag) {
+            return Err(format!("Tag '{}' not found", tag));
+        }
+        self.tags.retain(|t| t != tag);
+        self.mark_prefab_override(TAGS_OVERRIDE);
+        Ok(())
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     // Helper methods to check resource existence
     pub fn has_image(&self, path: &PathBuf) -> bool {
         self.images.contains(path)
@@ -161,16 +233,19 @@ impl Entity {
     }
 
     // Attribute management
+    // The value must match data_type, or coerce to it losslessly
+    // (see AttributeValue::coerce_to)
     pub fn create_attribute(
         &mut self,
         name: &str,
         data_type: AttributeType,
         value: AttributeValue,
-    ) -> Result<Uuid, String> {
+    ) -> Result<Uuid, AttributeError> {
         // Check for duplicate names
         if self.get_attribute_by_name(name).is_ok() {
-            return Err(format!("Attribute '{}' already exists", name));
+            return Err(AttributeError::AlreadyExists(name.to_string()));
         }
+        let value = value.coerce_to(&data_type)?;
 
         let id = Uuid::new_v4();
         let attribute = Attribute {
@@ -179,7 +254,13 @@ impl Entity {
             data_type,
             value,
         };
-        self.attributes.insert(id, attribute);
+        self.attributes.insert(attribute);
+        self.mark_prefab_override(name);
+        self.events.push(SceneEvent::AttributeCreated {
+            entity_id: self.id,
+            attribute_id: id,
+            name: name.to_string(),
+        });
         Ok(id)
     }
 
@@ -194,12 +275,10 @@ impl Entity {
                 }
                 // Physics-specific attributes
                 "is_movable" | "has_gravity" | "creates_gravity" | "has_collision" | "friction"
-                | "restitution" | "density" | "can_rotate" => {
-                    if self.name.contains("physical") {
-                        return Err(
-                            "Cannot delete physics attributes from physical entity".to_string()
-                        );
-                    }
+                | "restitution" | "density" | "can_rotate"
+                    if self.name.contains("physical") =>
+                {
+                    return Err("Cannot delete physics attributes from physical entity".to_string());
                 }
                 // Camera-specific attributes - expanded list
                 "width" | "height" | "zoom" | "rotation" | "is_camera"
@@ -209,6 +288,13 @@ impl Entity {
                 }
                 _ => {}
             }
+            let name = attr.name.clone();
+            self.mark_prefab_override(&name);
+            self.events.push(SceneEvent::AttributeDeleted {
+                entity_id: self.id,
+                attribute_id: id,
+                name,
+            });
             Ok(self.attributes.shift_remove(&id).is_some())
         } else {
             Ok(false)
@@ -228,44 +314,142 @@ impl Entity {
             .ok_or_else(|| format!("Attribute {} not found", id))
     }
 
-    pub fn get_attribute_mut(&mut self, id: Uuid) -> Result<&mut Attribute, String> {
+    // Type and value only, unchecked; rename and coerce with modify_attribute
+    pub fn get_attribute_mut(&mut self, id: Uuid) -> Result<AttributeMut<'_>, String> {
         self.attributes
             .get_mut(&id)
             .ok_or_else(|| format!("Attribute {} not found", id))
     }
 
+    // Type and value stay consistent: a new value is coerced to the
+    // (new or current) type, and a type change alone converts the current
+    // value. Nothing is changed if any part fails.
     pub fn modify_attribute(
         &mut self,
         id: Uuid,
         new_name: Option<String>,
         new_type: Option<AttributeType>,
         new_value: Option<AttributeValue>,
-    ) -> Result<(), String> {
-        if let Some(attr) = self.attributes.get_mut(&id) {
-            if attr.name == "is_camera" {
-                return Err("Cannot modify is_camera attribute".to_string());
+    ) -> Result<(), AttributeError> {
+        let attr = self
+            .attributes
+            .get(&id)
+            .ok_or_else(|| AttributeError::NotFound(id.to_string()))?;
+        if attr.name == "is_camera" {
+            return Err(AttributeError::Protected("is_camera".to_string()));
+        }
+        if let Some(name) = &new_name {
+            if self
+                .attributes
+                .get_by_name(name)
+                .is_some_and(|other| other.id != id)
+            {
+                return Err(AttributeError::AlreadyExists(name.clone()));
             }
+        }
+        let data_type = new_type.unwrap_or_else(|| attr.data_type.clone());
+        let value = match new_value {
+            Some(value) => value.coerce_to(&data_type)?,
+            None => attr.value.clone().coerce_to(&data_type)?,
+        };
+
+        // A rename overrides both the old and the new name
+        let old_name = attr.name.clone();
+        if let Some(name) = new_name {
+            self.attributes.rename(id, &name)?;
+        }
+        let attr = self
+            .attributes
+            .get_mut(&id)
+            .ok_or_else(|| AttributeError::NotFound(id.to_string()))?;
+        *attr.data_type = data_type;
+        let old_value = std::mem::replace(attr.value, value);
+        let new_name = attr.name.to_string();
+        let new_value = attr.value.clone();
+        self.mark_prefab_override(&old_name);
+        self.mark_prefab_override(&new_name);
+
+        if old_name != new_name {
+            self.events.push(SceneEvent::AttributeDeleted {
+                entity_id: self.id,
+                attribute_id: id,
+                name: old_name,
+            });
+            self.events.push(SceneEvent::AttributeCreated {
+                entity_id: self.id,
+                attribute_id: id,
+                name: new_name,
+            });
+        } else if old_value != new_value {
+            self.events.push(SceneEvent::AttributeChanged {
+                entity_id: self.id,
+                attribute_id: id,
+                name: new_name,
+                old_value,
+                new_value,
+            });
+        }
+        Ok(())
+    }
 
-            if let Some(name) = new_name {
-                attr.name = name;
+    // Set a value (coerced to the attribute's type) without recording a
+    // change event or a prefab override: for simulation output such as the
+    // physics write-back, which changes every frame and isn't an edit
+    pub(super) fn write_attribute_value(
+        &mut self,
+        id: Uuid,
+        value: AttributeValue,
+    ) -> Result<(), AttributeError> {
+        let attr = self
+            .attributes
+            .get_mut(&id)
+            .ok_or_else(|| AttributeError::NotFound(id.to_string()))?;
+        if attr.name == "is_camera" {
+            return Err(AttributeError::Protected("is_camera".to_string()));
+        }
+        *attr.value = value.coerce_to(attr.data_type)?;
+        Ok(())
+    }
+
+    /// Rewrite the EntityRef values in all attributes (see
+    /// `AttributeValue::remap_entity_refs`). Not an instance override.
+    pub fn remap_entity_refs(&mut self, f: &impl Fn(Uuid) -> Option<Uuid>) {
+        for attr in self.attributes.values_mut() {
+            attr.value.remap_entity_refs(f);
+        }
+    }
+
+    fn record_resource(&mut self, added: bool, kind: ResourceKind, path: &std::path::Path) {
+        let entity_id = self.id;
+        let path = path.to_path_buf();
+        self.events.push(if added {
+            SceneEvent::ResourceAdded {
+                entity_id,
+                kind,
+                path,
             }
-            if let Some(data_type) = new_type {
-                attr.data_type = data_type;
+        } else {
+            SceneEvent::ResourceRemoved {
+                entity_id,
+                kind,
+                path,
             }
-            if let Some(value) = new_value {
-                attr.value = value;
+        });
+    }
+
+    // On prefab instances, record that this attribute is now owned by the
+    // instance so prefab syncs leave it alone
+    fn mark_prefab_override(&mut self, name: &str) {
+        if let Some(link) = self.prefab.as_mut() {
+            if !link.overrides.iter().any(|o| o == name) {
+                link.overrides.push(name.to_string());
             }
-            Ok(())
-        } else {
-            Err("Attribute not found".to_string())
         }
     }
 
     pub fn get_attribute_by_name(&self, name: &str) -> Result<&Attribute, String> {
         self.attributes
-            .iter()
-            .find(|(_, attr)| attr.name == name)
-            .map(|(_, attr)| attr)
+            .get_by_name(name)
             .ok_or_else(|| format!("Attribute '{}' not found", name))
     }
 
@@ -340,10 +524,41 @@ impl Entity {
             AttributeType::Boolean,
             AttributeValue::Boolean(physics.can_rotate),
         )?;
+        // Degrees; the physics engine writes the body's rotation back to it
+        entity.create_attribute("rotation", AttributeType::Float, AttributeValue::Float(0.0))?;
 
         Ok(entity)
     }
 
+    /// Set the attribute called `name` to `value`, creating it (or changing
+    /// its type) if needed.
+    pub fn set_attribute(
+        &mut self,
+        name: &str,
+        data_type: AttributeType,
+        value: AttributeValue,
+    ) -> Result<Uuid, String> {
+        match self.get_attribute_by_name(name).map(|attr| attr.id) {
+            Ok(id) => {
+                self.modify_attribute(id, None, Some(data_type), Some(value))?;
+                Ok(id)
+            }
+            Err(_) => Ok(self.create_attribute(name, data_type, value)?),
+        }
+    }
+
+    /// Set the collision layers the entity is in and the ones it collides
+    /// with (bit masks, see `SceneManager::collision_layers`), creating the
+    /// `collision_layers` / `collision_mask` attributes if needed.
+    pub fn set_collision_layers(&mut self, layers: u32, mask: u32) -> Result<(), String> {
+        for (name, bits) in [("collision_layers", layers), ("collision_mask", mask)] {
+            // Stored as the same 32 bits
+            let value = AttributeValue::Integer(bits as i32);
+            self.set_attribute(name, AttributeType::Integer, value)?;
+        }
+        Ok(())
+    }
+
     // Helper methods for position
     pub fn set_x(&mut self, x: f32) -> Result<(), String> {
         if let Ok(attr) = self.get_attribute_by_name("x") {
@@ -0,0 +1,122 @@
+use super::{AttributeValue, Entity, Scene, SceneManager};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Something that happened to a scene's entities. Recorded as it happens
+/// and handed out by `Scene::drain_events` / `SceneManager::drain_events`;
+/// whoever drains them (the editor, or the game runtime feeding physics and
+/// Lua observers) sees each event once.
+///
+/// Only changes made through the `Scene` / `Entity` methods are recorded;
+/// writing to their public fields directly goes unnoticed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneEvent {
+    EntityCreated(Uuid),
+    EntityDeleted(Uuid),
+    AttributeCreated {
+        entity_id: Uuid,
+        attribute_id: Uuid,
+        name: String,
+    },
+    // Only when the value actually changed; a rename is a delete + create
+    AttributeChanged {
+        entity_id: Uuid,
+        attribute_id: Uuid,
+        name: String,
+        old_value: AttributeValue,
+        new_value: AttributeValue,
+    },
+    AttributeDeleted {
+        entity_id: Uuid,
+        attribute_id: Uuid,
+        name: String,
+    },
+    ResourceAdded {
+        entity_id: Uuid,
+        kind: ResourceKind,
+        path: PathBuf,
+    },
+    ResourceRemoved {
+        entity_id: Uuid,
+        kind: ResourceKind,
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    Image,
+    Sound,
+    Script,
+}
+
+impl SceneEvent {
+    /// The entity the event is about.
+    pub fn entity_id(&self) -> Uuid {
+        match self {
+            SceneEvent::EntityCreated(id) | SceneEvent::EntityDeleted(id) => *id,
+            SceneEvent::AttributeCreated { entity_id, .. }
+            | SceneEvent::AttributeChanged { entity_id, .. }
+            | SceneEvent::AttributeDeleted { entity_id, .. }
+            | SceneEvent::ResourceAdded { entity_id, .. }
+            | SceneEvent::ResourceRemoved { entity_id, .. } => *entity_id,
+        }
+    }
+
+    /// Name of the attribute the event is about, if it is about one.
+    pub fn attribute_name(&self) -> Option<&str> {
+        match self {
+            SceneEvent::AttributeCreated { name, .. }
+            | SceneEvent::AttributeChanged { name, .. }
+            | SceneEvent::AttributeDeleted { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+// =============== Draining events ===============
+impl Scene {
+    /// Every event recorded in the scene since the last call: entity
+    /// creation/deletion first, then each entity's own events in scene
+    /// order (each entity's in the order they happened).
+    pub fn drain_events(&mut self) -> Vec<SceneEvent> {
+        let mut events = std::mem::take(&mut self.events);
+        for entity in self.entities.values_mut() {
+            events.append(&mut entity.events);
+        }
+        events
+    }
+
+    // Add an entity, recording its creation (the attributes it was built
+    // with are part of that, not separate events)
+    pub(super) fn insert_entity(&mut self, mut entity: Entity) {
+        entity.events.clear();
+        self.events.push(SceneEvent::EntityCreated(entity.id));
+        self.entities.insert(entity.id, entity);
+    }
+
+    // Remove an entity, keeping the events it recorded before going away
+    pub(super) fn remove_entity(&mut self, id: Uuid) -> bool {
+        let Some(mut entity) = self.entities.shift_remove(&id) else {
+            return false;
+        };
+        self.events.append(&mut entity.events);
+        self.events.push(SceneEvent::EntityDeleted(id));
+        true
+    }
+}
+
+impl SceneManager {
+    /// Events of every scene (see `Scene::drain_events`) followed by those
+    /// of the shared entities not mounted anywhere.
+    pub fn drain_events(&mut self) -> Vec<SceneEvent> {
+        let mut events = Vec::new();
+        for scene in self.scenes.values_mut() {
+            events.extend(scene.drain_events());
+        }
+        for entity in self.shared_entities.values_mut() {
+            events.append(&mut entity.events);
+        }
+        events
+    }
+}
@@ -0,0 +1,257 @@
+use super::{AttributeValue, Entity, Scene};
+use uuid::Uuid;
+
+/// An entity's transform resolved through its parent chain.
+///
+/// Children store `x`/`y`/`z`, `rotation` and `scale` relative to their
+/// parent. The world transform composes them top-down:
+/// - position: the local offset is scaled by the parent's scale, rotated by
+///   the parent's rotation, then added to the parent's position
+/// - rotation: parent + local (degrees, like the `rotation` attribute)
+/// - scale: parent * local, per axis
+/// - z: parent + local, so a group keeps its children's relative ordering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldTransform {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub rotation: f32, // In degrees
+    pub scale: (f32, f32),
+}
+
+impl Default for WorldTransform {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+        }
+    }
+}
+
+impl WorldTransform {
+    /// The entity's own (local) transform, read from its attributes.
+    pub fn local(entity: &Entity) -> Self {
+        let rotation = entity
+            .get_attribute_by_name("rotation")
+            .ok()
+            .and_then(|attr| match attr.value {
+                AttributeValue::Float(r) => Some(r),
+                _ => None,
+            })
+            .unwrap_or(0.0);
+        let scale = entity
+            .get_attribute_by_name("scale")
+            .ok()
+            .and_then(|attr| match attr.value {
+                AttributeValue::Vector2(sx, sy) => Some((sx, sy)),
+                _ => None,
+            })
+            .unwrap_or((1.0, 1.0));
+
+        Self {
+            x: entity.get_x(),
+            y: entity.get_y(),
+            z: entity.get_z(),
+            rotation,
+            scale,
+        }
+    }
+
+    /// Map a point from this transform's local space into world space.
+    /// Rotation uses the same screen-space convention as the renderer.
+    pub fn transform_point(&self, local: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let sx = local.0 * self.scale.0;
+        let sy = local.1 * self.scale.1;
+        (self.x + sx * cos - sy * sin, self.y + sx * sin + sy * cos)
+    }
+
+    /// Inverse of `transform_point`. A zero scale axis maps to 0.
+    pub fn inverse_transform_point(&self, world: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let dx = world.0 - self.x;
+        let dy = world.1 - self.y;
+        let rx = dx * cos + dy * sin;
+        let ry = -dx * sin + dy * cos;
+        let unscale = |v: f32, s: f32| if s == 0.0 { 0.0 } else { v / s };
+        (unscale(rx, self.scale.0), unscale(ry, self.scale.1))
+    }
+
+    /// Compose a child's local transform onto this (parent world) transform.
+    fn then(&self, local: &WorldTransform) -> WorldTransform {
+        let (x, y) = self.transform_point((local.x, local.y));
+        WorldTransform {
+            x,
+            y,
+            z: self.z + local.z,
+            rotation: self.rotation + local.rotation,
+            scale: (self.scale.0 * local.scale.0, self.scale.1 * local.scale.1),
+        }
+    }
+}
+
+// =============== Scene hierarchy (parent/child links) ===============
+impl Scene {
+    pub fn get_parent(&self, id: Uuid) -> Option<Uuid> {
+        self.entities.get(&id).and_then(|entity| entity.parent)
+    }
+
+    /// Direct children of an entity, in scene order.
+    pub fn get_children(&self, id: Uuid) -> Vec<Uuid> {
+        self.entities
+            .iter()
+            .filter(|(_, entity)| entity.parent == Some(id))
+            .map(|(child_id, _)| *child_id)
+            .collect()
+    }
+
+    /// All descendants of an entity (children, grandchildren, ...),
+    /// depth-first, not including the entity itself.
+    pub fn get_descendants(&self, id: Uuid) -> Vec<Uuid> {
+        let mut descendants = Vec::new();
+        let mut stack = self.get_children(id);
+        while let Some(child_id) = stack.pop() {
+            if descendants.contains(&child_id) {
+                continue; // corrupted (cyclic) data - don't loop forever
+            }
+            descendants.push(child_id);
+            stack.extend(self.get_children(child_id));
+        }
+        descendants
+    }
+
+    /// Entities without a parent, in scene order.
+    pub fn get_root_entities(&self) -> Vec<Uuid> {
+        self.entities
+            .iter()
+            .filter(|(_, entity)| entity.parent.is_none())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Whether `ancestor` appears anywhere in `id`'s parent chain.
+    pub fn is_ancestor(&self, ancestor: Uuid, id: Uuid) -> bool {
+        let mut current = self.get_parent(id);
+        let mut hops = 0;
+        while let Some(parent_id) = current {
+            if parent_id == ancestor {
+                return true;
+            }
+            hops += 1;
+            if hops > self.entities.len() {
+                return false;
+            }
+            current = self.get_parent(parent_id);
+        }
+        false
+    }
+
+    /// Whether an entity takes part in the game: it and every ancestor are
+    /// enabled. False for unknown ids.
+    pub fn is_active(&self, id: Uuid) -> bool {
+        let mut current = Some(id);
+        let mut hops = 0;
+        while let Some(entity_id) = current {
+            let Some(entity) = self.entities.get(&entity_id) else {
+                // A dangling parent is treated as a root
+                return entity_id != id;
+            };
+            if !entity.enabled {
+                return false;
+            }
+            hops += 1;
+            if hops > self.entities.len() {
+                return true; // corrupted (cyclic) data - don't loop forever
+            }
+            current = entity.parent;
+        }
+        true
+    }
+
+    /// Attach `child` to `parent` (or detach it with `None`).
+    ///
+    /// The child keeps its world position: its x/y/z are rewritten relative
+    /// to the new parent. Rotation and scale stay as authored (local).
+    /// Refuses self-parenting, parents outside the scene and cycles.
+    pub fn set_parent(&mut self, child: Uuid, parent: Option<Uuid>) -> Result<(), String> {
+        if !self.entities.contains_key(&child) {
+            return Err(format!("Entity {} not found", child));
+        }
+        if let Some(parent_id) = parent {
+            if parent_id == child {
+                return Err("An entity cannot be its own parent".to_string());
+            }
+            if !self.entities.contains_key(&parent_id) {
+                return Err(format!("Parent entity {} not found", parent_id));
+            }
+            if self.is_ancestor(child, parent_id) {
+                return Err("Cannot parent an entity to one of its descendants".to_string());
+            }
+        }
+
+        let world = self.world_transform(child)?;
+        let parent_world = match parent {
+            Some(parent_id) => self.world_transform(parent_id)?,
+            None => WorldTransform::default(),
+        };
+        let (x, y) = parent_world.inverse_transform_point((world.x, world.y));
+        let z = world.z - parent_world.z;
+
+        let entity = self.get_entity_mut(child)?;
+        entity.parent = parent;
+        entity.set_position(x, y, z)
+    }
+
+    /// Resolve an entity's transform through its parent chain.
+    pub fn world_transform(&self, id: Uuid) -> Result<WorldTransform, String> {
+        // Collect the chain leaf -> root, then compose root -> leaf
+        let mut chain = vec![self.get_entity(id)?];
+        let mut current = chain[0].parent;
+        while let Some(parent_id) = current {
+            // A dangling parent is treated as a root
+            let Some(parent) = self.entities.get(&parent_id) else {
+                break;
+            };
+            if chain.len() > self.entities.len() {
+                return Err(format!("Entity {} has a cyclic parent chain", id));
+            }
+            chain.push(parent);
+            current = parent.parent;
+        }
+
+        Ok(chain
+            .iter()
+            .rev()
+            .fold(WorldTransform::default(), |world, entity| {
+                world.then(&WorldTransform::local(entity))
+            }))
+    }
+
+    /// World-space (x, y, z) of an entity.
+    pub fn world_position(&self, id: Uuid) -> Result<(f32, f32, f32), String> {
+        let world = self.world_transform(id)?;
+        Ok((world.x, world.y, world.z))
+    }
+
+    /// Convert a world-space point into the local space of `id`'s parent,
+    /// i.e. the x/y values that would place `id` at that point.
+    pub fn world_to_local(&self, id: Uuid, world: (f32, f32)) -> Result<(f32, f32), String> {
+        match self.get_entity(id)?.parent {
+            Some(parent_id) if self.entities.contains_key(&parent_id) => Ok(self
+                .world_transform(parent_id)?
+                .inverse_transform_point(world)),
+            _ => Ok(world),
+        }
+    }
+
+    /// Move an entity so that it ends up at a world-space x/y.
+    pub fn set_world_position(&mut self, id: Uuid, x: f32, y: f32) -> Result<(), String> {
+        let (local_x, local_y) = self.world_to_local(id, (x, y))?;
+        let entity = self.get_entity_mut(id)?;
+        entity.set_x(local_x)?;
+        entity.set_y(local_y)
+    }
+}
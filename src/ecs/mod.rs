@@ -6,18 +6,32 @@
 //!      Scene
 //!      └── Manages Entities directly
 //!          Entity
-//!          └── Manages its own Attributes + resource paths
+//!          └── Manages its own Attributes + resource paths + tags
+//!              (+ optional parent, see `hierarchy`)
 //! ```
 //!
+//! Changes made through `Scene`/`Entity` methods are recorded as
+//! `SceneEvent`s (see `Scene::drain_events`).
+//!
 //! Split across submodules purely for readability; everything is re-exported
 //! here so external code keeps using `crate::ecs::*` paths.
 
 mod attribute;
 mod entity;
+mod events;
+mod hierarchy;
+mod prefab;
+mod query;
 mod scene;
 mod scene_manager;
 
-pub use attribute::{Attribute, AttributeType, AttributeValue};
+pub use attribute::{
+    Attribute, AttributeError, AttributeMap, AttributeMut, AttributeType, AttributeValue,
+};
 pub use entity::{Entity, PhysicsProperties};
+pub use events::{ResourceKind, SceneEvent};
+pub use hierarchy::WorldTransform;
+pub use prefab::{Prefab, PrefabLink};
+pub use query::EntityQuery;
 pub use scene::Scene;
 pub use scene_manager::SceneManager;
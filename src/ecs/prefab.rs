@@ -0,0 +1,367 @@
+use super::entity::TAGS_OVERRIDE;
+use super::{Entity, Scene};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Link from a scene entity back to the prefab it was instantiated from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrefabLink {
+    pub source: PathBuf,   // Prefab file, relative to the project root
+    pub template_id: Uuid, // The prefab entity this instance was made from
+    // Attribute names owned by this instance (created, edited or deleted
+    // after instantiation); prefab syncs leave them alone
+    #[serde(default)]
+    pub overrides: Vec<String>,
+}
+
+/// A reusable entity template: a root entity plus its descendants.
+///
+/// Stored as JSON under `assets/prefabs/` (see `ProjectManager::save_prefab`).
+/// Entity ids inside a prefab are template ids: instances get fresh ids and
+/// remember the template id in their `PrefabLink`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    // Root first, then descendants (parents always before their children);
+    // `parent` refers to template ids inside this list
+    pub entities: Vec<Entity>,
+}
+
+impl Prefab {
+    /// Capture an entity and its descendants as a prefab.
+    pub fn from_scene(scene: &Scene, root: Uuid) -> Result<Self, String> {
+        let ids = subtree(scene, root);
+
+        // Capturing an instance of a prefab keeps that prefab's template ids,
+        // so saving it back updates the prefab instead of replacing it
+        let root_source = scene
+            .get_entity(root)?
+            .prefab
+            .as_ref()
+            .map(|link| link.source.clone());
+        let template_id = |entity: &Entity| match &entity.prefab {
+            Some(link) if Some(&link.source) == root_source.as_ref() => link.template_id,
+            _ => entity.id,
+        };
+        let mut id_map: HashMap<Uuid, Uuid> = HashMap::new();
+        for id in &ids {
+            id_map.insert(*id, template_id(scene.get_entity(*id)?));
+        }
+
+        let mut entities = Vec::with_capacity(ids.len());
+        for id in ids {
+            let mut entity = scene.get_entity(id)?.clone();
+            entity.id = id_map[&id];
+            entity.parent = if id == root {
+                None
+            } else {
+                entity.parent.map(|parent| id_map[&parent])
+            };
+            // Nested prefabs aren't supported: the template owns everything
+            entity.prefab = None;
+            // References inside the subtree point at template ids
+            entity.remap_entity_refs(&|target| Some(*id_map.get(&target).unwrap_or(&target)));
+            entities.push(entity);
+        }
+
+        Ok(Self {
+            name: entities[0].name.clone(),
+            entities,
+        })
+    }
+
+    /// A single-entity prefab.
+    pub fn from_entity(entity: &Entity) -> Self {
+        let mut entity = entity.clone();
+        entity.parent = None;
+        entity.prefab = None;
+        Self {
+            name: entity.name.clone(),
+            entities: vec![entity],
+        }
+    }
+
+    pub fn root(&self) -> Result<&Entity, String> {
+        self.entities
+            .first()
+            .ok_or_else(|| format!("Prefab '{}' has no entities", self.name))
+    }
+
+    fn template(&self, template_id: Uuid) -> Option<&Entity> {
+        self.entities.iter().find(|e| e.id == template_id)
+    }
+}
+
+// `root` and its descendants, breadth-first so parents come before children
+fn subtree(scene: &Scene, root: Uuid) -> Vec<Uuid> {
+    let mut ids = vec![root];
+    let mut index = 0;
+    while index < ids.len() {
+        ids.extend(scene.get_children(ids[index]));
+        index += 1;
+    }
+    ids
+}
+
+// Fresh copy of a template entity for a scene (new entity/attribute ids)
+fn instance_of(template: &Entity, source: Option<&Path>) -> Entity {
+    let mut entity = template.clone();
+    entity.id = Uuid::new_v4();
+    entity.parent = None;
+    entity.attributes = template
+        .attributes
+        .values()
+        .map(|attr| {
+            let mut attr = attr.clone();
+            attr.id = Uuid::new_v4();
+            (attr.id, attr)
+        })
+        .collect();
+    entity.prefab = source.map(|source| PrefabLink {
+        source: source.to_path_buf(),
+        template_id: template.id,
+        overrides: Vec::new(),
+    });
+    entity
+}
+
+// =============== Scene prefab instances ===============
+impl Scene {
+    /// Spawn a prefab into the scene with its root at world `position`.
+    /// Returns the new root entity's id.
+    ///
+    /// `source` is the prefab's path relative to the project root; it's what
+    /// links instances back to the prefab for `sync_prefab_instances`. Pass
+    /// `None` for templates that aren't files (built-in prefabs): the result
+    /// is plain, unlinked entities.
+    pub fn instantiate_prefab(
+        &mut self,
+        prefab: &Prefab,
+        source: Option<&Path>,
+        position: (f32, f32),
+    ) -> Result<Uuid, String> {
+        let root_template = prefab.root()?;
+
+        let mut template_to_instance: HashMap<Uuid, Uuid> = HashMap::new();
+        for template in &prefab.entities {
+            let mut entity = instance_of(template, source);
+            if template.id != root_template.id {
+                entity.parent = match template.parent.and_then(|p| template_to_instance.get(&p)) {
+                    Some(parent) => Some(*parent),
+                    None => {
+                        return Err(format!(
+                            "Prefab '{}' entity '{}' has no parent inside the prefab",
+                            prefab.name, template.name
+                        ))
+                    }
+                };
+            }
+            template_to_instance.insert(template.id, entity.id);
+            self.insert_entity(entity);
+        }
+        self.remap_template_refs(
+            template_to_instance.values().copied(),
+            &template_to_instance,
+        );
+
+        // The root's placement belongs to the instance (marked as override)
+        let root_id = template_to_instance[&root_template.id];
+        let root = self.get_entity_mut(root_id)?;
+        let z = root.get_z();
+        root.set_position(position.0, position.1, z)?;
+        Ok(root_id)
+    }
+
+    /// Entities in this scene instantiated from the prefab at `source`.
+    pub fn prefab_instances(&self, source: &Path) -> Vec<Uuid> {
+        self.entities
+            .iter()
+            .filter(|(_, e)| e.prefab.as_ref().is_some_and(|link| link.source == source))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Bring every instance of the prefab at `source` up to date with
+    /// `prefab`, keeping per-instance overrides:
+    /// - attributes not overridden take the prefab's type and value (and
+    ///   are added/removed as the prefab gained/lost them)
+    /// - images, sounds and script follow the prefab; so do tags unless
+    ///   the instance's tags were edited
+    /// - entities removed from the prefab are deleted, new ones are spawned
+    ///
+    /// Returns the number of instance entities touched.
+    pub fn sync_prefab_instances(&mut self, prefab: &Prefab, source: &Path) -> usize {
+        // Instances whose template no longer exists go away (with children)
+        for id in self.prefab_instances(source) {
+            let removed = self
+                .entities
+                .get(&id)
+                .and_then(|e| e.prefab.as_ref())
+                .is_some_and(|link| prefab.template(link.template_id).is_none());
+            if removed && self.entities.contains_key(&id) {
+                let _ = self.delete_entity(id);
+            }
+        }
+
+        let mut touched = 0;
+        for id in self.prefab_instances(source) {
+            let Some(entity) = self.entities.get_mut(&id) else {
+                continue;
+            };
+            let Some(link) = entity.prefab.clone() else {
+                continue;
+            };
+            let Some(template) = prefab.template(link.template_id) else {
+                continue;
+            };
+            let owned = |name: &str| link.overrides.iter().any(|o| o == name);
+
+            // Attributes dropped from the prefab
+            entity.attributes.retain(|attr| {
+                owned(&attr.name) || template.get_attribute_by_name(&attr.name).is_ok()
+            });
+            // Attributes changed in (or added to) the prefab. Written
+            // directly so they don't count as instance overrides.
+            for template_attr in template.attributes.values() {
+                if owned(&template_attr.name) {
+                    continue;
+                }
+                let existing = entity
+                    .attributes
+                    .get_by_name(&template_attr.name)
+                    .map(|attr| attr.id);
+                match existing.and_then(|id| entity.attributes.get_mut(&id)) {
+                    Some(attr) => {
+                        *attr.data_type = template_attr.data_type.clone();
+                        *attr.value = template_attr.value.clone();
+                    }
+                    None => {
+                        let mut attr = template_attr.clone();
+                        attr.id = Uuid::new_v4();
+                        entity.attributes.insert(attr);
+                    }
+                }
+            }
+            entity.images = template.images.clone();
+            entity.sounds = template.sounds.clone();
+            entity.script = template.script.clone();
+            if !owned(TAGS_OVERRIDE) {
+                entity.tags = template.tags.clone();
+            }
+            touched += 1;
+        }
+
+        // Entities added to the prefab: spawn them under each instance root
+        let Ok(root_template) = prefab.root() else {
+            return touched;
+        };
+        let roots: Vec<Uuid> = self
+            .prefab_instances(source)
+            .into_iter()
+            .filter(|id| {
+                self.entities[id]
+                    .prefab
+                    .as_ref()
+                    .is_some_and(|link| link.template_id == root_template.id)
+            })
+            .collect();
+        for root_id in roots {
+            let mut template_to_instance = self.instance_ids(root_id, source);
+            for template in &prefab.entities {
+                if template_to_instance.contains_key(&template.id) {
+                    continue;
+                }
+                let Some(parent) = template.parent.and_then(|p| template_to_instance.get(&p))
+                else {
+                    continue;
+                };
+                let mut entity = instance_of(template, Some(source));
+                entity.parent = Some(*parent);
+                template_to_instance.insert(template.id, entity.id);
+                self.insert_entity(entity);
+                touched += 1;
+            }
+
+            // Synced values carry template ids; point them at this instance
+            let ids: Vec<Uuid> = template_to_instance.values().copied().collect();
+            self.remap_template_refs(ids.into_iter(), &template_to_instance);
+        }
+
+        touched
+    }
+
+    // Template id -> entity id for the instance rooted at `root_id`
+    fn instance_ids(&self, root_id: Uuid, source: &Path) -> HashMap<Uuid, Uuid> {
+        std::iter::once(root_id)
+            .chain(self.get_descendants(root_id))
+            .filter_map(|id| {
+                let link = self.entities.get(&id)?.prefab.as_ref()?;
+                (link.source == source).then_some((link.template_id, id))
+            })
+            .collect()
+    }
+
+    // Point EntityRefs of `ids` that hold template ids at the instance
+    fn remap_template_refs(
+        &mut self,
+        ids: impl Iterator<Item = Uuid>,
+        template_to_instance: &HashMap<Uuid, Uuid>,
+    ) {
+        let remap = |target: Uuid| Some(*template_to_instance.get(&target).unwrap_or(&target));
+        for id in ids {
+            if let Some(entity) = self.entities.get_mut(&id) {
+                entity.remap_entity_refs(&remap);
+            }
+        }
+    }
+
+    /// Capture `root` and its descendants as a prefab stored at `source`,
+    /// and turn them into instances of it (the root keeps its placement as
+    /// an override).
+    pub fn create_prefab(&mut self, root: Uuid, source: &Path) -> Result<Prefab, String> {
+        let prefab = Prefab::from_scene(self, root)?;
+
+        for (id, template) in subtree(self, root).iter().zip(&prefab.entities) {
+            let entity = self.get_entity_mut(*id)?;
+            let overrides = if *id == root {
+                vec!["x".to_string(), "y".to_string(), "z".to_string()]
+            } else {
+                Vec::new()
+            };
+            entity.prefab = Some(PrefabLink {
+                source: source.to_path_buf(),
+                template_id: template.id,
+                overrides,
+            });
+        }
+        Ok(prefab)
+    }
+
+    /// Detach an instance from its prefab; it and the descendants linked to
+    /// the same prefab become plain entities. Instances of other prefabs
+    /// under it stay linked.
+    pub fn unlink_prefab(&mut self, id: Uuid) -> Result<(), String> {
+        let Some(source) = self
+            .get_entity_mut(id)?
+            .prefab
+            .take()
+            .map(|link| link.source)
+        else {
+            return Ok(());
+        };
+        for descendant in self.get_descendants(id) {
+            if let Some(entity) = self.entities.get_mut(&descendant) {
+                if entity
+                    .prefab
+                    .as_ref()
+                    .is_some_and(|link| link.source == source)
+                {
+                    entity.prefab = None;
+                }
+            }
+        }
+        Ok(())
+    }
+}
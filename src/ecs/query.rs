@@ -0,0 +1,144 @@
+use super::Scene;
+use uuid::Uuid;
+
+/// Filter for `Scene::query`, built up with the `with_*` / `in_region`
+/// methods. Every condition that is set must match.
+#[derive(Clone, Debug, Default)]
+pub struct EntityQuery {
+    pub tags: Vec<String>,            // Entity has all of these tags
+    pub attributes: Vec<String>,      // Entity has all of these attributes
+    pub name_pattern: Option<String>, // `*` / `?` wildcards, case-sensitive
+    // World-space x/y inside (min, max), edges included
+    pub region: Option<((f32, f32), (f32, f32))>,
+}
+
+impl EntityQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn with_attribute(mut self, name: &str) -> Self {
+        self.attributes.push(name.to_string());
+        self
+    }
+
+    pub fn with_name(mut self, pattern: &str) -> Self {
+        self.name_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Corners can be given in any order.
+    pub fn in_region(mut self, corner1: (f32, f32), corner2: (f32, f32)) -> Self {
+        let min = (corner1.0.min(corner2.0), corner1.1.min(corner2.1));
+        let max = (corner1.0.max(corner2.0), corner1.1.max(corner2.1));
+        self.region = Some((min, max));
+        self
+    }
+
+    pub fn matches(&self, scene: &Scene, id: Uuid) -> bool {
+        let Ok(entity) = scene.get_entity(id) else {
+            return false;
+        };
+        if !self.tags.iter().all(|tag| entity.has_tag(tag)) {
+            return false;
+        }
+        if !self
+            .attributes
+            .iter()
+            .all(|name| entity.get_attribute_by_name(name).is_ok())
+        {
+            return false;
+        }
+        if let Some(pattern) = &self.name_pattern {
+            if !wildcard_match(pattern, &entity.name) {
+                return false;
+            }
+        }
+        if let Some((min, max)) = self.region {
+            let Ok((x, y, _)) = scene.world_position(id) else {
+                return false;
+            };
+            if x < min.0 || x > max.0 || y < min.1 || y > max.1 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// `*` matches any run of characters, `?` exactly one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, star_t)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star + 1;
+            t = star_t + 1;
+            backtrack = Some((star, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// =============== Scene queries ===============
+impl Scene {
+    /// Ids of the scene's entities (shared ones only while mounted) matching `query`,
+    /// in scene order.
+    pub fn query(&self, query: &EntityQuery) -> Vec<Uuid> {
+        self.entities
+            .keys()
+            .filter(|id| query.matches(self, **id))
+            .copied()
+            .collect()
+    }
+
+    pub fn find_entities_with_tag(&self, tag: &str) -> Vec<Uuid> {
+        self.query(&EntityQuery::new().with_tag(tag))
+    }
+
+    /// First entity (in scene order) with exactly this name.
+    pub fn find_entity_by_name(&self, name: &str) -> Option<Uuid> {
+        self.entities
+            .iter()
+            .find(|(_, entity)| entity.name == name)
+            .map(|(id, _)| *id)
+    }
+
+    pub fn find_entities_by_name(&self, pattern: &str) -> Vec<Uuid> {
+        self.query(&EntityQuery::new().with_name(pattern))
+    }
+
+    pub fn find_entities_in_region(&self, corner1: (f32, f32), corner2: (f32, f32)) -> Vec<Uuid> {
+        self.query(&EntityQuery::new().in_region(corner1, corner2))
+    }
+
+    /// Every tag used in the scene, sorted.
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .entities
+            .values()
+            .flat_map(|entity| entity.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
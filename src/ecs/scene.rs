@@ -1,4 +1,4 @@
-use super::{AttributeValue, Entity, PhysicsProperties, SceneManager};
+use super::{AttributeValue, Entity, PhysicsProperties, SceneEvent, SceneManager};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -11,6 +11,9 @@ pub struct Scene {
     pub entities: IndexMap<Uuid, Entity>,
     pub shared_entity_refs: Vec<Uuid>,
     pub default_camera: Option<Uuid>,
+    // Entity creation/deletion not yet drained (see `drain_events`)
+    #[serde(skip)]
+    pub(super) events: Vec<SceneEvent>,
 }
 
 impl Scene {
@@ -21,11 +24,14 @@ impl Scene {
             entities: IndexMap::new(),
             shared_entity_refs: Vec::new(),
             default_camera: None,
+            events: Vec::new(),
         };
 
         // Create default camera
         let camera_id = scene.create_camera("main_camera")?;
         scene.default_camera = Some(camera_id);
+        // A new scene has no history
+        scene.events.clear();
 
         Ok(scene)
     }
@@ -43,15 +49,37 @@ impl Scene {
     pub fn create_entity(&mut self, name: &str) -> Result<Uuid, String> {
         let id = Uuid::new_v4();
         let entity = Entity::new(id, name)?;
-        self.entities.insert(id, entity);
+        self.insert_entity(entity);
         Ok(id)
     }
 
+    /// Delete an entity together with all of its descendants. EntityRef
+    /// attributes pointing at any of them are cleared.
     pub fn delete_entity(&mut self, id: Uuid) -> Result<bool, String> {
         if Some(id) == self.default_camera {
             return Err("Cannot delete default camera".to_string());
         }
-        Ok(self.entities.shift_remove(&id).is_some())
+        let descendants = self.get_descendants(id);
+        if self
+            .default_camera
+            .is_some_and(|camera| descendants.contains(&camera))
+        {
+            return Err("Cannot delete an entity that contains the default camera".to_string());
+        }
+        for descendant in &descendants {
+            self.remove_entity(*descendant);
+        }
+        let removed = self.remove_entity(id);
+        self.clear_entity_refs(&[&descendants[..], &[id]].concat());
+        Ok(removed)
+    }
+
+    // Clear EntityRef attributes that point at `ids`
+    pub(crate) fn clear_entity_refs(&mut self, ids: &[Uuid]) {
+        let dangling = |target: Uuid| (!ids.contains(&target)).then_some(target);
+        for entity in self.entities.values_mut() {
+            entity.remap_entity_refs(&dangling);
+        }
     }
 
     pub fn list_entity(&self) -> Vec<(Uuid, &str)> {
@@ -137,7 +165,7 @@ impl Scene {
     pub fn create_camera(&mut self, name: &str) -> Result<Uuid, String> {
         let id = Uuid::new_v4();
         let camera = Entity::new_camera(id, name)?;
-        self.entities.insert(id, camera);
+        self.insert_entity(camera);
         Ok(id)
     }
 
@@ -150,22 +178,35 @@ impl Scene {
     ) -> Result<Uuid, String> {
         let id = Uuid::new_v4();
         let entity = Entity::new_physical(id, name, position, physics)?;
-        self.entities.insert(id, entity);
+        self.insert_entity(entity);
         Ok(id)
     }
 
+    /// Write back a batch of simulated values (e.g. from
+    /// `PhysicsEngine::step`). These aren't edits, so no change events are
+    /// recorded. An update that fails doesn't stop the rest; the error lists
+    /// every failure.
     pub fn update_entity_attributes(
         &mut self,
         updates: Vec<(Uuid, Uuid, AttributeValue)>,
     ) -> Result<(), String> {
+        let mut errors = Vec::new();
         for (entity_id, attr_id, new_value) in updates {
-            if let Some(entity) = self.entities.get_mut(&entity_id) {
-                entity.modify_attribute(attr_id, None, None, Some(new_value.clone()))?;
-            } else {
-                return Err(format!("Entity {} not found", entity_id));
+            let result = match self.entities.get_mut(&entity_id) {
+                Some(entity) => entity
+                    .write_attribute_value(attr_id, new_value)
+                    .map_err(String::from),
+                None => Err("not found".to_string()),
+            };
+            if let Err(e) = result {
+                errors.push(format!("Entity {}: {}", entity_id, e));
             }
         }
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
     }
 
     pub fn update_entity_attribute(
@@ -1,4 +1,4 @@
-use super::{Entity, Scene};
+use super::{AttributeValue, Entity, Scene};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -9,6 +9,17 @@ pub struct SceneManager {
     pub scenes: IndexMap<Uuid, Scene>,
     pub shared_entities: IndexMap<Uuid, Entity>,
     pub active_scene: Option<Uuid>, // Track currently active scene
+    // Scenes loaded on top of the active one, bottom to top
+    #[serde(default)]
+    pub additive_scenes: Vec<Uuid>,
+    // Names of the project's collision layers: layer n is bit n of the
+    // `collision_layers` / `collision_mask` entity attributes (at most 32)
+    #[serde(default)]
+    pub collision_layers: Vec<String>,
+    // Scenes that exist in the project but haven't been read from disk yet
+    // (id -> name); `project_manager` fills this in and reads them on demand
+    #[serde(skip)]
+    pub deferred_scenes: IndexMap<Uuid, String>,
 }
 
 impl Default for SceneManager {
@@ -23,9 +34,24 @@ impl SceneManager {
             scenes: IndexMap::new(),
             shared_entities: IndexMap::new(),
             active_scene: None,
+            additive_scenes: Vec::new(),
+            collision_layers: Vec::new(),
+            deferred_scenes: IndexMap::new(),
         }
     }
 
+    /// Bit mask of the named collision layers.
+    pub fn collision_layer_mask(&self, names: &[&str]) -> Result<u32, String> {
+        names.iter().try_fold(0, |mask, name| {
+            let index = self
+                .collision_layers
+                .iter()
+                .position(|layer| layer == name)
+                .ok_or_else(|| format!("Collision layer '{}' not found", name))?;
+            Ok(mask | 1 << index)
+        })
+    }
+
     pub fn create_scene(&mut self, name: &str) -> Result<Uuid, String> {
         let id = Uuid::new_v4();
         let scene = Scene::new(name)?;
@@ -37,7 +63,27 @@ impl SceneManager {
         if self.active_scene == Some(id) {
             return Err("Cannot delete active scene".to_string());
         }
-        Ok(self.scenes.shift_remove(&id).is_some())
+        self.additive_scenes.retain(|loaded| *loaded != id);
+        let deferred = self.deferred_scenes.shift_remove(&id).is_some();
+        Ok(self.scenes.shift_remove(&id).is_some() || deferred)
+    }
+
+    /// True if the scene exists, whether or not it has been read yet.
+    pub fn has_scene(&self, id: Uuid) -> bool {
+        self.scenes.contains_key(&id) || self.deferred_scenes.contains_key(&id)
+    }
+
+    pub fn is_scene_deferred(&self, id: Uuid) -> bool {
+        self.deferred_scenes.contains_key(&id)
+    }
+
+    /// Hand over the contents of a deferred scene once they have been read.
+    pub fn insert_deferred_scene(&mut self, id: Uuid, scene: Scene) -> Result<(), String> {
+        if self.deferred_scenes.shift_remove(&id).is_none() {
+            return Err(format!("Scene {} is not waiting to be read", id));
+        }
+        self.scenes.insert(id, scene);
+        Ok(())
     }
 
     pub fn list_scene(&self) -> Vec<(Uuid, &str)> {
@@ -62,6 +108,21 @@ impl SceneManager {
             .map(|(_, scene)| scene)
     }
 
+    /// Id of the first scene named `name` (scene ids are the map keys, which
+    /// may differ from `Scene::id`). Deferred scenes count too.
+    pub fn get_scene_id_by_name(&self, name: &str) -> Option<Uuid> {
+        self.scenes
+            .iter()
+            .map(|(id, scene)| (id, scene.name.as_str()))
+            .chain(
+                self.deferred_scenes
+                    .iter()
+                    .map(|(id, deferred)| (id, deferred.as_str())),
+            )
+            .find(|(_, scene_name)| *scene_name == name)
+            .map(|(id, _)| *id)
+    }
+
     pub fn create_shared_entity(&mut self, name: &str) -> Result<Uuid, String> {
         let id = Uuid::new_v4();
         let entity = Entity::new(id, name)?;
@@ -70,12 +131,25 @@ impl SceneManager {
     }
 
     pub fn delete_shared_entity(&mut self, id: Uuid) -> Result<bool, String> {
+        // A scene not read yet may reference it
+        if !self.deferred_scenes.is_empty() {
+            return Err("Read every scene before deleting a shared entity".to_string());
+        }
         for scene in self.scenes.values() {
             if scene.shared_entity_refs.contains(&id) {
                 return Err("Entity is still referenced by a scene".to_string());
             }
         }
-        Ok(self.shared_entities.shift_remove(&id).is_some())
+        let removed = self.shared_entities.shift_remove(&id).is_some();
+        // Clear EntityRef attributes that pointed at it
+        let dangling = |target: Uuid| (target != id).then_some(target);
+        for scene in self.scenes.values_mut() {
+            scene.clear_entity_refs(&[id]);
+        }
+        for entity in self.shared_entities.values_mut() {
+            entity.remap_entity_refs(&dangling);
+        }
+        Ok(removed)
     }
 
     pub fn list_shared_entity(&self) -> Vec<(Uuid, &str)> {
@@ -108,9 +182,67 @@ impl SceneManager {
             .collect()
     }
 
+    /// Move the shared entities a scene references into its `entities`, so
+    /// rendering, physics and scripts treat them like the scene's own. The
+    /// game runtime does this for the scene being played and undoes it with
+    /// `unmount_shared_entities` when leaving the scene; state reached while
+    /// mounted travels with the entity.
+    ///
+    /// A shared entity has one live copy: it leaves `shared_entities` while
+    /// mounted, and a scene loaded while another loaded scene already holds
+    /// it shares that copy (see `find_loaded_entity`) instead of getting a
+    /// second one. Returns the ids that were mounted.
+    pub fn mount_shared_entities(&mut self, scene_id: Uuid) -> Result<Vec<Uuid>, String> {
+        let scene = self
+            .scenes
+            .get_mut(&scene_id)
+            .ok_or_else(|| format!("Scene {} not found", scene_id))?;
+        let mut mounted = Vec::new();
+        for id in &scene.shared_entity_refs {
+            if let Some(entity) = self.shared_entities.shift_remove(id) {
+                scene.entities.insert(*id, entity);
+                mounted.push(*id);
+            }
+        }
+        Ok(mounted)
+    }
+
+    /// Move a scene's mounted shared entities back into `shared_entities`,
+    /// or over to another loaded scene that references them, so they stay in
+    /// play there. A shared entity deleted while mounted stays deleted.
+    /// Returns the ids that went back to `shared_entities`.
+    pub fn unmount_shared_entities(&mut self, scene_id: Uuid) -> Result<Vec<Uuid>, String> {
+        let refs = self
+            .scenes
+            .get(&scene_id)
+            .ok_or_else(|| format!("Scene {} not found", scene_id))?
+            .shared_entity_refs
+            .clone();
+        let mut unmounted = Vec::new();
+        for id in refs {
+            let Some(entity) = self.scenes[&scene_id].entities.shift_remove(&id) else {
+                continue;
+            };
+            let heir = self.loaded_scenes().into_iter().find(|loaded| {
+                *loaded != scene_id && self.scenes[loaded].shared_entity_refs.contains(&id)
+            });
+            match heir {
+                Some(heir) => {
+                    self.scenes[&heir].entities.insert(id, entity);
+                }
+                None => {
+                    self.shared_entities.insert(id, entity);
+                    unmounted.push(id);
+                }
+            }
+        }
+        Ok(unmounted)
+    }
+
     // Add these methods for active scene management
     pub fn set_active_scene(&mut self, id: Uuid) -> Result<(), String> {
         if self.scenes.contains_key(&id) {
+            self.additive_scenes.retain(|loaded| *loaded != id);
             self.active_scene = Some(id);
             Ok(())
         } else {
@@ -129,4 +261,102 @@ impl SceneManager {
     pub fn clear_active_scene(&mut self) {
         self.active_scene = None;
     }
+
+    // =============== Additive scenes ===============
+
+    /// Load a scene on top of the active one (and of earlier additive
+    /// scenes), e.g. a HUD or game-manager scene that stays loaded while the
+    /// active scene changes underneath it.
+    pub fn load_scene_additive(&mut self, id: Uuid) -> Result<(), String> {
+        if !self.scenes.contains_key(&id) {
+            return Err("Scene not found".to_string());
+        }
+        if self.is_scene_loaded(id) {
+            return Err(format!("Scene {} is already loaded", id));
+        }
+        self.additive_scenes.push(id);
+        Ok(())
+    }
+
+    /// Unload an additively loaded scene. The active scene can only be
+    /// replaced, not unloaded.
+    pub fn unload_scene(&mut self, id: Uuid) -> Result<(), String> {
+        let Some(index) = self.additive_scenes.iter().position(|loaded| *loaded == id) else {
+            return Err(format!("Scene {} is not loaded additively", id));
+        };
+        self.additive_scenes.remove(index);
+        Ok(())
+    }
+
+    pub fn is_scene_loaded(&self, id: Uuid) -> bool {
+        self.active_scene == Some(id) || self.additive_scenes.contains(&id)
+    }
+
+    /// Every loaded scene in update and render order: the active scene
+    /// first, then additive scenes in the order they were loaded (later
+    /// scenes draw on top).
+    pub fn loaded_scenes(&self) -> Vec<Uuid> {
+        let additive = self
+            .additive_scenes
+            .iter()
+            .filter(|id| Some(**id) != self.active_scene);
+        self.active_scene
+            .iter()
+            .chain(additive)
+            .filter(|id| self.scenes.contains_key(*id))
+            .copied()
+            .collect()
+    }
+
+    /// The loaded scene an entity belongs to (shared entities count while
+    /// mounted).
+    pub fn find_loaded_entity(&self, entity_id: Uuid) -> Option<(Uuid, &Entity)> {
+        self.loaded_scenes().into_iter().find_map(|scene_id| {
+            let entity = self.scenes.get(&scene_id)?.entities.get(&entity_id)?;
+            Some((scene_id, entity))
+        })
+    }
+
+    pub fn find_loaded_entity_mut(&mut self, entity_id: Uuid) -> Option<(Uuid, &mut Entity)> {
+        let (scene_id, _) = self.find_loaded_entity(entity_id)?;
+        let entity = self
+            .scenes
+            .get_mut(&scene_id)?
+            .entities
+            .get_mut(&entity_id)?;
+        Some((scene_id, entity))
+    }
+
+    /// Write back simulated values (e.g. from `PhysicsEngine::step_scenes`)
+    /// to whichever loaded scene holds each entity, like
+    /// `Scene::update_entity_attributes`: no change events, and a failed
+    /// update doesn't stop the rest.
+    pub fn update_loaded_entity_attributes(
+        &mut self,
+        updates: Vec<(Uuid, Uuid, AttributeValue)>,
+    ) -> Result<(), String> {
+        let loaded = self.loaded_scenes();
+        let mut errors = Vec::new();
+        for (entity_id, attribute_id, value) in updates {
+            let holder = loaded
+                .iter()
+                .find(|scene_id| self.scenes[*scene_id].entities.contains_key(&entity_id));
+            let Some(entity) = holder.and_then(|scene_id| {
+                self.scenes
+                    .get_mut(scene_id)
+                    .and_then(|scene| scene.entities.get_mut(&entity_id))
+            }) else {
+                errors.push(format!("Entity {} is not in a loaded scene", entity_id));
+                continue;
+            };
+            if let Err(e) = entity.write_attribute_value(attribute_id, value) {
+                errors.push(format!("Entity {}: {}", entity_id, e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
 }
@@ -17,6 +17,7 @@ use std::fs;
 use std::path::PathBuf;
 
 pub mod script_editor;
+mod shape_editor;
 
 pub struct EngineGui {
     // Window States
@@ -53,6 +54,8 @@ pub struct EngineGui {
     allow_close: bool,
     // Entity being moved in the viewport: (entity id, world-space grab offset)
     viewport_drag: Option<(uuid::Uuid, (f32, f32))>,
+    // Collider point being moved in the viewport (see shape_editor.rs)
+    viewport_point_drag: Option<usize>,
 }
 
 impl EngineGui {
@@ -101,6 +104,7 @@ impl EngineGui {
             show_api_palette: false,
             allow_close: false,
             viewport_drag: None,
+            viewport_point_drag: None,
         }
     }
 
@@ -125,6 +129,12 @@ impl EngineGui {
         let spacing = 4.0;
         let min_side_panel_width = 200.0;
 
+        // Entity changes since the last frame (viewport drags, undo, ...)
+        if let Some(scene_manager) = &mut self.gui_state.scene_manager {
+            let events = scene_manager.drain_events();
+            self.inspector.handle_scene_events(&events);
+        }
+
         // Scene undo/redo shortcuts, only when no text field is focused
         // (focused TextEdits have their own Ctrl+Z handling)
         if ctx.memory(|m| m.focused().is_none()) {
@@ -786,6 +796,9 @@ impl EngineGui {
             ui.make_persistent_id("scene_viewport_interaction"),
             egui::Sense::click_and_drag(),
         );
+        if self.edit_collider_points(ui, &response, content_rect) {
+            return;
+        }
 
         let alt_held = ui.ctx().input(|i| i.modifiers.alt);
         let zoom = self.render_engine.camera.zoom.max(0.0001);
@@ -837,8 +850,10 @@ impl EngineGui {
 
                     if let Some(scene_manager) = &self.gui_state.scene_manager {
                         if let Some(scene) = scene_manager.get_scene(active_scene_id) {
-                            if let Ok(entity) = scene.get_entity(entity_id) {
-                                let offset = (world_x - entity.get_x(), world_y - entity.get_y());
+                            // Grab offset is taken against the world position
+                            // so dragging a child doesn't jump by its parent's offset
+                            if let Ok((entity_x, entity_y, _)) = scene.world_position(entity_id) {
+                                let offset = (world_x - entity_x, world_y - entity_y);
                                 self.viewport_drag = Some((entity_id, offset));
                                 // Select what we grab
                                 self.gui_state.selected_item =
@@ -871,10 +886,10 @@ impl EngineGui {
 
                 if let Some(scene_manager) = &mut self.gui_state.scene_manager {
                     if let Some(scene) = scene_manager.get_scene_mut(active_scene_id) {
+                        // x/y are written in the parent's local space
+                        let _ = scene.set_world_position(entity_id, new_x, new_y);
                         if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                            let _ = entity.set_x(new_x);
-                            let _ = entity.set_y(new_y);
-                            // Keep the optional position Vector2 in sync
+                            // Keep the optional position Vector2 (world space) in sync
                             if let Ok(pos_attr) = entity.get_attribute_by_name("position") {
                                 let pos_id = pos_attr.id;
                                 let _ = entity.modify_attribute(
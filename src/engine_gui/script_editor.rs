@@ -80,6 +80,61 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
                 "remove_entity_from_physics_engine(entity_id)",
                 "Remove the entity's physics body",
             ),
+            (
+                "create_joint",
+                "create_joint(entity_a, entity_b, \"revolute\", { anchor = {0.0, 0.0} })",
+                "Join two bodies (revolute, prismatic, fixed, rope, spring); returns the joint id",
+            ),
+            (
+                "remove_joint",
+                "remove_joint(joint_id)",
+                "Remove a joint; true if it existed",
+            ),
+            (
+                "set_joint_motor",
+                "set_joint_motor(joint_id, 1.0, 1000.0)",
+                "Drive a revolute / prismatic joint at a velocity, up to a max force",
+            ),
+            (
+                "set_joint_limits",
+                "set_joint_limits(joint_id, -0.5, 0.5)",
+                "Limit a revolute (radians) / prismatic (pixels) joint",
+            ),
+            (
+                "raycast",
+                "local hit = raycast(x, y, 0.0, 1.0, 100.0, { exclude = entity_id })",
+                "First hit along a ray: { entity_id, point, normal, distance } or nil",
+            ),
+            (
+                "cast_shape",
+                "local hit = cast_shape({ radius = 8.0 }, x, y, 1.0, 0.0, 50.0)",
+                "Sweep a circle ({ radius }) or box ({ width, height }) along a direction",
+            ),
+            (
+                "intersect_point",
+                "local ids = intersect_point(x, y)",
+                "Entities whose collider contains a point",
+            ),
+            (
+                "intersect_aabb",
+                "local ids = intersect_aabb(x1, y1, x2, y2)",
+                "Entities whose collider overlaps a rectangle",
+            ),
+            (
+                "set_collision_layers",
+                "set_collision_layers(entity_id, \"bullets\", { \"enemies\", \"walls\" })",
+                "Set the collision layers an entity is in and collides with",
+            ),
+            (
+                "move_character",
+                "move_character(entity_id, dx, dy)",
+                "Move an is_character entity next step, sliding along what's in the way",
+            ),
+            (
+                "is_grounded",
+                "is_grounded(entity_id)",
+                "Whether a character is standing on something",
+            ),
         ],
     ),
     (
@@ -93,13 +148,18 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
             (
                 "remove_entity",
                 "remove_entity(scene_id, entity_id)",
-                "Delete an entity from the scene",
+                "Delete an entity (and its children) from the scene",
             ),
             (
                 "create_physical_entity",
                 "create_physical_entity(scene_id, \"name\", 0.0, 0.0, 0.0)",
                 "Entity with physics attributes at x, y, z",
             ),
+            (
+                "spawn_prefab",
+                "spawn_prefab(scene_id, \"assets/prefabs/name.prefab\", 0.0, 0.0)",
+                "Instantiate a prefab at x, y; returns the root id",
+            ),
             (
                 "get_entity_name",
                 "get_entity_name(scene_id, entity_id)",
@@ -125,6 +185,36 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
                 "list_entities_name_x_y(scene_id)",
                 "Array of {id, name, x, y} for every entity",
             ),
+            (
+                "set_parent",
+                "set_parent(scene_id, entity_id, parent_id)",
+                "Attach to a parent (nil detaches); keeps the world position",
+            ),
+            (
+                "get_parent",
+                "get_parent(scene_id, entity_id)",
+                "Parent id, or nil for root entities",
+            ),
+            (
+                "get_children",
+                "get_children(scene_id, entity_id)",
+                "Array of direct child ids",
+            ),
+            (
+                "get_world_position",
+                "get_world_position(scene_id, entity_id)",
+                "x, y through the parent chain",
+            ),
+            (
+                "set_enabled",
+                "set_enabled(scene_id, entity_id, false)",
+                "Disable (hide, no physics, no scripts) or re-enable; children follow",
+            ),
+            (
+                "is_enabled",
+                "is_enabled(scene_id, entity_id)",
+                "The entity's own enabled flag",
+            ),
         ],
     ),
     (
@@ -133,7 +223,7 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
             (
                 "get_attribute",
                 "get_attribute(scene_id, entity_id, \"name\")",
-                "Read any attribute: number/bool/string/{x, y}, nil if missing",
+                "Read any attribute: number/bool/string/table/entity id, nil if missing",
             ),
             (
                 "set_attribute",
@@ -160,6 +250,61 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
                 "create_attribute_vector2(scene_id, entity_id, \"name\", 0.0, 0.0)",
                 "Add a Vector2 attribute",
             ),
+            (
+                "on_attribute_changed",
+                "on_attribute_changed(entity_id, \"hp\", function(id, name, value, old_value)\n    \nend)",
+                "Call a function whenever the attribute's value changes (once per frame)",
+            ),
+        ],
+    ),
+    (
+        "Tags & queries",
+        &[
+            (
+                "add_tag",
+                "add_tag(scene_id, entity_id, \"enemy\")",
+                "Tag an entity",
+            ),
+            (
+                "remove_tag",
+                "remove_tag(scene_id, entity_id, \"enemy\")",
+                "Untag; false if it wasn't tagged",
+            ),
+            (
+                "has_tag",
+                "has_tag(scene_id, entity_id, \"enemy\")",
+                "True if the entity has the tag",
+            ),
+            (
+                "find_entities_with_tag",
+                "find_entities_with_tag(scene_id, \"enemy\")",
+                "Array of entity ids with the tag",
+            ),
+            (
+                "find_entity_by_name",
+                "find_entity_by_name(scene_id, \"player\")",
+                "Entity id, or nil if there's none",
+            ),
+            (
+                "find_entities",
+                "find_entities(scene_id, { tag = \"enemy\", has = { \"hp\" }, name = \"boss_*\", region = { 0, 0, 800, 600 } })",
+                "Array of ids matching every given filter",
+            ),
+            (
+                "find_entity_in_loaded_scenes",
+                "local scene, entity = find_entity_in_loaded_scenes(\"player\")",
+                "Scene id and entity id of the first match in any loaded scene",
+            ),
+            (
+                "get_entity_scene",
+                "get_entity_scene(entity_id)",
+                "Id of the loaded scene holding the entity, or nil",
+            ),
+            (
+                "get_loaded_scenes",
+                "get_loaded_scenes()",
+                "Array of loaded scene ids, active scene first",
+            ),
         ],
     ),
     (
@@ -191,6 +336,21 @@ const API_GROUPS: &[(&str, &[ApiEntry])] = &[
                 "end_game()",
                 "Game over: freezes on this frame; only Reset exits",
             ),
+            (
+                "load_scene",
+                "load_scene(\"level_2\", \"fade\", 0.5)",
+                "Switch scene (name or id); transition \"cut\", \"fade\" or \"crossfade\"",
+            ),
+            (
+                "load_scene_additive",
+                "load_scene_additive(\"hud\")",
+                "Load a scene on top of the active one (end of frame)",
+            ),
+            (
+                "unload_scene",
+                "unload_scene(\"hud\")",
+                "Unload an additively loaded scene (end of frame)",
+            ),
             (
                 "accumulated_time",
                 "accumulated_time",
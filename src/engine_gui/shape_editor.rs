@@ -0,0 +1,199 @@
+//! Viewport editing of collider points (`collider_points`, the vertices of
+//! polygon, polyline and segment colliders, relative to the entity's x/y).
+//! The selected entity's points are drawn as handles: dragging a handle
+//! moves its point, right-clicking one removes it, and Ctrl+click adds a
+//! point at the cursor.
+
+use super::EngineGui;
+use crate::ecs::{AttributeType, AttributeValue, Entity};
+use crate::gui::gui_state::ScenePanelSelectedItem;
+use crate::gui::scene_hierarchy::utils;
+use crate::logger::LOGGER;
+use crate::physics_engine::{body_point_to_world, world_to_body_point};
+use eframe::egui;
+
+// Screen-space radius of a point handle; clicks a little outside still hit
+const HANDLE_RADIUS: f32 = 5.0;
+const HANDLE_HIT_RADIUS: f32 = 8.0;
+
+// Shapes built from `collider_points`
+const POINT_SHAPES: [&str; 3] = ["polygon", "polyline", "segment"];
+
+impl EngineGui {
+    /// Draw the selected entity's collider points and apply edits to them.
+    /// Returns true if the pointer was used here, so the viewport doesn't
+    /// also select or move entities with it.
+    pub(super) fn edit_collider_points(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        content_rect: egui::Rect,
+    ) -> bool {
+        let ScenePanelSelectedItem::Entity(scene_id, entity_id) =
+            self.gui_state.scene_panel_selected_item
+        else {
+            self.viewport_point_drag = None;
+            return false;
+        };
+        let Some(scene) = self
+            .gui_state
+            .scene_manager
+            .as_mut()
+            .and_then(|manager| manager.get_scene_mut(scene_id))
+        else {
+            return false;
+        };
+        let Ok(world) = scene.world_transform(entity_id) else {
+            return false;
+        };
+        let Ok(entity) = scene.get_entity_mut(entity_id) else {
+            return false;
+        };
+        let Some((points, closed)) = editable_points(entity) else {
+            self.viewport_point_drag = None;
+            return false;
+        };
+
+        // Points are in the body's space: scaled, and turned about the
+        // sprite's center, like the collider made from them
+        let camera = &self.render_engine.camera;
+        let zoom = camera.zoom.max(0.0001);
+        let to_screen = |point: (f32, f32)| {
+            let (screen_x, screen_y) =
+                camera.world_to_screen(body_point_to_world(entity, &world, point));
+            egui::pos2(content_rect.min.x + screen_x, content_rect.min.y + screen_y)
+        };
+        let to_local = |pointer: egui::Pos2| {
+            let world_point = (
+                (pointer.x - content_rect.min.x) / zoom + camera.position.0,
+                (pointer.y - content_rect.min.y) / zoom + camera.position.1,
+            );
+            world_to_body_point(entity, &world, world_point)
+        };
+
+        // Outline and handles
+        let handles: Vec<(usize, egui::Pos2)> = points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| match point {
+                AttributeValue::Vector2(x, y) => Some((index, to_screen((*x, *y)))),
+                _ => None,
+            })
+            .collect();
+        let stroke = egui::Stroke::new(1.5_f32, egui::Color32::from_rgb(0, 200, 255));
+        let mut outline: Vec<egui::Pos2> = handles.iter().map(|(_, pos)| *pos).collect();
+        if closed && outline.len() > 2 {
+            outline.extend(outline.first().copied());
+        }
+        ui.painter().add(egui::Shape::line(outline, stroke));
+        for (index, pos) in &handles {
+            let fill = if self.viewport_point_drag == Some(*index) {
+                egui::Color32::from_rgb(255, 200, 0)
+            } else {
+                egui::Color32::WHITE
+            };
+            ui.painter().circle(*pos, HANDLE_RADIUS, fill, stroke);
+        }
+
+        let handle_at = |pointer: egui::Pos2| {
+            handles
+                .iter()
+                .rev()
+                .find(|(_, pos)| pos.distance(pointer) <= HANDLE_HIT_RADIUS)
+                .map(|(index, _)| *index)
+        };
+        let alt_held = ui.ctx().input(|i| i.modifiers.alt);
+        let ctrl_held = ui.ctx().input(|i| i.modifiers.command);
+        let pointer = response.interact_pointer_pos();
+
+        let mut points = points;
+        let mut changed = false;
+        let mut used = false;
+        // Drags are saved once they end
+        let mut save = false;
+        if response.drag_started_by(egui::PointerButton::Primary) && !alt_held {
+            self.viewport_point_drag = pointer.and_then(handle_at);
+            used = self.viewport_point_drag.is_some();
+        }
+        if let (Some(index), Some(pointer)) = (self.viewport_point_drag, pointer) {
+            if let (true, Some(point)) = (
+                response.dragged_by(egui::PointerButton::Primary),
+                points.get_mut(index),
+            ) {
+                let (x, y) = to_local(pointer);
+                *point = AttributeValue::Vector2(x, y);
+                changed = true;
+                used = true;
+            }
+        }
+        if response.drag_stopped_by(egui::PointerButton::Primary)
+            && self.viewport_point_drag.take().is_some()
+        {
+            used = true;
+            save = true;
+        }
+        if response.secondary_clicked() {
+            if let Some(index) = pointer.and_then(handle_at) {
+                points.remove(index);
+                changed = true;
+                used = true;
+                save = true;
+            }
+        }
+        if response.clicked() && ctrl_held {
+            if let Some(pointer) = pointer {
+                let (x, y) = to_local(pointer);
+                points.push(AttributeValue::Vector2(x, y));
+                changed = true;
+                used = true;
+                save = true;
+            }
+        }
+
+        if changed {
+            if let Err(e) = set_points(entity, points) {
+                LOGGER.error(format!("Failed to edit collider points: {}", e));
+            }
+        }
+        if save {
+            utils::save_project(&mut self.gui_state);
+        }
+        used
+    }
+}
+
+// The entity's collider points and whether they make a closed outline, if
+// it has them or a shape that uses them
+fn editable_points(entity: &Entity) -> Option<(Vec<AttributeValue>, bool)> {
+    let shape = match entity
+        .get_attribute_by_name("collider_shape")
+        .map(|attr| &attr.value)
+    {
+        Ok(AttributeValue::String(shape)) => shape.to_lowercase(),
+        _ => String::new(),
+    };
+    let points = match entity
+        .get_attribute_by_name("collider_points")
+        .map(|attr| &attr.value)
+    {
+        Ok(AttributeValue::List(points)) => points.clone(),
+        Ok(_) => return None,
+        Err(_) if POINT_SHAPES.contains(&shape.as_str()) => Vec::new(),
+        Err(_) => return None,
+    };
+    Some((points, shape == "polygon"))
+}
+
+fn set_points(entity: &mut Entity, points: Vec<AttributeValue>) -> Result<(), String> {
+    let value = AttributeValue::List(points);
+    match entity
+        .get_attribute_by_name("collider_points")
+        .map(|attr| attr.id)
+    {
+        Ok(id) => Ok(entity.modify_attribute(id, None, None, Some(value))?),
+        Err(_) => entity
+            .create_attribute("collider_points", AttributeType::List, value)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    }
+}
@@ -6,12 +6,14 @@ use crate::{
     logger::LOGGER,
     lua_scripting::LuaScripting,
     physics_engine::PhysicsEngine,
-    render_engine::RenderEngine,
+    project_manager::ProjectManager,
+    render_engine::{RenderEngine, RenderQueueEntry},
 };
 use egui::Rect;
 use std::any::Any;
 use std::cell::{Ref, RefCell};
 use std::rc::Rc;
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RuntimeState {
@@ -23,6 +25,41 @@ pub enum RuntimeState {
     Stopped,
 }
 
+/// How the runtime moves to another scene (`GameRuntime::load_scene`, Lua
+/// `load_scene`). Durations are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneTransition {
+    /// Switch at the next frame
+    Cut,
+    /// Fade to black (the old scene frozen) over the first half, switch,
+    /// then fade the new scene in
+    Fade(f32),
+    /// Switch at once; the old scene's last frame fades out on top
+    Crossfade(f32),
+}
+
+/// A scene change asked for by a script; the runtime applies it at the end
+/// of the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneRequest {
+    /// Replace the active scene (`GameRuntime::load_scene`)
+    Load(Uuid, SceneTransition),
+    /// `GameRuntime::load_scene_additive`
+    LoadAdditive(Uuid),
+    /// `GameRuntime::unload_scene`
+    Unload(Uuid),
+}
+
+// A scene change in progress
+struct ActiveTransition {
+    target: Uuid,
+    transition: SceneTransition,
+    elapsed: f32,
+    switched: bool,
+    // Crossfade: the old scene's last render queue
+    old_frame: Vec<RenderQueueEntry>,
+}
+
 pub trait Game: Any {
     fn init(&mut self, scene_manager: &mut SceneManager);
     fn update(&mut self, scene_manager: &mut SceneManager, input: &InputHandler, delta_time: f32);
@@ -47,6 +84,7 @@ pub struct GameRuntime {
     lua_scripting: LuaScripting,
     // Fixed-timestep accumulator: real time not yet consumed by physics steps
     time_accumulator: f32,
+    transition: Option<ActiveTransition>,
 }
 
 impl GameRuntime {
@@ -77,6 +115,7 @@ impl GameRuntime {
             game: None,
             lua_scripting: LuaScripting::new(),
             time_accumulator: 0.0,
+            transition: None,
         }
     }
 
@@ -133,6 +172,7 @@ impl GameRuntime {
     pub fn reset(&mut self) {
         // First stop everything
         self.set_state(RuntimeState::Stopped);
+        self.transition = None;
 
         // Clear all engines
         self.physics_engine.borrow_mut().cleanup();
@@ -167,16 +207,33 @@ impl GameRuntime {
             if manager.get_active_scene().is_none() {
                 manager.set_active_scene(scene_ids[0])?;
             }
+
+            // Changes made in the editor aren't news to the game
+            manager.drain_events();
+
+            // Scenes entered later via load_scene start from this state
+            if self.dev_state_snapshot.is_none() {
+                self.dev_state_snapshot = Some(manager.clone());
+            }
+
+            // Shared entities (persistent player, HUD, ...) play as part of
+            // the loaded scenes referencing them. This only changes the
+            // runtime's copy of the scenes, never the editor's.
+            for scene_id in manager.loaded_scenes() {
+                manager.mount_shared_entities(scene_id)?;
+            }
         }
 
-        // Load the scene into physics engine, replacing any previously
+        // Load the scenes into physics engine, replacing any previously
         // loaded physics world so repeated runs don't leak duplicate bodies
         {
             let mut physics = self.physics_engine.borrow_mut();
             physics.cleanup();
             let manager = self.scene_manager.borrow();
-            if let Some(scene) = manager.get_active_scene() {
-                physics.load_scene(scene);
+            for scene_id in manager.loaded_scenes() {
+                if let Some(scene) = manager.get_scene(scene_id) {
+                    physics.load_scene(scene);
+                }
             }
         }
 
@@ -192,6 +249,7 @@ impl GameRuntime {
 
         LOGGER.info("Game starting with active scene");
         self.time_accumulator = 0.0;
+        self.transition = None;
         self.running = true;
         self.state = RuntimeState::Playing;
         Ok(())
@@ -214,6 +272,13 @@ impl GameRuntime {
             // window drag, breakpoint - doesn't cause a huge catch-up burst)
             let frame_dt = ctx.input(|i| i.stable_dt).min(0.25);
 
+            // A scene change in progress freezes the game while fading out
+            if self.advance_transition(frame_dt) {
+                self.paint_scene(ui, viewport_rect);
+                ctx.request_repaint();
+                return;
+            }
+
             // Update game logic with the input handler
             if let Some(game) = &mut self.game {
                 game.update(
@@ -234,12 +299,13 @@ impl GameRuntime {
                 LOGGER.error(format!("Failed to update Lua keys_pressed: {}", e));
             }
 
-            let active_scene_id = self.scene_manager.borrow().active_scene;
-            if let Some(active_scene_id) = active_scene_id {
-                if let Err(err) = self.lua_scripting.run_scripts_for_scene(active_scene_id) {
+            // Every loaded scene, active scene first
+            let loaded_scenes = self.scene_manager.borrow().loaded_scenes();
+            for scene_id in &loaded_scenes {
+                if let Err(err) = self.lua_scripting.run_scripts_for_scene(*scene_id) {
                     LOGGER.error(format!(
                         "Error running scripts for scene {}: {}",
-                        active_scene_id, err
+                        scene_id, err
                     ));
                 }
             }
@@ -253,6 +319,24 @@ impl GameRuntime {
                 return;
             }
 
+            // Entity changes since the last frame (made by scripts; the
+            // physics write-back records none): physics picks up edited
+            // physics attributes, Lua observers get their callbacks
+            let events = self.scene_manager.borrow_mut().drain_events();
+            if !events.is_empty() {
+                {
+                    let manager = self.scene_manager.borrow();
+                    let scenes: Vec<_> = loaded_scenes
+                        .iter()
+                        .filter_map(|scene_id| manager.get_scene(*scene_id))
+                        .collect();
+                    self.physics_engine
+                        .borrow_mut()
+                        .apply_scene_events(&scenes, &events);
+                }
+                self.lua_scripting.dispatch_scene_events(&events);
+            }
+
             // Run physics on a fixed timestep, decoupled from the display
             // refresh rate: accumulate real time and consume it in fixed
             // steps so simulation speed is identical on 60Hz and 144Hz
@@ -267,28 +351,34 @@ impl GameRuntime {
                 self.time_accumulator -= step_dt;
 
                 let mut manager = self.scene_manager.borrow_mut();
-                match manager.get_active_scene_mut() {
-                    Some(scene) => {
-                        let physics_updates = self.physics_engine.borrow_mut().step(scene);
-
-                        // Filter out those values are NaN
-                        let filtered_physics_updates: Vec<(_, _, AttributeValue)> = physics_updates
-                            .into_iter()
-                            .filter(|(_, _, attr)| match attr {
-                                AttributeValue::Float(val) => !val.is_nan(),
-                                AttributeValue::Vector2(x, y) => !x.is_nan() && !y.is_nan(),
-                                _ => true,
-                            })
-                            .collect();
-
-                        if let Err(err) = scene.update_entity_attributes(filtered_physics_updates) {
-                            LOGGER.error(format!("Failed to update entity attributes: {}", err));
-                        }
-                    }
-                    None => {
-                        scene_lost = true;
-                        break;
-                    }
+                if manager.get_active_scene().is_none() {
+                    scene_lost = true;
+                    break;
+                }
+
+                // All loaded scenes share one physics world
+                let physics_updates = {
+                    let scenes: Vec<_> = manager
+                        .loaded_scenes()
+                        .into_iter()
+                        .filter_map(|scene_id| manager.get_scene(scene_id))
+                        .collect();
+                    self.physics_engine.borrow_mut().step_scenes(&scenes)
+                };
+
+                // Filter out those values are NaN
+                let filtered_physics_updates: Vec<(_, _, AttributeValue)> = physics_updates
+                    .into_iter()
+                    .filter(|(_, _, attr)| match attr {
+                        AttributeValue::Float(val) => !val.is_nan(),
+                        AttributeValue::Vector2(x, y) => !x.is_nan() && !y.is_nan(),
+                        _ => true,
+                    })
+                    .collect();
+
+                if let Err(err) = manager.update_loaded_entity_attributes(filtered_physics_updates)
+                {
+                    LOGGER.error(format!("Failed to update entity attributes: {}", err));
                 }
             }
 
@@ -299,21 +389,29 @@ impl GameRuntime {
                 return;
             }
 
-            // Fire on_collision hooks for contacts that began this frame
-            if let Some(active_scene_id) = active_scene_id {
-                if let Err(e) = self
-                    .lua_scripting
-                    .dispatch_collision_events(active_scene_id)
-                {
-                    LOGGER.error(format!("Error dispatching collision events: {}", e));
-                }
-                // A collision handler may have ended the game
-                if self.lua_scripting.take_game_stop_request() {
-                    LOGGER.info("Game over: a script called end_game()");
-                    self.set_state(RuntimeState::Ended);
-                    self.paint_scene(ui, viewport_rect);
-                    ctx.request_repaint();
-                    return;
+            // Fire collision and trigger hooks for this frame's contact events
+            if let Err(e) = self.lua_scripting.dispatch_collision_events(&loaded_scenes) {
+                LOGGER.error(format!("Error dispatching collision events: {}", e));
+            }
+            // A collision handler may have ended the game
+            if self.lua_scripting.take_game_stop_request() {
+                LOGGER.info("Game over: a script called end_game()");
+                self.set_state(RuntimeState::Ended);
+                self.paint_scene(ui, viewport_rect);
+                ctx.request_repaint();
+                return;
+            }
+
+            // Scene changes scripts asked for: additive loads and unloads
+            // happen now, a new active scene takes over at the next frame
+            for request in self.lua_scripting.take_scene_requests() {
+                let result = match request {
+                    SceneRequest::Load(target, transition) => self.load_scene(target, transition),
+                    SceneRequest::LoadAdditive(target) => self.load_scene_additive(target),
+                    SceneRequest::Unload(target) => self.unload_scene(target),
+                };
+                if let Err(e) = result {
+                    LOGGER.error(format!("Failed to change scenes: {}", e));
                 }
             }
 
@@ -330,16 +428,240 @@ impl GameRuntime {
         ctx.request_repaint();
     }
 
-    /// Paint the active scene (sprites + collider debug shapes) into the
+    /// Switch the active scene, with an optional transition. The switch
+    /// happens at the next frame (or halfway through a fade): the old scene
+    /// gets `on_scene_exit`, its physics bodies, the audio and its script
+    /// state are torn down, and the new scene gets `on_scene_enter`.
+    /// Additively loaded scenes stay loaded (a target that is one of them is
+    /// unloaded first).
+    ///
+    /// Scenes start from the state they had when Play was pressed, so
+    /// loading the current scene restarts it; shared entities and
+    /// `script_state` carry over.
+    pub fn load_scene(
+        &mut self,
+        scene_id: Uuid,
+        transition: SceneTransition,
+    ) -> Result<(), String> {
+        if !self.scene_manager.borrow().has_scene(scene_id) {
+            return Err(format!("Scene {} not found", scene_id));
+        }
+        self.read_deferred_scene(scene_id)?;
+        self.transition = Some(ActiveTransition {
+            target: scene_id,
+            transition,
+            elapsed: 0.0,
+            switched: false,
+            old_frame: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Load a scene on top of the ones already playing (see
+    /// `SceneManager::load_scene_additive`). Like `load_scene`, it starts
+    /// from its pre-play state; its entities join the physics world and get
+    /// `init` and `on_scene_enter` at their first frame.
+    pub fn load_scene_additive(&mut self, scene_id: Uuid) -> Result<(), String> {
+        self.read_deferred_scene(scene_id)?;
+        {
+            let mut manager = self.scene_manager.borrow_mut();
+            manager.load_scene_additive(scene_id)?;
+            self.restore_pre_play_scene(&mut manager, scene_id);
+            let mounted = manager.mount_shared_entities(scene_id)?;
+            let mut fresh: Vec<Uuid> = manager.scenes[&scene_id].entities.keys().copied().collect();
+            fresh.retain(|id| !mounted.contains(id));
+            self.lua_scripting.forget_entities(&fresh);
+            self.physics_engine
+                .borrow_mut()
+                .load_scene(&manager.scenes[&scene_id]);
+        }
+        if let Err(e) = self.lua_scripting.dispatch_scene_enter(scene_id) {
+            LOGGER.error(format!("Error dispatching on_scene_enter: {}", e));
+        }
+        LOGGER.info(format!("Loaded scene {} additively", scene_id));
+        Ok(())
+    }
+
+    /// Unload an additively loaded scene: its entities get `on_scene_exit`
+    /// and leave the physics world; shared entities it mounted go back to
+    /// the shared pool.
+    pub fn unload_scene(&mut self, scene_id: Uuid) -> Result<(), String> {
+        if !self
+            .scene_manager
+            .borrow()
+            .additive_scenes
+            .contains(&scene_id)
+        {
+            return Err(format!("Scene {} is not loaded additively", scene_id));
+        }
+        if let Err(e) = self.lua_scripting.dispatch_scene_exit(scene_id) {
+            LOGGER.error(format!("Error dispatching on_scene_exit: {}", e));
+        }
+
+        let mut manager = self.scene_manager.borrow_mut();
+        let unmounted = manager.unmount_shared_entities(scene_id)?;
+        let scene = &manager.scenes[&scene_id];
+        let mut physics = self.physics_engine.borrow_mut();
+        physics.unload_scene(scene);
+        // Shared entities handed over to another loaded scene keep their body
+        for id in unmounted {
+            physics.remove_entity(id);
+        }
+        let unloaded: Vec<Uuid> = scene.entities.keys().copied().collect();
+        self.lua_scripting.forget_entities(&unloaded);
+        manager.unload_scene(scene_id)?;
+        LOGGER.info(format!("Unloaded scene {}", scene_id));
+        Ok(())
+    }
+
+    // A scene the project hasn't read yet (see
+    // `ProjectManager::load_scene_hierarchy`) comes off disk when first
+    // played; the pre-play snapshot gets it too, so restarting it works
+    fn read_deferred_scene(&mut self, scene_id: Uuid) -> Result<(), String> {
+        let mut manager = self.scene_manager.borrow_mut();
+        if !manager.is_scene_deferred(scene_id) {
+            return Ok(());
+        }
+        let project_path = ProjectManager::get_project_path()
+            .ok_or_else(|| format!("Scene {} hasn't been read and no project is open", scene_id))?;
+        ProjectManager::load_deferred_scene(
+            std::path::Path::new(&project_path),
+            &mut manager,
+            scene_id,
+        )?;
+        if let Some(snapshot) = self.dev_state_snapshot.as_mut() {
+            snapshot.insert_deferred_scene(scene_id, manager.scenes[&scene_id].clone())?;
+        }
+        Ok(())
+    }
+
+    // Put a scene back to the state it had when Play was pressed
+    fn restore_pre_play_scene(&self, manager: &mut SceneManager, scene_id: Uuid) {
+        let fresh = self
+            .dev_state_snapshot
+            .as_ref()
+            .and_then(|snapshot| snapshot.get_scene(scene_id))
+            .cloned();
+        if let (Some(fresh), Some(scene)) = (fresh, manager.get_scene_mut(scene_id)) {
+            *scene = fresh;
+        }
+    }
+
+    /// True while a scene change (including its transition) is in progress.
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    // Move a pending scene change along by `dt`. Returns true while the game
+    // is frozen (fading out of the old scene).
+    fn advance_transition(&mut self, dt: f32) -> bool {
+        let Some(active) = self.transition.as_mut() else {
+            return false;
+        };
+        active.elapsed += dt;
+        let (switch_at, duration) = match active.transition {
+            SceneTransition::Cut => (0.0, 0.0),
+            SceneTransition::Fade(duration) => (duration / 2.0, duration),
+            SceneTransition::Crossfade(duration) => (0.0, duration),
+        };
+
+        if !active.switched {
+            if active.elapsed < switch_at {
+                return true;
+            }
+            active.switched = true;
+            let target = active.target;
+            if matches!(active.transition, SceneTransition::Crossfade(_)) {
+                let old_frame = self.render_loaded_scenes();
+                if let Some(active) = self.transition.as_mut() {
+                    active.old_frame = old_frame;
+                }
+            }
+            if let Err(e) = self.switch_scene(target) {
+                LOGGER.error(format!("Failed to switch scene: {}", e));
+                self.transition = None;
+                return false;
+            }
+        }
+
+        if self
+            .transition
+            .as_ref()
+            .is_some_and(|active| active.elapsed >= duration)
+        {
+            self.transition = None;
+        }
+        false
+    }
+
+    fn switch_scene(&mut self, target: Uuid) -> Result<(), String> {
+        if self
+            .scene_manager
+            .borrow()
+            .additive_scenes
+            .contains(&target)
+        {
+            self.unload_scene(target)?;
+        }
+        let previous = self.scene_manager.borrow().active_scene;
+        if let Some(previous) = previous {
+            if let Err(e) = self.lua_scripting.dispatch_scene_exit(previous) {
+                LOGGER.error(format!("Error dispatching on_scene_exit: {}", e));
+            }
+        }
+
+        {
+            let mut manager = self.scene_manager.borrow_mut();
+            let mut physics = self.physics_engine.borrow_mut();
+            let mut forgotten = Vec::new();
+            if let Some(previous) = previous {
+                for id in manager.unmount_shared_entities(previous)? {
+                    physics.remove_entity(id);
+                }
+                if let Some(scene) = manager.get_scene(previous) {
+                    physics.unload_scene(scene);
+                    forgotten.extend(scene.entities.keys().copied());
+                }
+            }
+            // Start the scene over from its pre-play state
+            self.restore_pre_play_scene(&mut manager, target);
+            forgotten.extend(manager.scenes[&target].entities.keys().copied());
+            self.lua_scripting.forget_entities(&forgotten);
+
+            manager.set_active_scene(target)?;
+            manager.mount_shared_entities(target)?;
+            // Carried-over shared entities get a fresh body (at rest) too
+            physics.load_scene(&manager.scenes[&target]);
+        }
+        self.audio_engine.borrow_mut().cleanup();
+        self.time_accumulator = 0.0;
+
+        if let Err(e) = self.lua_scripting.dispatch_scene_enter(target) {
+            LOGGER.error(format!("Error dispatching on_scene_enter: {}", e));
+        }
+        LOGGER.info(format!("Switched to scene {}", target));
+        Ok(())
+    }
+
+    // Render queue of every loaded scene, in load order (each scene sorted
+    // by z on its own, later scenes on top)
+    fn render_loaded_scenes(&mut self) -> Vec<RenderQueueEntry> {
+        let manager = self.scene_manager.borrow();
+        manager
+            .loaded_scenes()
+            .into_iter()
+            .filter_map(|scene_id| manager.get_scene(scene_id))
+            .flat_map(|scene| self.render_engine.render(scene))
+            .collect()
+    }
+
+    /// Paint the loaded scenes (sprites + collider debug shapes) into the
     /// viewport. Used by both the playing and paused states.
     fn paint_scene(&mut self, ui: &mut egui::Ui, viewport_rect: Rect) {
-        let render_queue = {
-            let manager = self.scene_manager.borrow();
-            match manager.get_active_scene() {
-                Some(scene) => self.render_engine.render(scene),
-                None => return,
-            }
-        };
+        if self.scene_manager.borrow().get_active_scene().is_none() {
+            return;
+        }
+        let render_queue = self.render_loaded_scenes();
 
         // Clip all game drawing to the viewport; the GPU scissor handles
         // partial visibility (also correct for rotated sprites, unlike
@@ -374,7 +696,7 @@ impl GameRuntime {
         let collider_data = self.physics_engine.borrow().get_collider_data();
         let collider_render_queue = self.render_engine.render_colliders(&collider_data);
 
-        for (screen_position, screen_size, shape) in collider_render_queue {
+        for (screen_position, screen_size, shape, outline) in collider_render_queue {
             match shape.as_str() {
                 "Circle" => {
                     let center = egui::pos2(
@@ -403,9 +725,78 @@ impl GameRuntime {
                         egui::StrokeKind::Middle,
                     );
                 }
+                "Capsule" | "Polygon" | "Polyline" => {
+                    let mut points: Vec<egui::Pos2> = outline
+                        .iter()
+                        .map(|(x, y)| egui::pos2(viewport_rect.min.x + x, viewport_rect.min.y + y))
+                        .collect();
+                    // Close the outline of shapes with an inside
+                    if shape != "Polyline" {
+                        points.extend(points.first().copied());
+                    }
+                    painter.add(egui::Shape::line(
+                        points,
+                        egui::Stroke::new(1.0_f32, egui::Color32::BLUE),
+                    ));
+                }
                 _ => {}
             }
         }
+
+        // render joints: a line between the anchors
+        let joint_data = self.physics_engine.borrow().get_joint_data();
+        for (anchor1, anchor2) in self.render_engine.render_joints(&joint_data) {
+            let [a, b] = [anchor1, anchor2]
+                .map(|(x, y)| egui::pos2(viewport_rect.min.x + x, viewport_rect.min.y + y));
+            let stroke = egui::Stroke::new(1.0_f32, egui::Color32::GREEN);
+            painter.line_segment([a, b], stroke);
+            painter.circle_stroke(a, 3.0, stroke);
+            painter.circle_stroke(b, 3.0, stroke);
+        }
+
+        // Scene transition on top of everything
+        let Some(active) = &self.transition else {
+            return;
+        };
+        match active.transition {
+            SceneTransition::Fade(duration) if duration > 0.0 => {
+                let half = duration / 2.0;
+                let darkness = if active.switched {
+                    1.0 - (active.elapsed - half) / half
+                } else {
+                    active.elapsed / half
+                };
+                let alpha = (darkness.clamp(0.0, 1.0) * 255.0) as u8;
+                painter.rect_filled(viewport_rect, 0.0, egui::Color32::from_black_alpha(alpha));
+            }
+            SceneTransition::Crossfade(duration) if duration > 0.0 => {
+                let opacity = (1.0 - active.elapsed / duration).clamp(0.0, 1.0);
+                let tint = egui::Color32::from_white_alpha((opacity * 255.0) as u8);
+                for entry in &active.old_frame {
+                    let Some(texture) = self
+                        .render_engine
+                        .get_egui_texture(ui.ctx(), entry.texture_id)
+                    else {
+                        continue;
+                    };
+                    let texture_rect = egui::Rect::from_min_size(
+                        egui::pos2(
+                            viewport_rect.min.x + entry.screen_pos.0,
+                            viewport_rect.min.y + entry.screen_pos.1,
+                        ),
+                        egui::vec2(entry.screen_size.0, entry.screen_size.1),
+                    );
+                    crate::render_engine::paint_sprite_tinted(
+                        &painter,
+                        texture.id(),
+                        texture_rect,
+                        entry.rotation,
+                        tint,
+                    );
+                }
+            }
+            _ => {}
+        }
     }
 
     pub fn stop(&mut self) {
@@ -416,6 +807,7 @@ impl GameRuntime {
         // Stop all running systems
         self.running = false;
         self.state = RuntimeState::Stopped;
+        self.transition = None;
 
         // Cleanup engines
         self.physics_engine.borrow_mut().cleanup();
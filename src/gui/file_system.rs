@@ -1,5 +1,6 @@
 use crate::gui::gui_state::{GuiState, SelectedItem};
 use crate::logger::LOGGER;
+use crate::physics_engine::auto_collider_cache_path;
 use eframe::egui;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -307,6 +308,8 @@ impl FileSystem {
                             LOGGER.error(format!("Failed to delete file: {}", err));
                         } else {
                             LOGGER.info(format!("Deleted file: {}", name));
+                            // An image's traced collider goes with it
+                            let _ = fs::remove_file(auto_collider_cache_path(&path));
                             self.refresh();
                             if matches!(&gui_state.selected_item,
                                 SelectedItem::File(selected_path)
@@ -1,4 +1,4 @@
-use crate::ecs::SceneManager;
+use crate::ecs::{Scene, SceneManager};
 use crate::project_manager::ProjectMetadata;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -91,6 +91,16 @@ impl UndoStack {
         Some(self.states.last().expect("len checked").clone())
     }
 
+    /// A deferred scene has been read: hand it to every recorded state that
+    /// still has it deferred, so undo/redo never go back to an unread copy.
+    pub fn add_read_scene(&mut self, scene_id: Uuid, scene: &Scene) {
+        for state in self.states.iter_mut().chain(self.redo.iter_mut()) {
+            if state.is_scene_deferred(scene_id) {
+                let _ = state.insert_deferred_scene(scene_id, scene.clone());
+            }
+        }
+    }
+
     /// Step forward one undone state; returns the state to restore.
     pub fn redo(&mut self) -> Option<SceneManager> {
         let state = self.redo.pop()?;
@@ -123,6 +133,8 @@ pub struct GuiState {
     pub build_result: Arc<Mutex<Option<Result<(), String>>>>,
     pub is_building: Arc<Mutex<bool>>,
     pub show_build_project_popup: bool,
+    pub show_collision_layers_popup: bool,
+    pub show_new_material_popup: bool,
 
     pub exit_request: ExitRequest,
 
@@ -166,6 +178,8 @@ impl GuiState {
             build_result: Arc::new(Mutex::new(None)),
             is_building: Arc::new(Mutex::new(false)),
             show_build_project_popup: false,
+            show_collision_layers_popup: false,
+            show_new_material_popup: false,
 
             exit_request: ExitRequest::None,
 
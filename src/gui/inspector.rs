@@ -1,9 +1,11 @@
 use crate::audio_engine::AudioEngine;
-use crate::ecs::{AttributeType, AttributeValue, Entity};
+use crate::ecs::{Attribute, AttributeError, AttributeType, AttributeValue, Entity, SceneEvent};
 use crate::gui::gui_state::{GuiState, SelectedItem};
 use crate::gui::scene_hierarchy::utils;
 use crate::gui::scene_hierarchy::utils::format_file_size;
 use crate::logger::LOGGER;
+use crate::physics_engine::{CombineRule, PhysicsMaterial};
+use crate::project_manager::ProjectManager;
 use eframe::egui;
 use eframe::egui::{ColorImage, TextureOptions, Vec2};
 use image;
@@ -27,8 +29,12 @@ pub struct Inspector {
     // decode images / audio metadata from disk every frame
     preview_image: Option<(PathBuf, egui::TextureHandle, (u32, u32))>,
     preview_audio_duration: Option<(PathBuf, Option<f32>)>,
+    // The selected physics material file, as being edited
+    material_edit: Option<(PathBuf, PhysicsMaterial)>,
     // Snippet queued for the script editor (clicking an attribute name)
     pending_script_insert: Option<String>,
+    // Text of the "add tag" field
+    new_tag: String,
 }
 
 impl Default for Inspector {
@@ -51,7 +57,9 @@ impl Inspector {
             delete_mode: false,
             preview_image: None,
             preview_audio_duration: None,
+            material_edit: None,
             pending_script_insert: None,
+            new_tag: String::new(),
         }
     }
 
@@ -281,6 +289,12 @@ impl Inspector {
                         ui.separator();
                         ui.label(format!("Size: {}", format_file_size(metadata.len())));
                     }
+                    "material" => {
+                        self.show_material_details(ui, file_path);
+                        ui.separator();
+                        ui.label("Path:");
+                        ui.label(format!("{}", file_path.to_string_lossy()));
+                    }
                     "ttf" | "otf" => {
                         ui.separator();
                         ui.label("Path:");
@@ -305,6 +319,45 @@ impl Inspector {
         }
     }
 
+    // Physics material settings, saved as they're edited; a game that's
+    // playing picks the change up on its next physics step
+    fn show_material_details(&mut self, ui: &mut egui::Ui, file_path: &Path) {
+        let cached = matches!(&self.material_edit, Some((p, _)) if p == file_path);
+        if !cached {
+            match PhysicsMaterial::load(file_path) {
+                Ok(material) => self.material_edit = Some((file_path.to_path_buf(), material)),
+                Err(e) => {
+                    ui.label(e);
+                    return;
+                }
+            }
+        }
+        let Some((_, material)) = &mut self.material_edit else {
+            return;
+        };
+
+        ui.label("Physics Material");
+        ui.separator();
+        let mut changed = false;
+        egui::Grid::new("physics_material")
+            .num_columns(2)
+            .show(ui, |ui| {
+                changed |= material_number(ui, "Friction", &mut material.friction);
+                changed |= material_number(ui, "Restitution", &mut material.restitution);
+                changed |= material_number(ui, "Density", &mut material.density);
+                changed |= material_rule(ui, "Friction combine", &mut material.friction_combine);
+                changed |=
+                    material_rule(ui, "Restitution combine", &mut material.restitution_combine);
+                changed |= material_number(ui, "Drag", &mut material.linear_damping);
+                changed |= material_number(ui, "Angular damping", &mut material.angular_damping);
+            });
+        if changed {
+            if let Err(e) = material.save(file_path) {
+                LOGGER.error(format!("Failed to save physics material: {}", e));
+            }
+        }
+    }
+
     /// Display entity information
     fn show_entity_details(
         &mut self,
@@ -314,26 +367,41 @@ impl Inspector {
         entity_id: Uuid,
         gui_state: &mut GuiState,
     ) {
+        let project_path = gui_state.project_path.clone();
         if let Some(scene_manager) = &mut gui_state.scene_manager {
+            let layer_names = scene_manager.collision_layers.clone();
             if let Some(scene) = scene_manager.get_scene_mut(scene_id) {
+                // Targets for EntityRef attributes
+                let scene_entities: Vec<(Uuid, String)> = scene
+                    .entities
+                    .iter()
+                    .map(|(id, entity)| (*id, entity.name.clone()))
+                    .collect();
                 if let Ok(entity) = scene.get_entity_mut(entity_id) {
                     ui.label(entity.name.to_string());
+                    if ui.checkbox(&mut entity.enabled, "Enabled").changed() {
+                        self.data_updated = true;
+                    }
                     ui.separator();
                     ui.label(format!("ID: {}", entity_id));
                     ui.separator();
                     ui.label(format!("Scene ID: {}", scene_id));
                     ui.separator();
 
-                    for (&attribute_id, attribute) in &entity.attributes.clone() {
+                    for attribute in entity.attributes.clone().values() {
                         self.display_attribute(
                             ui,
-                            attribute_id,
-                            &attribute.name,
-                            &attribute.value,
+                            attribute,
                             entity,
+                            &scene_entities,
+                            &layer_names,
+                            &project_path,
                         );
                     }
 
+                    self.show_tags(ui, entity);
+                    ui.separator();
+
                     // Buttons in same row with even spacing
                     ui.horizontal(|ui| {
                         let available_width = ui.available_width();
@@ -384,6 +452,51 @@ impl Inspector {
         }
     }
 
+    /// Forget the edit buffers of attributes changed or deleted elsewhere, so
+    /// their fields show the new value.
+    pub fn handle_scene_events(&mut self, events: &[SceneEvent]) {
+        for event in events {
+            if let SceneEvent::AttributeChanged { attribute_id, .. }
+            | SceneEvent::AttributeDeleted { attribute_id, .. } = event
+            {
+                self.editing_states.remove(attribute_id);
+            }
+        }
+    }
+
+    /// Entity tags: click a tag to remove it, type + Enter to add one
+    fn show_tags(&mut self, ui: &mut egui::Ui, entity: &mut Entity) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new("Tags").strong());
+            for tag in entity.tags.clone() {
+                if ui
+                    .small_button(format!("{} ✖", tag))
+                    .on_hover_text("Remove tag")
+                    .clicked()
+                {
+                    if let Err(e) = entity.remove_tag(&tag) {
+                        LOGGER.error(format!("Failed to remove tag: {}", e));
+                    }
+                    self.data_updated = true;
+                }
+            }
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.new_tag)
+                    .desired_width(80.0)
+                    .hint_text("add tag"),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let tag = std::mem::take(&mut self.new_tag);
+                if !tag.trim().is_empty() {
+                    match entity.add_tag(&tag) {
+                        Ok(()) => self.data_updated = true,
+                        Err(e) => LOGGER.warning(format!("Failed to add tag: {}", e)),
+                    }
+                }
+            }
+        });
+    }
+
     /// Add metadata popup, type must be in Entity's attribute types
     fn show_metadata_popup(
         &mut self,
@@ -401,57 +514,29 @@ impl Inspector {
 
                 ui.label("Select Attribute Type:");
                 egui::ComboBox::from_label("Type")
-                    .selected_text(format!("{:?}", self.metadata_new_value))
+                    .selected_text(format!("{:?}", self.metadata_new_type))
                     .show_ui(ui, |ui| {
-                        if ui
-                            .selectable_value(
-                                &mut self.metadata_new_value,
-                                AttributeValue::Integer(0),
-                                "Integer",
-                            )
-                            .clicked()
-                        {
-                            self.metadata_new_type = AttributeType::Integer;
-                        }
-                        if ui
-                            .selectable_value(
-                                &mut self.metadata_new_value,
-                                AttributeValue::Float(0.0),
-                                "Float",
-                            )
-                            .clicked()
-                        {
-                            self.metadata_new_type = AttributeType::Float;
-                        }
-                        if ui
-                            .selectable_value(
-                                &mut self.metadata_new_value,
-                                AttributeValue::String(String::new()),
-                                "String",
-                            )
-                            .clicked()
-                        {
-                            self.metadata_new_type = AttributeType::String;
-                        }
-                        if ui
-                            .selectable_value(
-                                &mut self.metadata_new_value,
-                                AttributeValue::Boolean(false),
-                                "Boolean",
-                            )
-                            .clicked()
-                        {
-                            self.metadata_new_type = AttributeType::Boolean;
-                        }
-                        if ui
-                            .selectable_value(
-                                &mut self.metadata_new_value,
-                                AttributeValue::Vector2(0.0, 0.0),
-                                "Vector2",
-                            )
-                            .clicked()
-                        {
-                            self.metadata_new_type = AttributeType::Vector2;
+                        for data_type in [
+                            AttributeType::Integer,
+                            AttributeType::Float,
+                            AttributeType::String,
+                            AttributeType::Boolean,
+                            AttributeType::Vector2,
+                            AttributeType::Vector3,
+                            AttributeType::Color,
+                            AttributeType::EntityRef,
+                            AttributeType::AssetRef,
+                            AttributeType::List,
+                            AttributeType::Map,
+                        ] {
+                            let selected = self.metadata_new_type == data_type;
+                            if ui
+                                .selectable_label(selected, format!("{:?}", data_type))
+                                .clicked()
+                            {
+                                self.metadata_new_value = AttributeValue::default_for(&data_type);
+                                self.metadata_new_type = data_type;
+                            }
                         }
                     });
 
@@ -489,7 +574,7 @@ impl Inspector {
                                 }
                                 Err(error) => {
                                     println!("Failed to create {}: {}", full_name, error);
-                                    self.metadata_error_message = error;
+                                    self.metadata_error_message = error.to_string();
                                 }
                             }
                         } else {
@@ -514,15 +599,28 @@ impl Inspector {
     fn display_attribute(
         &mut self,
         ui: &mut egui::Ui,
-        attribute_id: Uuid,
-        attribute_name: &str,
-        attribute_value: &AttributeValue,
+        attribute: &Attribute,
         entity: &mut Entity,
+        scene_entities: &[(Uuid, String)],
+        layer_names: &[String],
+        project_path: &Path,
     ) {
+        let attribute_id = attribute.id;
+        let attribute_name = attribute.name.as_str();
+        let attribute_value = &attribute.value;
+        let shown_value = || match attribute_value {
+            // Asset paths are edited relative to the project
+            AttributeValue::AssetRef(path) => path
+                .strip_prefix(project_path)
+                .unwrap_or(path)
+                .display()
+                .to_string(),
+            _ => attribute_value.to_string(),
+        };
         let temp_value = self
             .editing_states
             .entry(attribute_id)
-            .or_insert_with(|| attribute_value.to_string())
+            .or_insert_with(shown_value)
             .clone();
 
         ui.horizontal(|ui| {
@@ -592,7 +690,96 @@ impl Inspector {
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
                 ui.set_width(input_width);
 
+                let mut new_value = None;
                 match attribute_value {
+                    AttributeValue::Color(r, g, b, a) => {
+                        let mut rgba = [*r, *g, *b, *a];
+                        if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+                            let [r, g, b, a] = rgba;
+                            new_value = Some(AttributeValue::Color(r, g, b, a));
+                        }
+                    }
+                    // The project's materials by name
+                    AttributeValue::AssetRef(current) if attribute_name == "physics_material" => {
+                        let current_name = current
+                            .file_stem()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_else(|| "none".to_string());
+                        egui::ComboBox::from_id_salt(attribute_id)
+                            .width(input_width)
+                            .selected_text(current_name)
+                            .show_ui(ui, |ui| {
+                                if ui
+                                    .selectable_label(current.as_os_str().is_empty(), "none")
+                                    .clicked()
+                                {
+                                    new_value = Some(AttributeValue::AssetRef(PathBuf::new()));
+                                }
+                                for relative in ProjectManager::list_materials(project_path) {
+                                    let path = project_path.join(&relative);
+                                    let name = relative
+                                        .file_stem()
+                                        .map(|name| name.to_string_lossy().to_string())
+                                        .unwrap_or_default();
+                                    if ui.selectable_label(*current == path, name).clicked() {
+                                        new_value = Some(AttributeValue::AssetRef(path));
+                                    }
+                                }
+                            });
+                    }
+                    AttributeValue::EntityRef(target) => {
+                        let target_name = target
+                            .and_then(|id| scene_entities.iter().find(|(e_id, _)| *e_id == id))
+                            .map(|(_, name)| name.as_str())
+                            .unwrap_or("none");
+                        egui::ComboBox::from_id_salt(attribute_id)
+                            .width(input_width)
+                            .selected_text(target_name)
+                            .show_ui(ui, |ui| {
+                                if ui.selectable_label(target.is_none(), "none").clicked() {
+                                    new_value = Some(AttributeValue::EntityRef(None));
+                                }
+                                for (id, name) in scene_entities {
+                                    if ui.selectable_label(*target == Some(*id), name).clicked() {
+                                        new_value = Some(AttributeValue::EntityRef(Some(*id)));
+                                    }
+                                }
+                            });
+                    }
+                    // One checkbox per named layer of the project
+                    AttributeValue::Integer(bits)
+                        if matches!(attribute_name, "collision_layers" | "collision_mask") =>
+                    {
+                        let bits = *bits as u32;
+                        let selected: Vec<&str> = layer_names
+                            .iter()
+                            .enumerate()
+                            .filter(|(index, _)| bits & 1 << index != 0)
+                            .map(|(_, name)| name.as_str())
+                            .collect();
+                        let summary = if bits == u32::MAX {
+                            "all".to_string()
+                        } else if selected.is_empty() {
+                            "none".to_string()
+                        } else {
+                            selected.join(", ")
+                        };
+                        egui::ComboBox::from_id_salt(attribute_id)
+                            .width(input_width)
+                            .selected_text(summary)
+                            .show_ui(ui, |ui| {
+                                if layer_names.is_empty() {
+                                    ui.label("Add layers in Project > Collision Layers");
+                                }
+                                for (index, name) in layer_names.iter().enumerate() {
+                                    let mut on = bits & 1 << index != 0;
+                                    if ui.checkbox(&mut on, name).changed() {
+                                        let bits = bits ^ 1 << index;
+                                        new_value = Some(AttributeValue::Integer(bits as i32));
+                                    }
+                                }
+                            });
+                    }
                     AttributeValue::Boolean(_) => {
                         let mut value = temp_value.parse::<bool>().unwrap_or(false);
                         if ui.checkbox(&mut value, "").changed() {
@@ -616,25 +803,34 @@ impl Inspector {
                             .desired_width(input_width),
                         );
 
-                        if response.lost_focus() {
-                            if let Some(new_value) =
-                                self.parse_attribute_value(&temp_value, attribute_value)
-                            {
-                                if let Err(e) = entity.modify_attribute(
-                                    attribute_id,
-                                    None,
-                                    None,
-                                    Some(new_value),
-                                ) {
-                                    LOGGER.error(format!("Failed to modify attribute: {}", e));
+                        // Unchanged text isn't parsed again, which could
+                        // change the value it shows
+                        if response.lost_focus() && temp_value != shown_value() {
+                            match self.parse_attribute_value(&temp_value, attribute_value) {
+                                Ok(mut value) => {
+                                    value.map_asset_refs(&mut |path| project_path.join(path));
+                                    new_value = Some(value);
                                 }
-                                self.editing_states.remove(&attribute_id);
-                                self.data_updated = true;
-                            } else {
-                                self.editing_states.remove(&attribute_id);
+                                Err(e) => LOGGER.warning(format!(
+                                    "Invalid value for {}: {}",
+                                    attribute_name, e
+                                )),
                             }
                         }
+                        if response.lost_focus() {
+                            self.editing_states.remove(&attribute_id);
+                        }
+                    }
+                }
+
+                if let Some(new_value) = new_value {
+                    if let Err(e) =
+                        entity.modify_attribute(attribute_id, None, None, Some(new_value))
+                    {
+                        LOGGER.error(format!("Failed to modify attribute: {}", e));
                     }
+                    self.editing_states.remove(&attribute_id);
+                    self.data_updated = true;
                 }
             });
         });
@@ -646,26 +842,40 @@ impl Inspector {
         !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
     }
 
-    /// Parse input value
+    /// Parse input value as the type of the attribute's current value
     fn parse_attribute_value(
         &self,
         input: &str,
-        attribute_value: &AttributeValue,
-    ) -> Option<AttributeValue> {
-        match attribute_value {
-            AttributeValue::Integer(_) => input.parse::<i32>().ok().map(AttributeValue::Integer),
-            AttributeValue::Float(_) => input.parse::<f32>().ok().map(AttributeValue::Float),
-            AttributeValue::String(_) => Some(AttributeValue::String(input.to_string())),
-            AttributeValue::Boolean(_) => input.parse::<bool>().ok().map(AttributeValue::Boolean),
-            AttributeValue::Vector2(_, _) => {
-                let parts: Vec<&str> = input.split(',').map(|s| s.trim()).collect();
-                if parts.len() == 2 {
-                    if let (Ok(x), Ok(y)) = (parts[0].parse::<f32>(), parts[1].parse::<f32>()) {
-                        return Some(AttributeValue::Vector2(x, y));
-                    }
-                }
-                None
-            }
-        }
+        current: &AttributeValue,
+    ) -> Result<AttributeValue, AttributeError> {
+        AttributeValue::parse_like(input, current)
     }
 }
+
+// One number of the material editor; false unless it was changed
+fn material_number(ui: &mut egui::Ui, label: &str, value: &mut f32) -> bool {
+    ui.label(label);
+    let changed = ui
+        .add(
+            egui::DragValue::new(value)
+                .speed(0.01)
+                .range(0.0..=f32::MAX),
+        )
+        .changed();
+    ui.end_row();
+    changed
+}
+
+fn material_rule(ui: &mut egui::Ui, label: &str, rule: &mut CombineRule) -> bool {
+    ui.label(label);
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(label)
+        .selected_text(rule.name())
+        .show_ui(ui, |ui| {
+            for option in CombineRule::ALL {
+                changed |= ui.selectable_value(rule, option, option.name()).changed();
+            }
+        });
+    ui.end_row();
+    changed
+}
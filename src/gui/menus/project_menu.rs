@@ -1,14 +1,26 @@
-use crate::gui::gui_state::GuiState;
+use crate::gui::gui_state::{GuiState, SelectedItem};
+use crate::gui::scene_hierarchy::utils;
 use crate::logger::LOGGER;
 use crate::project_manager::ProjectManager;
 use eframe::egui;
 use std::sync::Arc;
 
-pub struct ProjectMenu;
+// Bits of the `collision_layers` / `collision_mask` attributes
+const MAX_COLLISION_LAYERS: usize = 32;
+
+pub struct ProjectMenu {
+    // Edit buffer of the Collision Layers dialog
+    collision_layers: Vec<String>,
+    // Name field of the New Physics Material dialog
+    material_name: String,
+}
 
 impl ProjectMenu {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            collision_layers: Vec::new(),
+            material_name: String::new(),
+        }
     }
 
     pub fn show(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui, gui_state: &mut GuiState) {
@@ -42,12 +54,159 @@ impl ProjectMenu {
 
             gui_state.show_build_project_popup = true;
         });
+
+        if ui
+            .add_enabled(
+                gui_state.scene_manager.is_some(),
+                egui::Button::new("Collision Layers"),
+            )
+            .clicked()
+        {
+            if let Some(scene_manager) = &gui_state.scene_manager {
+                self.collision_layers = scene_manager.collision_layers.clone();
+            }
+            gui_state.show_collision_layers_popup = true;
+            ui.close();
+        }
+
+        if ui
+            .add_enabled(
+                !gui_state.project_path.as_os_str().is_empty(),
+                egui::Button::new("New Physics Material"),
+            )
+            .clicked()
+        {
+            self.material_name.clear();
+            gui_state.show_new_material_popup = true;
+            ui.close();
+        }
     }
 
     pub fn show_active_popup(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
         if gui_state.show_build_project_popup {
             self.render_build_project_popup(ctx, gui_state);
         }
+        if gui_state.show_collision_layers_popup {
+            self.render_collision_layers_popup(ctx, gui_state);
+        }
+        if gui_state.show_new_material_popup {
+            self.render_new_material_popup(ctx, gui_state);
+        }
+    }
+
+    // Writes `assets/materials/<name>.material` and selects it, so its
+    // settings come up in the inspector
+    fn render_new_material_popup(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
+        let mut open = true;
+        egui::Window::new("New Physics Material")
+            .collapsible(false)
+            .resizable(false)
+            .order(egui::Order::Foreground)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut self.material_name);
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.material_name.trim().is_empty(),
+                            egui::Button::new("Create"),
+                        )
+                        .clicked()
+                    {
+                        match ProjectManager::create_material(
+                            &gui_state.project_path,
+                            &self.material_name,
+                        ) {
+                            Ok(relative_path) => {
+                                gui_state.selected_item =
+                                    SelectedItem::File(gui_state.project_path.join(relative_path));
+                                gui_state.show_new_material_popup = false;
+                            }
+                            Err(e) => LOGGER.error(format!("Failed to create material: {}", e)),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        gui_state.show_new_material_popup = false;
+                    }
+                });
+            });
+        if !open {
+            gui_state.show_new_material_popup = false;
+        }
+    }
+
+    // Layer n is bit n of the entities' layer attributes, so only the last
+    // layer can be removed without changing what the others mean
+    fn render_collision_layers_popup(&mut self, ctx: &egui::Context, gui_state: &mut GuiState) {
+        let mut open = true;
+        egui::Window::new("Collision Layers")
+            .collapsible(false)
+            .resizable(false)
+            .order(egui::Order::Foreground)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let last = self.collision_layers.len().checked_sub(1);
+                let mut remove_last = false;
+                for (index, name) in self.collision_layers.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{:2}", index));
+                        ui.text_edit_singleline(name);
+                        if Some(index) == last && ui.small_button("❌").clicked() {
+                            remove_last = true;
+                        }
+                    });
+                }
+                if remove_last {
+                    self.collision_layers.pop();
+                }
+                if ui
+                    .add_enabled(
+                        self.collision_layers.len() < MAX_COLLISION_LAYERS,
+                        egui::Button::new("➕ Add Layer"),
+                    )
+                    .clicked()
+                {
+                    self.collision_layers
+                        .push(format!("layer_{}", self.collision_layers.len()));
+                }
+                // Scripts refer to layers by name
+                let names_valid = self
+                    .collision_layers
+                    .iter()
+                    .enumerate()
+                    .all(|(index, name)| {
+                        !name.trim().is_empty() && !self.collision_layers[..index].contains(name)
+                    });
+                if !names_valid {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        "Layer names must be unique and not empty",
+                    );
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(names_valid, egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        if let Some(scene_manager) = &mut gui_state.scene_manager {
+                            scene_manager.collision_layers = self.collision_layers.clone();
+                        }
+                        utils::save_project(gui_state);
+                        gui_state.show_collision_layers_popup = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        gui_state.show_collision_layers_popup = false;
+                    }
+                });
+            });
+        if !open {
+            gui_state.show_collision_layers_popup = false;
+        }
     }
 
     fn render_build_project_popup(&self, ctx: &egui::Context, gui_state: &mut GuiState) {
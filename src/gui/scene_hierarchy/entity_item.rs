@@ -1,5 +1,7 @@
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
-use crate::gui::scene_hierarchy::SceneHierarchy;
+use crate::gui::scene_hierarchy::{utils, SceneHierarchy};
+use crate::logger::LOGGER;
+use crate::project_manager::ProjectManager;
 use egui::{Context, Ui};
 use uuid::Uuid;
 
@@ -12,6 +14,11 @@ pub struct EntityDisplay {
     pub sound_names: Vec<String>,
     pub script_name: Option<String>,
     pub script_path: Option<std::path::PathBuf>,
+    pub parent: Option<Uuid>,
+    pub prefab_source: Option<std::path::PathBuf>,
+    pub tags: Vec<String>,
+    pub enabled: bool, // The entity's own flag
+    pub active: bool,  // Enabled, and so are all its ancestors
 }
 
 pub struct EntityItem;
@@ -25,35 +32,69 @@ impl EntityItem {
         scene_id: &Uuid,
         entities: &[EntityDisplay],
     ) {
+        // While searching or filtering by tag, show matches as a flat list
+        // so children of non-matching parents still show up
+        if !hierarchy.search_query.is_empty() || hierarchy.tag_filter.is_some() {
+            let query = hierarchy.search_query.to_lowercase();
+            let tag_filter = hierarchy.tag_filter.clone();
+            for entity in entities {
+                let tagged = tag_filter
+                    .as_ref()
+                    .is_none_or(|tag| entity.tags.contains(tag));
+                if tagged && entity.name.to_lowercase().contains(&query) {
+                    Self::show_entity(
+                        ui, ctx, hierarchy, gui_state, scene_id, entity, entities, false,
+                    );
+                }
+            }
+            return;
+        }
+
+        // Roots are entities without a parent (or whose parent is missing)
         for entity in entities {
-            if !hierarchy.search_query.is_empty()
-                && !entity
-                    .name
-                    .to_lowercase()
-                    .contains(&hierarchy.search_query.to_lowercase())
-            {
-                continue;
+            let is_root = entity
+                .parent
+                .is_none_or(|parent| !entities.iter().any(|e| e.id == parent));
+            if is_root {
+                Self::show_entity(
+                    ui, ctx, hierarchy, gui_state, scene_id, entity, entities, true,
+                );
             }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn show_entity(
+        ui: &mut Ui,
+        ctx: &Context,
+        hierarchy: &mut SceneHierarchy,
+        gui_state: &mut GuiState,
+        scene_id: &Uuid,
+        entity: &EntityDisplay,
+        entities: &[EntityDisplay],
+        nested: bool,
+    ) {
+        let header_id = ui.make_persistent_id(entity.id);
 
-            let header_id = ui.make_persistent_id(entity.id);
+        let children: Vec<&EntityDisplay> = if nested {
+            entities
+                .iter()
+                .filter(|e| e.parent == Some(entity.id))
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-            // Show as collapsable if it has any attached assets
-            let has_assets = !entity.image_names.is_empty()
-                || !entity.sound_names.is_empty()
-                || entity.script_name.is_some();
+        // Show as collapsable if it has any attached assets or children
+        let has_assets = !entity.image_names.is_empty()
+            || !entity.sound_names.is_empty()
+            || entity.script_name.is_some();
 
-            if has_assets {
-                egui::collapsing_header::CollapsingState::load_with_default_open(
-                    ctx, header_id, true,
-                )
+        if has_assets || !children.is_empty() {
+            egui::collapsing_header::CollapsingState::load_with_default_open(ctx, header_id, true)
                 .show_header(ui, |ui| {
                     EntityItem::tree_item_entity(
-                        ui,
-                        scene_id,
-                        &entity.id,
-                        &entity.name,
-                        hierarchy,
-                        gui_state,
+                        ui, scene_id, entity, entities, hierarchy, gui_state,
                     );
                 })
                 .body(|ui| {
@@ -79,30 +120,112 @@ impl EntityItem {
                             }
                         });
                     }
+                    for child in children {
+                        Self::show_entity(
+                            ui, ctx, hierarchy, gui_state, scene_id, child, entities, true,
+                        );
+                    }
                 });
-            } else {
-                ui.horizontal(|ui| {
-                    EntityItem::tree_item_entity(
-                        ui,
-                        scene_id,
-                        &entity.id,
-                        &entity.name,
-                        hierarchy,
-                        gui_state,
+        } else {
+            ui.horizontal(|ui| {
+                EntityItem::tree_item_entity(ui, scene_id, entity, entities, hierarchy, gui_state);
+            });
+        }
+    }
+
+    // Whether `id` is `ancestor` or sits somewhere below it
+    fn is_in_subtree(entities: &[EntityDisplay], ancestor: Uuid, id: Uuid) -> bool {
+        let mut current = Some(id);
+        let mut hops = 0;
+        while let Some(current_id) = current {
+            if current_id == ancestor {
+                return true;
+            }
+            hops += 1;
+            if hops > entities.len() {
+                return false;
+            }
+            current = entities
+                .iter()
+                .find(|e| e.id == current_id)
+                .and_then(|e| e.parent);
+        }
+        false
+    }
+
+    fn set_parent(gui_state: &mut GuiState, scene_id: Uuid, entity_id: Uuid, parent: Option<Uuid>) {
+        let result = match gui_state
+            .scene_manager
+            .as_mut()
+            .and_then(|manager| manager.get_scene_mut(scene_id))
+        {
+            Some(scene) => scene.set_parent(entity_id, parent),
+            None => Err(format!("Scene {} not found", scene_id)),
+        };
+        match result {
+            Ok(()) => utils::save_project(gui_state),
+            Err(e) => LOGGER.error(format!("Failed to set parent: {}", e)),
+        }
+    }
+
+    // Save an entity (and its children) as a prefab. Saving a prefab
+    // instance writes back to its prefab and updates the other instances.
+    fn save_as_prefab(
+        gui_state: &mut GuiState,
+        scene_id: Uuid,
+        entity_id: Uuid,
+    ) -> Result<std::path::PathBuf, String> {
+        let project_path = gui_state.project_path.clone();
+        let scene_manager = gui_state
+            .scene_manager
+            .as_mut()
+            .ok_or("No project is open")?;
+        let scene = scene_manager
+            .get_scene_mut(scene_id)
+            .ok_or_else(|| format!("Scene {} not found", scene_id))?;
+        let entity = scene.get_entity(entity_id)?;
+
+        let source = match &entity.prefab {
+            Some(link) => {
+                let parent_link = entity
+                    .parent
+                    .and_then(|parent| scene.get_entity(parent).ok())
+                    .and_then(|parent| parent.prefab.as_ref());
+                if parent_link.is_some_and(|parent| parent.source == link.source) {
+                    return Err(
+                        "This entity is part of a prefab instance; save the instance root instead"
+                            .to_string(),
                     );
-                });
+                }
+                link.source.clone()
+            }
+            None => {
+                let source = ProjectManager::prefab_path(&entity.name)?;
+                if project_path.join(&source).exists() {
+                    return Err(format!("Prefab '{}' already exists", source.display()));
+                }
+                source
             }
+        };
+
+        let prefab = scene.create_prefab(entity_id, &source)?;
+        ProjectManager::save_prefab(&project_path, &source, &prefab)?;
+        for scene in scene_manager.scenes.values_mut() {
+            scene.sync_prefab_instances(&prefab, &source);
         }
+        Ok(source)
     }
 
     pub fn tree_item_entity(
         ui: &mut Ui,
         scene_id: &Uuid,
-        entity_id: &Uuid,
-        entity_name: &str,
+        entity: &EntityDisplay,
+        entities: &[EntityDisplay],
         hierarchy: &mut SceneHierarchy,
         gui_state: &mut GuiState,
     ) {
+        let entity_id = &entity.id;
+        let entity_name = entity.name.as_str();
         let selected = matches!(
             gui_state.scene_panel_selected_item,
             ScenePanelSelectedItem::Entity(s_id, e_id) if s_id == *scene_id && e_id == *entity_id
@@ -115,7 +238,24 @@ impl EntityItem {
             entity_name
         };
 
-        let response = ui.selectable_label(selected, format!("🖼 {}", display_name));
+        let icon = if entity.prefab_source.is_some() {
+            "📦"
+        } else {
+            "🖼"
+        };
+        let mut label = egui::RichText::new(format!("{} {}", icon, display_name));
+        if !entity.active {
+            label = label.weak().italics();
+        }
+        let mut response = ui.selectable_label(selected, label);
+        if let Some(source) = &entity.prefab_source {
+            response = response.on_hover_text(format!("Prefab instance: {}", source.display()));
+        }
+        if !entity.enabled {
+            response = response.on_hover_text("Disabled");
+        } else if !entity.active {
+            response = response.on_hover_text("Disabled by a parent");
+        }
         if response.clicked() {
             gui_state.selected_item = SelectedItem::Entity(*scene_id, *entity_id);
             gui_state.scene_panel_selected_item =
@@ -133,6 +273,58 @@ impl EntityItem {
                 hierarchy.popup_manager.manage_assets_popup_active = true;
                 ui.close();
             }
+            ui.menu_button("Set Parent", |ui| {
+                // Self and descendants are left out: they would form a cycle
+                for candidate in entities {
+                    if Self::is_in_subtree(entities, *entity_id, candidate.id) {
+                        continue;
+                    }
+                    let is_current = entity.parent == Some(candidate.id);
+                    if ui.selectable_label(is_current, &candidate.name).clicked() {
+                        Self::set_parent(gui_state, *scene_id, *entity_id, Some(candidate.id));
+                        ui.close();
+                    }
+                }
+            });
+            if entity.parent.is_some() && ui.button("Clear Parent").clicked() {
+                Self::set_parent(gui_state, *scene_id, *entity_id, None);
+                ui.close();
+            }
+            let toggle_label = if entity.enabled { "Disable" } else { "Enable" };
+            if ui.button(toggle_label).clicked() {
+                if let Some(scene_entity) = gui_state
+                    .scene_manager
+                    .as_mut()
+                    .and_then(|manager| manager.get_scene_mut(*scene_id))
+                    .and_then(|scene| scene.get_entity_mut(*entity_id).ok())
+                {
+                    scene_entity.set_enabled(!entity.enabled);
+                    utils::save_project(gui_state);
+                }
+                ui.close();
+            }
+            if ui.button("Save as Prefab").clicked() {
+                match Self::save_as_prefab(gui_state, *scene_id, *entity_id) {
+                    Ok(source) => {
+                        LOGGER.info(format!("Saved prefab {}", source.display()));
+                        utils::save_project(gui_state);
+                    }
+                    Err(e) => LOGGER.error(format!("Failed to save prefab: {}", e)),
+                }
+                ui.close();
+            }
+            if entity.prefab_source.is_some() && ui.button("Unlink Prefab").clicked() {
+                if let Some(scene) = gui_state
+                    .scene_manager
+                    .as_mut()
+                    .and_then(|manager| manager.get_scene_mut(*scene_id))
+                {
+                    if scene.unlink_prefab(*entity_id).is_ok() {
+                        utils::save_project(gui_state);
+                    }
+                }
+                ui.close();
+            }
             if ui.button("Rename").clicked() {
                 hierarchy.popup_manager.entity_rename_entity = Some((*scene_id, *entity_id));
                 hierarchy.popup_manager.rename_input = entity_name.to_string();
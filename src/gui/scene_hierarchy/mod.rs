@@ -12,6 +12,8 @@ use scene_item::SceneItem;
 pub struct SceneHierarchy {
     pub search_query: String,
     pub show_search: bool,
+    // Only show entities with this tag
+    pub tag_filter: Option<String>,
     pub popup_manager: PopupManager,
 }
 
@@ -26,6 +28,7 @@ impl SceneHierarchy {
         Self {
             search_query: String::new(),
             show_search: false,
+            tag_filter: None,
             popup_manager: PopupManager::new(),
         }
     }
@@ -62,6 +65,48 @@ impl SceneHierarchy {
                         self.popup_manager.create_popup_active = true;
                     }
 
+                    // Tag filter
+                    let mut tags: Vec<String> = gui_state
+                        .scene_manager
+                        .iter()
+                        .flat_map(|manager| manager.scenes.values())
+                        .flat_map(|scene| scene.all_tags())
+                        .collect();
+                    tags.sort();
+                    tags.dedup();
+                    if self
+                        .tag_filter
+                        .as_ref()
+                        .is_some_and(|filter| !tags.contains(filter))
+                    {
+                        self.tag_filter = None;
+                    }
+                    let filter_label = match &self.tag_filter {
+                        Some(tag) => format!("🏷 {}", tag),
+                        None => "🏷".to_string(),
+                    };
+                    ui.menu_button(filter_label, |ui| {
+                        if ui
+                            .selectable_label(self.tag_filter.is_none(), "All entities")
+                            .clicked()
+                        {
+                            self.tag_filter = None;
+                            ui.close();
+                        }
+                        if tags.is_empty() {
+                            ui.label("No tags in this project");
+                        }
+                        for tag in tags {
+                            let selected = self.tag_filter.as_ref() == Some(&tag);
+                            if ui.selectable_label(selected, &tag).clicked() {
+                                self.tag_filter = Some(tag);
+                                ui.close();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Filter entities by tag");
+
                     // Search box (only shown when active)
                     if self.show_search {
                         ui.add(
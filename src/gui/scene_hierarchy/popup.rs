@@ -1,6 +1,8 @@
 use crate::ecs::SceneManager;
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
-use crate::gui::scene_hierarchy::predefined_entities::PREDEFINED_ENTITIES;
+use crate::gui::scene_hierarchy::predefined_entities::{
+    find_predefined_entity, PREDEFINED_ENTITIES,
+};
 use crate::gui::scene_hierarchy::utils;
 use crate::logger::LOGGER;
 use std::path::Path;
@@ -329,24 +331,9 @@ impl PopupManager {
         let new_entity_id = match predefined_type {
             "Empty" => scene.create_entity(name),
             "Camera" => scene.create_camera(name),
-            "Physics" => match scene.create_entity(name) {
-                Ok(entity_id) => {
-                    if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                        if let Some(predefined) =
-                            PREDEFINED_ENTITIES.iter().find(|e| e.name == "Physics")
-                        {
-                            for (attr_name, attr_type, attr_value) in predefined.attributes.iter() {
-                                let _ = entity.create_attribute(
-                                    attr_name,
-                                    attr_type.clone(),
-                                    attr_value.clone(),
-                                );
-                            }
-                        }
-                    }
-                    Ok(entity_id)
-                }
-                Err(e) => Err(e),
+            "Physics" => match find_predefined_entity("Physics") {
+                Some(predefined) => predefined.instantiate(scene, name, (0.0, 0.0)),
+                None => scene.create_entity(name),
             },
             _ => scene.create_entity(name),
         };
@@ -437,6 +424,10 @@ impl PopupManager {
                     if ui.button("Delete").clicked() {
                         match self.pending_delete.take() {
                             Some(PendingDelete::Scene(scene_id, _)) => {
+                                // Undo needs its contents to bring it back
+                                if let Err(e) = utils::read_deferred_scene(gui_state, scene_id) {
+                                    LOGGER.error(format!("Failed to read scene: {}", e));
+                                }
                                 if let Some(scene_manager) = &mut gui_state.scene_manager {
                                     match scene_manager.delete_scene(scene_id) {
                                         Ok(_) => {
@@ -561,29 +552,27 @@ impl PopupManager {
                                                 if let Ok(entity) = scene.get_entity_mut(entity_id)
                                                 {
                                                     match self.selected_resource_type.as_str() {
-                                                        "Images" => {
-                                                            // Check if image is already attached
+                                                        // Skip images already attached
+                                                        "Images"
                                                             if !entity
                                                                 .images
-                                                                .contains(resource_path)
-                                                            {
-                                                                entity
-                                                                    .images
-                                                                    .push(resource_path.clone());
-                                                                changed = true;
-                                                            }
+                                                                .contains(resource_path) =>
+                                                        {
+                                                            entity
+                                                                .images
+                                                                .push(resource_path.clone());
+                                                            changed = true;
                                                         }
-                                                        "Sounds" => {
-                                                            // Check if sound is already attached
+                                                        // Skip sounds already attached
+                                                        "Sounds"
                                                             if !entity
                                                                 .sounds
-                                                                .contains(resource_path)
-                                                            {
-                                                                entity
-                                                                    .sounds
-                                                                    .push(resource_path.clone());
-                                                                changed = true;
-                                                            }
+                                                                .contains(resource_path) =>
+                                                        {
+                                                            entity
+                                                                .sounds
+                                                                .push(resource_path.clone());
+                                                            changed = true;
                                                         }
                                                         "Scripts"
                                                             if entity.script.as_ref()
@@ -1,10 +1,39 @@
-use crate::ecs::{AttributeType, AttributeValue};
+use crate::ecs::{AttributeType, AttributeValue, Entity, Prefab, Scene};
+use uuid::Uuid;
 
+/// Built-in entity templates offered by the editor's Create popup.
 pub struct EntityDefinition {
     pub name: &'static str,
     pub attributes: &'static [(&'static str, AttributeType, AttributeValue)],
 }
 
+impl EntityDefinition {
+    /// The definition as a built-in prefab (not backed by a file).
+    pub fn to_prefab(&self) -> Result<Prefab, String> {
+        let mut entity = Entity::new(Uuid::new_v4(), self.name)?;
+        for (attr_name, attr_type, attr_value) in self.attributes.iter() {
+            entity.create_attribute(attr_name, attr_type.clone(), attr_value.clone())?;
+        }
+        Ok(Prefab::from_entity(&entity))
+    }
+
+    /// Spawn a plain entity from this definition into `scene`.
+    pub fn instantiate(
+        &self,
+        scene: &mut Scene,
+        name: &str,
+        position: (f32, f32),
+    ) -> Result<Uuid, String> {
+        let entity_id = scene.instantiate_prefab(&self.to_prefab()?, None, position)?;
+        scene.get_entity_mut(entity_id)?.change_entity_name(name)?;
+        Ok(entity_id)
+    }
+}
+
+pub fn find_predefined_entity(name: &str) -> Option<&'static EntityDefinition> {
+    PREDEFINED_ENTITIES.iter().find(|e| e.name == name)
+}
+
 pub const PREDEFINED_ENTITIES: &[EntityDefinition] = &[
     EntityDefinition {
         name: "Camera",
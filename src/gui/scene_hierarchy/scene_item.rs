@@ -1,9 +1,12 @@
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
 use crate::gui::scene_hierarchy::{
     entity_item::{EntityDisplay, EntityItem},
-    SceneHierarchy,
+    utils, SceneHierarchy,
 };
+use crate::logger::LOGGER;
+use crate::project_manager::ProjectManager;
 use egui::{Context, Ui};
+use std::path::Path;
 use uuid::Uuid;
 
 /// Lightweight per-frame view of a scene for the hierarchy tree.
@@ -11,6 +14,7 @@ struct SceneDisplay {
     id: Uuid,
     name: String,
     entities: Vec<EntityDisplay>,
+    deferred: bool, // Not read from disk yet
 }
 
 pub struct SceneItem;
@@ -45,6 +49,11 @@ impl SceneItem {
                             sound_names: entity.sounds.iter().map(|p| file_name(p)).collect(),
                             script_name: entity.script.as_deref().map(file_name),
                             script_path: entity.script.clone(),
+                            parent: entity.parent,
+                            prefab_source: entity.prefab.as_ref().map(|link| link.source.clone()),
+                            tags: entity.tags.clone(),
+                            enabled: entity.enabled,
+                            active: scene.is_active(*entity_id),
                         })
                         .collect();
                     entities.sort_by_key(|e| e.name.to_lowercase());
@@ -53,8 +62,20 @@ impl SceneItem {
                         id: *scene_id,
                         name: scene.name.clone(),
                         entities,
+                        deferred: false,
                     }
                 })
+                .chain(
+                    scene_manager
+                        .deferred_scenes
+                        .iter()
+                        .map(|(scene_id, name)| SceneDisplay {
+                            id: *scene_id,
+                            name: name.clone(),
+                            entities: Vec::new(),
+                            deferred: true,
+                        }),
+                )
                 .collect()
         } else {
             egui::Frame {
@@ -79,6 +100,10 @@ impl SceneItem {
         scenes.sort_by_key(|s| s.name.to_lowercase());
 
         for scene in &scenes {
+            if scene.deferred {
+                SceneItem::tree_item_deferred_scene(ui, scene, hierarchy, gui_state);
+                continue;
+            }
             let header_id = ui.make_persistent_id(scene.id);
             egui::collapsing_header::CollapsingState::load_with_default_open(ctx, header_id, true)
                 .show_header(ui, |ui| {
@@ -97,6 +122,67 @@ impl SceneItem {
         }
     }
 
+    fn add_prefab_instance(gui_state: &mut GuiState, scene_id: Uuid, source: &Path) {
+        let result =
+            ProjectManager::load_prefab(&gui_state.project_path, source).and_then(|prefab| {
+                gui_state
+                    .scene_manager
+                    .as_mut()
+                    .and_then(|manager| manager.get_scene_mut(scene_id))
+                    .ok_or_else(|| format!("Scene {} not found", scene_id))?
+                    .instantiate_prefab(&prefab, Some(source), (0.0, 0.0))
+            });
+        match result {
+            Ok(entity_id) => {
+                gui_state.selected_item = SelectedItem::Entity(scene_id, entity_id);
+                gui_state.scene_panel_selected_item =
+                    ScenePanelSelectedItem::Entity(scene_id, entity_id);
+                utils::save_project(gui_state);
+            }
+            Err(e) => LOGGER.error(format!("Failed to add prefab: {}", e)),
+        }
+    }
+
+    // A scene that hasn't been read yet: clicking it (or Load) reads it
+    fn tree_item_deferred_scene(
+        ui: &mut Ui,
+        scene: &SceneDisplay,
+        hierarchy: &mut SceneHierarchy,
+        gui_state: &mut GuiState,
+    ) {
+        let read = |gui_state: &mut GuiState| match utils::read_deferred_scene(gui_state, scene.id)
+        {
+            Ok(()) => {
+                gui_state.selected_item = SelectedItem::Scene(scene.id);
+                gui_state.scene_panel_selected_item = ScenePanelSelectedItem::Scene(scene.id);
+            }
+            Err(e) => LOGGER.error(format!("Failed to read scene: {}", e)),
+        };
+
+        ui.horizontal(|ui| {
+            let response = ui
+                .selectable_label(false, egui::RichText::new(&scene.name).weak())
+                .on_hover_text("Not loaded yet - click to load");
+            if response.clicked() {
+                read(gui_state);
+            }
+            response.context_menu(|ui| {
+                if ui.button("Load").clicked() {
+                    read(gui_state);
+                    ui.close();
+                }
+                if ui.button("Delete").clicked() {
+                    hierarchy.popup_manager.pending_delete =
+                        Some(crate::gui::scene_hierarchy::popup::PendingDelete::Scene(
+                            scene.id,
+                            scene.name.clone(),
+                        ));
+                    ui.close();
+                }
+            });
+        });
+    }
+
     fn tree_item_scene(
         ui: &mut Ui,
         scene_id: &Uuid,
@@ -131,11 +217,53 @@ impl SceneItem {
                         ));
                     ui.close();
                 }
+                ui.menu_button("Add Prefab", |ui| {
+                    let prefabs = ProjectManager::list_prefabs(&gui_state.project_path);
+                    if prefabs.is_empty() {
+                        ui.label("No prefabs in assets/prefabs");
+                    }
+                    for source in prefabs {
+                        let label = source
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        if ui.button(label).clicked() {
+                            SceneItem::add_prefab_instance(gui_state, *scene_id, &source);
+                            ui.close();
+                        }
+                    }
+                });
                 if ui.button("Set Active").clicked() {
                     if let Some(scene_manager) = &mut gui_state.scene_manager {
                         let _ = scene_manager.set_active_scene(*scene_id);
                     }
                 }
+                // Additive scenes play on top of the active one (HUD, managers)
+                let additive = gui_state
+                    .scene_manager
+                    .as_ref()
+                    .is_some_and(|manager| manager.additive_scenes.contains(scene_id));
+                let label = if additive {
+                    "Don't Load Additively"
+                } else {
+                    "Load Additively"
+                };
+                if ui.button(label).clicked() {
+                    if let Some(scene_manager) = &mut gui_state.scene_manager {
+                        let result = if additive {
+                            scene_manager.unload_scene(*scene_id)
+                        } else {
+                            scene_manager.load_scene_additive(*scene_id)
+                        };
+                        match result {
+                            Ok(()) => utils::save_project(gui_state),
+                            Err(e) => {
+                                LOGGER.error(format!("Failed to change scene loading: {}", e))
+                            }
+                        }
+                    }
+                    ui.close();
+                }
             });
         });
     }
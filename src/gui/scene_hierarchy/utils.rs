@@ -1,6 +1,7 @@
 use crate::gui::gui_state::{GuiState, ScenePanelSelectedItem, SelectedItem};
 use crate::logger::LOGGER;
 use crate::project_manager::ProjectManager;
+use uuid::Uuid;
 
 /// Persist the project AND record the state in the undo history.
 /// Every completed editor mutation should go through here.
@@ -30,6 +31,19 @@ pub fn save_project_without_commit(gui_state: &GuiState) {
     }
 }
 
+/// Read a scene the project left deferred (see
+/// `ProjectManager::load_scene_hierarchy`), into the undo history too.
+pub fn read_deferred_scene(gui_state: &mut GuiState, scene_id: Uuid) -> Result<(), String> {
+    let Some(scene_manager) = gui_state.scene_manager.as_mut() else {
+        return Ok(());
+    };
+    ProjectManager::load_deferred_scene(&gui_state.project_path, scene_manager, scene_id)?;
+    if let Some(scene) = scene_manager.get_scene(scene_id) {
+        gui_state.undo_stack.add_read_scene(scene_id, scene);
+    }
+    Ok(())
+}
+
 /// Restore the previous committed state (Ctrl+Z / Edit > Undo).
 pub fn perform_undo(gui_state: &mut GuiState) {
     if let Some(state) = gui_state.undo_stack.undo() {
@@ -1,10 +1,14 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
+use uuid::Uuid;
 
 use super::{parse_uuid, LuaScripting};
-use crate::ecs::{AttributeType, AttributeValue, SceneManager};
-use crate::gui::scene_hierarchy::predefined_entities::PREDEFINED_ENTITIES;
+use crate::ecs::{
+    AttributeError, AttributeType, AttributeValue, EntityQuery, Prefab, SceneManager,
+};
+use crate::gui::scene_hierarchy::predefined_entities::find_predefined_entity;
 use crate::project_manager::ProjectManager;
 
 impl LuaScripting {
@@ -61,27 +65,18 @@ impl LuaScripting {
                 let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
                     mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
                 })?;
-                let entity_id = scene.create_entity(&name).map_err(|e| {
-                    mlua::Error::external(format!(
-                        "Failed to create physical entity '{}': {}",
-                        name, e
-                    ))
-                })?;
+                // Default attributes come from the built-in Physics prefab
+                let entity_id = find_predefined_entity("Physics")
+                    .ok_or_else(|| "Physics prefab is missing".to_string())
+                    .and_then(|predefined| predefined.instantiate(scene, &name, (x, y)))
+                    .map_err(|e| {
+                        mlua::Error::external(format!(
+                            "Failed to create physical entity '{}': {}",
+                            name, e
+                        ))
+                    })?;
 
-                // Assign default attributes based on the predefined Physics entity
                 if let Ok(entity) = scene.get_entity_mut(entity_id) {
-                    if let Some(predefined) =
-                        PREDEFINED_ENTITIES.iter().find(|e| e.name == "Physics")
-                    {
-                        for (attr_name, attr_type, attr_value) in predefined.attributes.iter() {
-                            let _ = entity.create_attribute(
-                                attr_name,
-                                attr_type.clone(),
-                                attr_value.clone(),
-                            );
-                        }
-                    }
-
                     // Apply the requested spawn position
                     entity.set_position(x, y, z).map_err(|e| {
                         mlua::Error::external(format!("Failed to set spawn position: {}", e))
@@ -107,6 +102,45 @@ impl LuaScripting {
         )?;
         globals.set("create_physical_entity", create_physical_entity)?;
 
+        // spawn_prefab(scene_id, relative_path, x, y) -> root entity id.
+        // Prefab files are read once per session and cached.
+        let manager = Rc::clone(scene_manager);
+        let prefab_cache: RefCell<HashMap<PathBuf, Prefab>> = RefCell::new(HashMap::new());
+        let spawn_prefab = self.lua.create_function(
+            move |_, (scene_id, prefab_path, x, y): (String, String, f32, f32)| {
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let relative_path = PathBuf::from(&prefab_path);
+                if !prefab_cache.borrow().contains_key(&relative_path) {
+                    let project_path = ProjectManager::get_project_path().ok_or_else(|| {
+                        mlua::Error::external("No project is currently open".to_string())
+                    })?;
+                    let prefab =
+                        ProjectManager::load_prefab(&PathBuf::from(project_path), &relative_path)
+                            .map_err(mlua::Error::external)?;
+                    prefab_cache
+                        .borrow_mut()
+                        .insert(relative_path.clone(), prefab);
+                }
+                let cache = prefab_cache.borrow();
+                let prefab = &cache[&relative_path];
+
+                let mut manager = manager.borrow_mut();
+                let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+                let entity_id = scene
+                    .instantiate_prefab(prefab, Some(&relative_path), (x, y))
+                    .map_err(|e| {
+                        mlua::Error::external(format!(
+                            "Failed to spawn prefab '{}': {}",
+                            prefab_path, e
+                        ))
+                    })?;
+                Ok(entity_id.to_string())
+            },
+        )?;
+        globals.set("spawn_prefab", spawn_prefab)?;
+
         // Position setters
         macro_rules! register_setter {
             ($lua_name:literal, $method:ident) => {{
@@ -171,12 +205,13 @@ impl LuaScripting {
                 .create_function(move |_, (entity_id, image_path): (String, String)| {
                     let mut manager = manager.borrow_mut();
                     let entity_uuid = parse_uuid(&entity_id, "entity")?;
-                    let scene = manager
-                        .get_active_scene_mut()
-                        .ok_or_else(|| mlua::Error::external("No active scene found"))?;
-                    let entity = scene.get_entity_mut(entity_uuid).map_err(|e| {
-                        mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e))
-                    })?;
+                    let (_, entity) =
+                        manager.find_loaded_entity_mut(entity_uuid).ok_or_else(|| {
+                            mlua::Error::external(format!(
+                                "Entity '{}' not found in a loaded scene",
+                                entity_uuid
+                            ))
+                        })?;
                     let project_path = ProjectManager::get_project_path().ok_or_else(|| {
                         mlua::Error::external("No project is currently open".to_string())
                     })?;
@@ -197,12 +232,13 @@ impl LuaScripting {
                 .create_function(move |_, (entity_id, script_path): (String, String)| {
                     let mut manager = manager.borrow_mut();
                     let entity_uuid = parse_uuid(&entity_id, "entity")?;
-                    let scene = manager
-                        .get_active_scene_mut()
-                        .ok_or_else(|| mlua::Error::external("No active scene found"))?;
-                    let entity = scene.get_entity_mut(entity_uuid).map_err(|e| {
-                        mlua::Error::external(format!("Entity '{}' not found: {}", entity_uuid, e))
-                    })?;
+                    let (_, entity) =
+                        manager.find_loaded_entity_mut(entity_uuid).ok_or_else(|| {
+                            mlua::Error::external(format!(
+                                "Entity '{}' not found in a loaded scene",
+                                entity_uuid
+                            ))
+                        })?;
                     let project_path = ProjectManager::get_project_path().ok_or_else(|| {
                         mlua::Error::external("No project is currently open".to_string())
                     })?;
@@ -357,7 +393,9 @@ impl LuaScripting {
         // variables: get_attribute / set_attribute / has_attribute.
 
         // get_attribute(scene_id, entity_id, name)
-        //   -> number | boolean | string | {x, y} table, or nil if missing
+        //   -> number | boolean | string | {x, y[, z]} | {r, g, b, a} |
+        //      entity id | project-relative asset path | array | table,
+        //      or nil if missing (or an unset EntityRef)
         let manager = Rc::clone(scene_manager);
         let get_attribute = self.lua.create_function(
             move |lua, (scene_id, entity_id, name): (String, String, String)| {
@@ -379,25 +417,15 @@ impl LuaScripting {
                     return Ok(mlua::Value::Nil);
                 };
 
-                Ok(match value {
-                    AttributeValue::Integer(i) => mlua::Value::Integer(i as i64),
-                    AttributeValue::Float(f) => mlua::Value::Number(f as f64),
-                    AttributeValue::Boolean(b) => mlua::Value::Boolean(b),
-                    AttributeValue::String(s) => mlua::Value::String(lua.create_string(&s)?),
-                    AttributeValue::Vector2(x, y) => {
-                        let table = lua.create_table()?;
-                        table.set("x", x)?;
-                        table.set("y", y)?;
-                        mlua::Value::Table(table)
-                    }
-                })
+                attribute_value_to_lua(lua, &value)
             },
         )?;
         globals.set("get_attribute", get_attribute)?;
 
         // set_attribute(scene_id, entity_id, name, value)
-        //   The Lua value is coerced to the attribute's declared type;
-        //   a mismatch or a missing attribute raises an error.
+        //   The Lua value is coerced to the attribute's declared type
+        //   (losslessly, see AttributeValue::coerce_to); a mismatch or a
+        //   missing attribute raises an error.
         let manager = Rc::clone(scene_manager);
         let set_attribute = self.lua.create_function(
             move |_, (scene_id, entity_id, name, value): (String, String, String, mlua::Value)| {
@@ -416,45 +444,8 @@ impl LuaScripting {
                 let attr_id = attr.id;
                 let attr_type = attr.data_type.clone();
 
-                let type_error = |expected: &str, got: &mlua::Value| {
-                    mlua::Error::external(format!(
-                        "Attribute '{}' is {}, got Lua {}",
-                        name,
-                        expected,
-                        got.type_name()
-                    ))
-                };
-
-                let new_value = match attr_type {
-                    AttributeType::Float => match &value {
-                        mlua::Value::Number(n) => AttributeValue::Float(*n as f32),
-                        mlua::Value::Integer(i) => AttributeValue::Float(*i as f32),
-                        other => return Err(type_error("a Float", other)),
-                    },
-                    AttributeType::Integer => match &value {
-                        mlua::Value::Integer(i) => AttributeValue::Integer(*i as i32),
-                        mlua::Value::Number(n) if n.fract() == 0.0 => {
-                            AttributeValue::Integer(*n as i32)
-                        }
-                        other => return Err(type_error("an Integer", other)),
-                    },
-                    AttributeType::Boolean => match &value {
-                        mlua::Value::Boolean(b) => AttributeValue::Boolean(*b),
-                        other => return Err(type_error("a Boolean", other)),
-                    },
-                    AttributeType::String => match &value {
-                        mlua::Value::String(s) => AttributeValue::String(s.to_str()?.to_string()),
-                        other => return Err(type_error("a String", other)),
-                    },
-                    AttributeType::Vector2 => match &value {
-                        mlua::Value::Table(t) => {
-                            let x: f32 = t.get("x").or_else(|_| t.get(1))?;
-                            let y: f32 = t.get("y").or_else(|_| t.get(2))?;
-                            AttributeValue::Vector2(x, y)
-                        }
-                        other => return Err(type_error("a Vector2 ({x, y} table)", other)),
-                    },
-                };
+                let new_value = lua_to_attribute_value(&value, &attr_type)
+                    .map_err(|e| mlua::Error::external(format!("Attribute '{}': {}", name, e)))?;
 
                 entity
                     .modify_attribute(attr_id, None, None, Some(new_value))
@@ -497,6 +488,451 @@ impl LuaScripting {
                 })?;
         globals.set("get_entity_name", get_entity_name)?;
 
+        // set_parent(scene_id, entity_id, parent_id) - pass nil as parent_id to
+        // detach. The entity keeps its world position.
+        let manager = Rc::clone(scene_manager);
+        let set_parent = self.lua.create_function(
+            move |_, (scene_id, entity_id, parent_id): (String, String, Option<String>)| {
+                let mut manager = manager.borrow_mut();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                let parent_uuid = parent_id
+                    .map(|id| parse_uuid(&id, "parent entity"))
+                    .transpose()?;
+                let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+                scene
+                    .set_parent(entity_uuid, parent_uuid)
+                    .map_err(|e| mlua::Error::external(format!("Failed to set parent: {}", e)))
+            },
+        )?;
+        globals.set("set_parent", set_parent)?;
+
+        // get_parent(scene_id, entity_id) -> parent id string, or nil for roots
+        let manager = Rc::clone(scene_manager);
+        let get_parent =
+            self.lua
+                .create_function(move |_, (scene_id, entity_id): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                    Ok(manager
+                        .get_scene(scene_uuid)
+                        .and_then(|scene| scene.get_parent(entity_uuid))
+                        .map(|id| id.to_string()))
+                })?;
+        globals.set("get_parent", get_parent)?;
+
+        // get_children(scene_id, entity_id) -> array of direct child id strings
+        let manager = Rc::clone(scene_manager);
+        let get_children =
+            self.lua
+                .create_function(move |lua, (scene_id, entity_id): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                    let table = lua.create_table()?;
+                    if let Some(scene) = manager.get_scene(scene_uuid) {
+                        for (index, id) in scene.get_children(entity_uuid).iter().enumerate() {
+                            table.set(index + 1, id.to_string())?;
+                        }
+                    }
+                    Ok(table)
+                })?;
+        globals.set("get_children", get_children)?;
+
+        // get_world_position(scene_id, entity_id) -> x, y (resolved through
+        // the parent chain; get_attribute "x"/"y" returns the local offset)
+        let manager = Rc::clone(scene_manager);
+        let get_world_position =
+            self.lua
+                .create_function(move |_, (scene_id, entity_id): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                    let scene = manager.get_scene(scene_uuid).ok_or_else(|| {
+                        mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                    })?;
+                    let (x, y, _) = scene
+                        .world_position(entity_uuid)
+                        .map_err(mlua::Error::external)?;
+                    Ok((x, y))
+                })?;
+        globals.set("get_world_position", get_world_position)?;
+
+        // === Enable / disable ===
+
+        // set_enabled(scene_id, entity_id, enabled): disabled entities stay
+        // in the scene but aren't drawn, simulated or scripted
+        let manager = Rc::clone(scene_manager);
+        let set_enabled = self.lua.create_function(
+            move |_, (scene_id, entity_id, enabled): (String, String, bool)| {
+                let mut manager = manager.borrow_mut();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+                scene
+                    .get_entity_mut(entity_uuid)
+                    .map_err(mlua::Error::external)?
+                    .set_enabled(enabled);
+                Ok(())
+            },
+        )?;
+        globals.set("set_enabled", set_enabled)?;
+
+        // is_enabled(scene_id, entity_id) -> bool: the entity's own flag
+        // (false for a missing entity)
+        let manager = Rc::clone(scene_manager);
+        let is_enabled =
+            self.lua
+                .create_function(move |_, (scene_id, entity_id): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                    Ok(manager
+                        .get_scene(scene_uuid)
+                        .and_then(|scene| scene.get_entity(entity_uuid).ok())
+                        .is_some_and(|entity| entity.is_enabled()))
+                })?;
+        globals.set("is_enabled", is_enabled)?;
+
+        // === Tags & queries ===
+
+        // add_tag / remove_tag(scene_id, entity_id, tag); remove_tag
+        // returns false if the entity didn't have the tag
+        let manager = Rc::clone(scene_manager);
+        let add_tag = self.lua.create_function(
+            move |_, (scene_id, entity_id, tag): (String, String, String)| {
+                let mut manager = manager.borrow_mut();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+                let entity = scene
+                    .get_entity_mut(entity_uuid)
+                    .map_err(mlua::Error::external)?;
+                // Tagging twice is harmless from a script's point of view
+                if !entity.has_tag(tag.trim()) {
+                    entity.add_tag(&tag).map_err(mlua::Error::external)?;
+                }
+                Ok(())
+            },
+        )?;
+        globals.set("add_tag", add_tag)?;
+
+        let manager = Rc::clone(scene_manager);
+        let remove_tag = self.lua.create_function(
+            move |_, (scene_id, entity_id, tag): (String, String, String)| {
+                let mut manager = manager.borrow_mut();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                let scene = manager.get_scene_mut(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+                let entity = scene
+                    .get_entity_mut(entity_uuid)
+                    .map_err(mlua::Error::external)?;
+                Ok(entity.remove_tag(&tag).is_ok())
+            },
+        )?;
+        globals.set("remove_tag", remove_tag)?;
+
+        // has_tag(scene_id, entity_id, tag) -> bool (false for a missing entity)
+        let manager = Rc::clone(scene_manager);
+        let has_tag = self.lua.create_function(
+            move |_, (scene_id, entity_id, tag): (String, String, String)| {
+                let manager = manager.borrow();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                Ok(manager
+                    .get_scene(scene_uuid)
+                    .and_then(|scene| scene.get_entity(entity_uuid).ok())
+                    .is_some_and(|entity| entity.has_tag(&tag)))
+            },
+        )?;
+        globals.set("has_tag", has_tag)?;
+
+        // find_entities_with_tag(scene_id, tag) -> array of entity ids
+        let manager = Rc::clone(scene_manager);
+        let find_entities_with_tag =
+            self.lua
+                .create_function(move |lua, (scene_id, tag): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let scene = manager.get_scene(scene_uuid).ok_or_else(|| {
+                        mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                    })?;
+                    ids_to_lua(lua, &scene.find_entities_with_tag(&tag))
+                })?;
+        globals.set("find_entities_with_tag", find_entities_with_tag)?;
+
+        // find_entity_by_name(scene_id, name) -> entity id or nil
+        let manager = Rc::clone(scene_manager);
+        let find_entity_by_name =
+            self.lua
+                .create_function(move |_, (scene_id, name): (String, String)| {
+                    let manager = manager.borrow();
+                    let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                    let scene = manager.get_scene(scene_uuid).ok_or_else(|| {
+                        mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                    })?;
+                    Ok(scene.find_entity_by_name(&name).map(|id| id.to_string()))
+                })?;
+        globals.set("find_entity_by_name", find_entity_by_name)?;
+
+        // find_entities(scene_id, { tag = "enemy", tags = {...}, has = {...},
+        //   name = "pipe_*", region = { x1, y1, x2, y2 } }) -> array of ids
+        //   All fields are optional; every given one must match.
+        let manager = Rc::clone(scene_manager);
+        let find_entities = self.lua.create_function(
+            move |lua, (scene_id, filter): (String, Option<mlua::Table>)| {
+                let manager = manager.borrow();
+                let scene_uuid = parse_uuid(&scene_id, "scene")?;
+                let scene = manager.get_scene(scene_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!("Scene '{}' not found", scene_uuid))
+                })?;
+
+                let mut query = EntityQuery::new();
+                if let Some(filter) = filter {
+                    if let Some(tag) = filter.get::<Option<String>>("tag")? {
+                        query = query.with_tag(&tag);
+                    }
+                    for tag in filter
+                        .get::<Option<Vec<String>>>("tags")?
+                        .unwrap_or_default()
+                    {
+                        query = query.with_tag(&tag);
+                    }
+                    for name in filter
+                        .get::<Option<Vec<String>>>("has")?
+                        .unwrap_or_default()
+                    {
+                        query = query.with_attribute(&name);
+                    }
+                    if let Some(pattern) = filter.get::<Option<String>>("name")? {
+                        query = query.with_name(&pattern);
+                    }
+                    if let Some(region) = filter.get::<Option<Vec<f32>>>("region")? {
+                        let [x1, y1, x2, y2] = region[..] else {
+                            return Err(mlua::Error::external("region must be { x1, y1, x2, y2 }"));
+                        };
+                        query = query.in_region((x1, y1), (x2, y2));
+                    }
+                }
+                ids_to_lua(lua, &scene.query(&query))
+            },
+        )?;
+        globals.set("find_entities", find_entities)?;
+
+        // === Loaded scenes ===
+        // get_loaded_scenes() -> array of scene ids, active scene first
+        let manager = Rc::clone(scene_manager);
+        let get_loaded_scenes = self
+            .lua
+            .create_function(move |lua, ()| ids_to_lua(lua, &manager.borrow().loaded_scenes()))?;
+        globals.set("get_loaded_scenes", get_loaded_scenes)?;
+
+        // find_entity_in_loaded_scenes(name) -> scene id, entity id (or nil, nil)
+        let manager = Rc::clone(scene_manager);
+        let find_entity_in_loaded_scenes = self.lua.create_function(move |_, name: String| {
+            let manager = manager.borrow();
+            let found = manager.loaded_scenes().into_iter().find_map(|scene_id| {
+                let entity_id = manager.get_scene(scene_id)?.find_entity_by_name(&name)?;
+                Some((scene_id.to_string(), entity_id.to_string()))
+            });
+            Ok(found.unzip())
+        })?;
+        globals.set("find_entity_in_loaded_scenes", find_entity_in_loaded_scenes)?;
+
+        // get_entity_scene(entity_id) -> id of the loaded scene holding it, or nil
+        let manager = Rc::clone(scene_manager);
+        let get_entity_scene = self.lua.create_function(move |_, entity_id: String| {
+            let entity_uuid = parse_uuid(&entity_id, "entity")?;
+            Ok(manager
+                .borrow()
+                .find_loaded_entity(entity_uuid)
+                .map(|(scene_id, _)| scene_id.to_string()))
+        })?;
+        globals.set("get_entity_scene", get_entity_scene)?;
+
         Ok(())
     }
 }
+
+// Entity ids as a Lua array of strings
+pub(super) fn ids_to_lua(lua: &mlua::Lua, ids: &[Uuid]) -> mlua::Result<mlua::Table> {
+    let table = lua.create_table()?;
+    for (index, id) in ids.iter().enumerate() {
+        table.set(index + 1, id.to_string())?;
+    }
+    Ok(table)
+}
+
+// Attribute value as a Lua value (see get_attribute)
+pub(super) fn attribute_value_to_lua(
+    lua: &mlua::Lua,
+    value: &AttributeValue,
+) -> mlua::Result<mlua::Value> {
+    Ok(match value {
+        AttributeValue::Integer(i) => mlua::Value::Integer(*i as i64),
+        AttributeValue::Float(f) => mlua::Value::Number(*f as f64),
+        AttributeValue::Boolean(b) => mlua::Value::Boolean(*b),
+        AttributeValue::String(s) => mlua::Value::String(lua.create_string(s)?),
+        AttributeValue::Vector2(x, y) => {
+            let table = lua.create_table()?;
+            table.set("x", *x)?;
+            table.set("y", *y)?;
+            mlua::Value::Table(table)
+        }
+        AttributeValue::Vector3(x, y, z) => {
+            let table = lua.create_table()?;
+            table.set("x", *x)?;
+            table.set("y", *y)?;
+            table.set("z", *z)?;
+            mlua::Value::Table(table)
+        }
+        AttributeValue::Color(r, g, b, a) => {
+            let table = lua.create_table()?;
+            table.set("r", *r)?;
+            table.set("g", *g)?;
+            table.set("b", *b)?;
+            table.set("a", *a)?;
+            mlua::Value::Table(table)
+        }
+        AttributeValue::EntityRef(Some(id)) => {
+            mlua::Value::String(lua.create_string(id.to_string())?)
+        }
+        AttributeValue::EntityRef(None) => mlua::Value::Nil,
+        AttributeValue::AssetRef(path) => {
+            // Scripts use project-relative paths (like add_image)
+            let relative = ProjectManager::get_project_path()
+                .and_then(|project| path.strip_prefix(project).ok().map(PathBuf::from))
+                .unwrap_or_else(|| path.clone());
+            mlua::Value::String(lua.create_string(relative.to_string_lossy().as_ref())?)
+        }
+        AttributeValue::List(items) => {
+            let table = lua.create_table()?;
+            for (index, item) in items.iter().enumerate() {
+                table.set(index + 1, attribute_value_to_lua(lua, item)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+        AttributeValue::Map(entries) => {
+            let table = lua.create_table()?;
+            for (key, item) in entries {
+                table.set(key.as_str(), attribute_value_to_lua(lua, item)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+    })
+}
+
+// Convert a Lua value to an attribute of type `target`. Tables are read
+// by the shape the target expects ({x, y}, {r, g, b[, a]}, arrays, ...);
+// everything else maps to its natural AttributeValue and then goes through
+// a lossless coercion.
+fn lua_to_attribute_value(
+    value: &mlua::Value,
+    target: &AttributeType,
+) -> Result<AttributeValue, AttributeError> {
+    let unsupported = || AttributeError::Parse {
+        input: format!("Lua {}", value.type_name()),
+        expected: target.clone(),
+    };
+    let field = |t: &mlua::Table, key: &str, index: usize| -> Result<f32, AttributeError> {
+        t.get(key)
+            .or_else(|_| t.get(index))
+            .map_err(|_| unsupported())
+    };
+
+    match (target, value) {
+        (AttributeType::Vector2, mlua::Value::Table(t)) => {
+            return Ok(AttributeValue::Vector2(
+                field(t, "x", 1)?,
+                field(t, "y", 2)?,
+            ));
+        }
+        (AttributeType::Vector3, mlua::Value::Table(t)) => {
+            return Ok(AttributeValue::Vector3(
+                field(t, "x", 1)?,
+                field(t, "y", 2)?,
+                field(t, "z", 3)?,
+            ));
+        }
+        (AttributeType::Color, mlua::Value::Table(t)) => {
+            let a = field(t, "a", 4).unwrap_or(1.0);
+            return Ok(AttributeValue::Color(
+                field(t, "r", 1)?,
+                field(t, "g", 2)?,
+                field(t, "b", 3)?,
+                a,
+            ));
+        }
+        // Lua numbers are i64 or f64: checked at that precision, not after
+        // rounding to f32 (or i32). Other targets refuse them below.
+        (AttributeType::Integer | AttributeType::Float, mlua::Value::Integer(i)) => {
+            if *i as f64 as i128 != *i as i128 {
+                return Err(AttributeError::Lossy {
+                    value: i.to_string(),
+                    target: target.clone(),
+                });
+            }
+            return AttributeValue::coerce_number(*i as f64, target);
+        }
+        (AttributeType::Integer | AttributeType::Float, mlua::Value::Number(n)) => {
+            return AttributeValue::coerce_number(*n, target);
+        }
+        (AttributeType::EntityRef, mlua::Value::Nil) => return Ok(AttributeValue::EntityRef(None)),
+        (AttributeType::EntityRef, mlua::Value::String(s)) => {
+            return Uuid::parse_str(s.to_str().map_err(|_| unsupported())?.as_ref())
+                .map(|id| AttributeValue::EntityRef(Some(id)))
+                .map_err(|_| unsupported());
+        }
+        (AttributeType::AssetRef, mlua::Value::String(s)) => {
+            // Relative paths are joined onto the open project's root
+            let path = PathBuf::from(s.to_str().map_err(|_| unsupported())?.to_string());
+            let path = match ProjectManager::get_project_path() {
+                Some(project) if path.is_relative() => PathBuf::from(project).join(path),
+                _ => path,
+            };
+            return Ok(AttributeValue::AssetRef(path));
+        }
+        _ => {}
+    }
+
+    lua_to_natural_value(value)
+        .ok_or_else(unsupported)?
+        .coerce_to(target)
+}
+
+// The AttributeValue a Lua value maps to on its own: tables with a
+// sequence part become Lists, other tables Maps (string keys only)
+fn lua_to_natural_value(value: &mlua::Value) -> Option<AttributeValue> {
+    Some(match value {
+        mlua::Value::Integer(i) => match i32::try_from(*i) {
+            Ok(i) => AttributeValue::Integer(i),
+            Err(_) => AttributeValue::Float(*i as f32),
+        },
+        mlua::Value::Number(n) => AttributeValue::Float(*n as f32),
+        mlua::Value::Boolean(b) => AttributeValue::Boolean(*b),
+        mlua::Value::String(s) => AttributeValue::String(s.to_str().ok()?.to_string()),
+        mlua::Value::Table(t) if t.raw_len() > 0 => AttributeValue::List(
+            t.sequence_values::<mlua::Value>()
+                .map(|item| item.ok().and_then(|item| lua_to_natural_value(&item)))
+                .collect::<Option<Vec<_>>>()?,
+        ),
+        mlua::Value::Table(t) => AttributeValue::Map(
+            t.pairs::<String, mlua::Value>()
+                .map(|pair| {
+                    let (key, item) = pair.ok()?;
+                    Some((key, lua_to_natural_value(&item)?))
+                })
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
@@ -8,16 +8,20 @@ use mlua::{Function as LuaFunction, Lua};
 use uuid::Uuid;
 
 use crate::audio_engine::AudioEngine;
-use crate::ecs::SceneManager;
+use crate::ecs::{SceneEvent, SceneManager};
+use crate::game_runtime::{SceneRequest, SceneTransition};
 use crate::input_handler::InputHandler;
 use crate::logger::LOGGER;
-use crate::physics_engine::PhysicsEngine;
+use crate::physics_engine::{ContactEvent, PhysicsEngine};
 
 mod audio_bindings;
 mod ecs_bindings;
 mod input_bindings;
 mod physics_bindings;
 
+// on_attribute_changed() callbacks, per entity and attribute name
+type AttributeObservers = HashMap<(Uuid, String), Vec<LuaFunction>>;
+
 /// A compiled script's functions, cached for the duration of a play session.
 /// `modified` allows hot-reloading when the file changes on disk.
 #[derive(Clone)]
@@ -26,6 +30,13 @@ struct CachedScript {
     update_fn: LuaFunction,
     init_fn: Option<LuaFunction>,
     on_collision_fn: Option<LuaFunction>,
+    on_collision_end_fn: Option<LuaFunction>,
+    on_trigger_enter_fn: Option<LuaFunction>,
+    on_trigger_exit_fn: Option<LuaFunction>,
+    on_enable_fn: Option<LuaFunction>,
+    on_disable_fn: Option<LuaFunction>,
+    on_scene_enter_fn: Option<LuaFunction>,
+    on_scene_exit_fn: Option<LuaFunction>,
 }
 
 /// Lua scripting engine.
@@ -51,8 +62,20 @@ struct CachedScript {
 /// Script lifecycle hooks (all optional except `update`):
 /// - `init(scene_id, entity_id)` - once per entity, before its first update
 /// - `update(scene_id, entity_id)` - every rendered frame
-/// - `on_collision(scene_id, entity_id, other_id)` - when a contact with
-///   another physics entity begins (fires once per new contact)
+/// - `on_collision(scene_id, entity_id, other_id)` /
+///   `on_collision_end(...)` - when a contact with another physics entity
+///   begins / ends (once per contact, see `dispatch_collision_events`)
+/// - `on_trigger_enter(scene_id, entity_id, other_id)` /
+///   `on_trigger_exit(...)` - when an entity enters / leaves a sensor
+///   (`is_sensor`); the sensor's script gets it too
+/// - `on_enable(scene_id, entity_id)` / `on_disable(scene_id, entity_id)` -
+///   at the next frame after the entity became active / inactive (its own
+///   `enabled` flag or an ancestor's). Inactive entities get no other calls,
+///   so one that starts disabled gets its `init` just before `on_enable`.
+/// - `on_scene_exit(scene_id, entity_id)` - when the runtime leaves or
+///   unloads the scene (see `load_scene`), before it's torn down
+/// - `on_scene_enter(scene_id, entity_id)` - right after the runtime switched
+///   to the scene or loaded it additively (after the entity's `init`)
 pub struct LuaScripting {
     pub lua: Lua,
     accumulated_time: f32,
@@ -61,10 +84,24 @@ pub struct LuaScripting {
     physics_engine: Option<Rc<RefCell<PhysicsEngine>>>,
     // Entities whose init() has already run this session
     initialized_entities: HashSet<Uuid>,
-    // Contact sets from the previous frame, for edge-triggered on_collision
-    previous_contacts: HashMap<Uuid, HashSet<Uuid>>,
+    // Active state of scripted entities at their last frame, for
+    // edge-triggered on_enable/on_disable
+    previous_active: HashMap<Uuid, bool>,
     // Set by the end_game() binding; polled by the runtime each frame
     game_stop_requested: Rc<Cell<bool>>,
+    // Queued by load_scene() / load_scene_additive() / unload_scene();
+    // drained by the runtime each frame
+    scene_requests: Rc<RefCell<Vec<SceneRequest>>>,
+    attribute_observers: Rc<RefCell<AttributeObservers>>,
+}
+
+// Scene given to a Lua binding by id or by name
+fn resolve_scene(manager: &SceneManager, target: &str) -> Result<Uuid, mlua::Error> {
+    Uuid::parse_str(target)
+        .ok()
+        .filter(|id| manager.has_scene(*id))
+        .or_else(|| manager.get_scene_id_by_name(target))
+        .ok_or_else(|| mlua::Error::external(format!("Scene '{}' not found", target)))
 }
 
 pub(crate) fn parse_uuid(value: &str, what: &str) -> Result<Uuid, mlua::Error> {
@@ -87,8 +124,10 @@ impl LuaScripting {
             scene_manager: None,
             physics_engine: None,
             initialized_entities: HashSet::new(),
-            previous_contacts: HashMap::new(),
+            previous_active: HashMap::new(),
             game_stop_requested: Rc::new(Cell::new(false)),
+            scene_requests: Rc::new(RefCell::new(Vec::new())),
+            attribute_observers: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -104,11 +143,13 @@ impl LuaScripting {
         self.lua = Lua::new();
         self.script_cache.clear();
         self.initialized_entities.clear();
-        self.previous_contacts.clear();
+        self.previous_active.clear();
         self.accumulated_time = 0.0;
         self.scene_manager = Some(Rc::clone(&scene_manager));
         self.physics_engine = Some(Rc::clone(&physics_engine));
         self.game_stop_requested.set(false);
+        self.scene_requests.borrow_mut().clear();
+        self.attribute_observers.borrow_mut().clear();
 
         let globals = self.lua.globals();
         globals.set("accumulated_time", 0.0)?;
@@ -129,6 +170,75 @@ impl LuaScripting {
         })?;
         globals.set("end_game", end_game)?;
 
+        // load_scene(name_or_id, [transition, duration]): switch the active
+        // scene at the end of the frame. transition is "cut" (default),
+        // "fade" or "crossfade"; duration in seconds (default 0.5). The last
+        // request in a frame wins.
+        let requests = Rc::clone(&self.scene_requests);
+        let manager = Rc::clone(&scene_manager);
+        let load_scene = self.lua.create_function(
+            move |_, (target, transition, duration): (String, Option<String>, Option<f32>)| {
+                let scene_id = resolve_scene(&manager.borrow(), &target)?;
+                let duration = duration.unwrap_or(0.5).max(0.0);
+                let transition = match transition.as_deref().unwrap_or("cut") {
+                    "cut" => SceneTransition::Cut,
+                    "fade" => SceneTransition::Fade(duration),
+                    "crossfade" => SceneTransition::Crossfade(duration),
+                    other => {
+                        return Err(mlua::Error::external(format!(
+                            "Unknown transition '{}' (expected \"cut\", \"fade\" or \"crossfade\")",
+                            other
+                        )))
+                    }
+                };
+                requests
+                    .borrow_mut()
+                    .push(SceneRequest::Load(scene_id, transition));
+                Ok(())
+            },
+        )?;
+        globals.set("load_scene", load_scene)?;
+
+        // load_scene_additive(name_or_id) / unload_scene(name_or_id): load
+        // a scene on top of the active one, or unload it again, at the end
+        // of the frame
+        let requests = Rc::clone(&self.scene_requests);
+        let manager = Rc::clone(&scene_manager);
+        let load_scene_additive = self.lua.create_function(move |_, target: String| {
+            let scene_id = resolve_scene(&manager.borrow(), &target)?;
+            requests
+                .borrow_mut()
+                .push(SceneRequest::LoadAdditive(scene_id));
+            Ok(())
+        })?;
+        globals.set("load_scene_additive", load_scene_additive)?;
+
+        let requests = Rc::clone(&self.scene_requests);
+        let manager = Rc::clone(&scene_manager);
+        let unload_scene = self.lua.create_function(move |_, target: String| {
+            let scene_id = resolve_scene(&manager.borrow(), &target)?;
+            requests.borrow_mut().push(SceneRequest::Unload(scene_id));
+            Ok(())
+        })?;
+        globals.set("unload_scene", unload_scene)?;
+
+        // on_attribute_changed(entity_id, name, fn): call
+        // fn(entity_id, name, value, old_value) whenever the attribute's value
+        // changes (see dispatch_scene_events)
+        let observers = Rc::clone(&self.attribute_observers);
+        let on_attribute_changed = self.lua.create_function(
+            move |_, (entity_id, name, callback): (String, String, LuaFunction)| {
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                observers
+                    .borrow_mut()
+                    .entry((entity_uuid, name))
+                    .or_default()
+                    .push(callback);
+                Ok(())
+            },
+        )?;
+        globals.set("on_attribute_changed", on_attribute_changed)?;
+
         self.register_physics_bindings(&physics_engine, &scene_manager)?;
         self.register_input_bindings(&input_handler)?;
         self.register_ecs_bindings(&scene_manager)?;
@@ -144,6 +254,80 @@ impl LuaScripting {
         self.game_stop_requested.replace(false)
     }
 
+    /// Scene changes requested by scripts since the last call, in the order
+    /// they were made. Clears the queue.
+    pub fn take_scene_requests(&self) -> Vec<SceneRequest> {
+        self.scene_requests.take()
+    }
+
+    /// Forget per-entity script state (whether `init` ran, enabled
+    /// tracking) for entities whose scene was unloaded or started
+    /// over, so their scripts start over too if they come back.
+    pub fn forget_entities(&mut self, entity_ids: &[Uuid]) {
+        for id in entity_ids {
+            self.initialized_entities.remove(id);
+            self.previous_active.remove(id);
+        }
+        self.attribute_observers
+            .borrow_mut()
+            .retain(|(entity_id, _), _| !entity_ids.contains(entity_id));
+    }
+
+    /// Call the `on_attribute_changed` observers of every attribute change
+    /// in `events` (drained from the scene manager by the runtime once per
+    /// frame), in order. Observers of deleted entities are dropped. Changes
+    /// the observers make are seen the next time events are dispatched.
+    pub fn dispatch_scene_events(&mut self, events: &[SceneEvent]) {
+        for event in events {
+            let (entity_id, name, new_value, old_value) = match event {
+                SceneEvent::AttributeChanged {
+                    entity_id,
+                    name,
+                    old_value,
+                    new_value,
+                    ..
+                } => (*entity_id, name, new_value, old_value),
+                SceneEvent::EntityDeleted(entity_id) => {
+                    self.attribute_observers
+                        .borrow_mut()
+                        .retain(|(id, _), _| id != entity_id);
+                    continue;
+                }
+                _ => continue,
+            };
+            // Cloned so observers can register more observers
+            let Some(callbacks) = self
+                .attribute_observers
+                .borrow()
+                .get(&(entity_id, name.clone()))
+                .cloned()
+            else {
+                continue;
+            };
+            let to_lua = |value| ecs_bindings::attribute_value_to_lua(&self.lua, value);
+            let (new_value, old_value) = match (to_lua(new_value), to_lua(old_value)) {
+                (Ok(new_value), Ok(old_value)) => (new_value, old_value),
+                (Err(e), _) | (_, Err(e)) => {
+                    LOGGER.error(format!("Failed to convert attribute '{}': {}", name, e));
+                    continue;
+                }
+            };
+            for callback in callbacks {
+                if let Err(e) = callback.call::<()>((
+                    entity_id.to_string(),
+                    name.as_str(),
+                    new_value.clone(),
+                    old_value.clone(),
+                )) {
+                    LOGGER.error(format!(
+                        "on_attribute_changed callback error for entity {} ({}): {}",
+                        entity_id, name, e
+                    ));
+                }
+            }
+        }
+    }
+
     /// Increment the shared game clock and expose it to scripts.
     pub fn update_global_time(&mut self, delta_time: f32) -> Result<(), String> {
         self.accumulated_time += delta_time;
@@ -163,7 +347,9 @@ impl LuaScripting {
         Ok(())
     }
 
-    /// Run the `update` function of every scripted entity in the scene.
+    /// Run the `update` function of every active scripted entity in the
+    /// scene, firing `on_enable`/`on_disable` first for entities whose active
+    /// state changed since the last frame.
     ///
     /// The entity list is snapshotted first, so scripts can safely add or
     /// remove entities while running. A failing script is logged and skipped
@@ -189,17 +375,24 @@ impl LuaScripting {
         };
 
         for (entity_id, script_path) in scripts {
-            // The entity may have been removed by a script earlier this frame
-            {
+            // The entity may have been removed (or enabled/disabled) by a
+            // script earlier this frame
+            let active = {
                 let manager = scene_manager.borrow();
                 match manager.get_scene(active_scene_id) {
                     Some(scene) => {
                         if !scene.entities.contains_key(&entity_id) {
                             continue;
                         }
+                        scene.is_active(entity_id)
                     }
                     None => break,
                 }
+            };
+            let was_active = self.previous_active.insert(entity_id, active);
+            let changed = was_active.is_some_and(|was| was != active);
+            if !active && !changed {
+                continue;
             }
 
             let script = match self.get_or_load_script(&script_path) {
@@ -210,20 +403,27 @@ impl LuaScripting {
                 }
             };
 
-            // init(scene_id, entity_id): once per entity, before first update
-            if self.initialized_entities.insert(entity_id) {
-                if let Some(init_fn) = &script.init_fn {
-                    if let Err(e) =
-                        init_fn.call::<()>((active_scene_id.to_string(), entity_id.to_string()))
-                    {
-                        LOGGER.error(format!(
-                            "Script init() error for entity {} ({}): {}",
-                            entity_id,
-                            script_path.display(),
-                            e
-                        ));
-                    }
-                }
+            // An entity that started out disabled gets its init() before
+            // the on_enable() of its first frame
+            if active {
+                self.ensure_initialized(active_scene_id, entity_id, &script, &script_path);
+            }
+            if changed {
+                let (hook, hook_name) = if active {
+                    (&script.on_enable_fn, "on_enable")
+                } else {
+                    (&script.on_disable_fn, "on_disable")
+                };
+                call_entity_hook(
+                    hook.as_ref(),
+                    hook_name,
+                    active_scene_id,
+                    entity_id,
+                    &script_path,
+                );
+            }
+            if !active {
+                continue;
             }
 
             if let Err(e) = script
@@ -242,10 +442,95 @@ impl LuaScripting {
         Ok(())
     }
 
-    /// Fire `on_collision(scene_id, entity_id, other_id)` for every scripted
-    /// entity whose contact set gained a new entity since the last call.
-    /// Called by the runtime after the physics step.
-    pub fn dispatch_collision_events(&mut self, active_scene_id: Uuid) -> Result<(), String> {
+    /// Fire `on_scene_exit` for every active scripted entity in the scene.
+    /// Called by the runtime before it leaves the scene.
+    pub fn dispatch_scene_exit(&mut self, scene_id: Uuid) -> Result<(), String> {
+        self.dispatch_scene_hook(scene_id, false)
+    }
+
+    /// Fire `on_scene_enter` for every active scripted entity in the scene,
+    /// running `init` first for entities that haven't had it yet. Called by
+    /// the runtime right after switching to the scene.
+    pub fn dispatch_scene_enter(&mut self, scene_id: Uuid) -> Result<(), String> {
+        self.dispatch_scene_hook(scene_id, true)
+    }
+
+    fn dispatch_scene_hook(&mut self, scene_id: Uuid, entering: bool) -> Result<(), String> {
+        let scene_manager = self
+            .scene_manager
+            .clone()
+            .ok_or_else(|| "Lua session not started".to_string())?;
+
+        let scripts: Vec<(Uuid, PathBuf)> = {
+            let manager = scene_manager.borrow();
+            let scene = manager
+                .get_scene(scene_id)
+                .ok_or_else(|| format!("Scene {} not found", scene_id))?;
+            scene
+                .entities
+                .iter()
+                .filter(|(id, _)| scene.is_active(**id))
+                .filter_map(|(id, entity)| entity.script.clone().map(|path| (*id, path)))
+                .collect()
+        };
+
+        for (entity_id, script_path) in scripts {
+            let script = match self.get_or_load_script(&script_path) {
+                Ok(f) => f,
+                Err(e) => {
+                    LOGGER.error(format!("Script load error for entity {}: {}", entity_id, e));
+                    continue;
+                }
+            };
+            if entering {
+                self.ensure_initialized(scene_id, entity_id, &script, &script_path);
+                call_entity_hook(
+                    script.on_scene_enter_fn.as_ref(),
+                    "on_scene_enter",
+                    scene_id,
+                    entity_id,
+                    &script_path,
+                );
+            } else {
+                call_entity_hook(
+                    script.on_scene_exit_fn.as_ref(),
+                    "on_scene_exit",
+                    scene_id,
+                    entity_id,
+                    &script_path,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // init(scene_id, entity_id): once per entity, before anything else runs
+    fn ensure_initialized(
+        &mut self,
+        scene_id: Uuid,
+        entity_id: Uuid,
+        script: &CachedScript,
+        script_path: &Path,
+    ) {
+        if self.initialized_entities.insert(entity_id) {
+            call_entity_hook(
+                script.init_fn.as_ref(),
+                "init",
+                scene_id,
+                entity_id,
+                script_path,
+            );
+        }
+    }
+
+    /// Fire the contact hooks for the physics engine's contact events
+    /// since the last call: `on_collision` / `on_collision_end` when two
+    /// solid colliders start / stop touching, `on_trigger_enter` /
+    /// `on_trigger_exit` when a collider enters / leaves a sensor. Both
+    /// entities get the hook, each with the other's id. Called by the
+    /// runtime after the physics step, with the loaded scenes.
+    pub fn dispatch_collision_events(&mut self, loaded_scene_ids: &[Uuid]) -> Result<(), String> {
         let scene_manager = self
             .scene_manager
             .clone()
@@ -254,59 +539,63 @@ impl LuaScripting {
             .physics_engine
             .clone()
             .ok_or_else(|| "Lua session not started".to_string())?;
+        let events = physics.borrow_mut().drain_contact_events();
 
-        // Snapshot scripted entities and their current contacts up front
-        let contacts: Vec<(Uuid, PathBuf, HashSet<Uuid>)> = {
+        // Resolve every (scene, scripted entity, other) up front, so hooks
+        // are free to change the scenes
+        let calls: Vec<(Uuid, Uuid, Uuid, PathBuf, ContactEvent)> = {
             let manager = scene_manager.borrow();
-            let Some(scene) = manager.get_scene(active_scene_id) else {
-                return Ok(());
+            let script_of = |entity_id: Uuid| {
+                loaded_scene_ids.iter().find_map(|scene_id| {
+                    let scene = manager.get_scene(*scene_id)?;
+                    let entity = scene.entities.get(&entity_id)?;
+                    let path = entity.script.clone()?;
+                    scene.is_active(entity_id).then_some((*scene_id, path))
+                })
             };
-            let physics = physics.borrow();
-            scene
-                .entities
+            events
                 .iter()
-                .filter_map(|(id, entity)| {
-                    let path = entity.script.clone()?;
-                    let current: HashSet<Uuid> =
-                        physics.get_colliding_entities(id).into_iter().collect();
-                    Some((*id, path, current))
+                .flat_map(|event| {
+                    let (a, b) = event.entities();
+                    [(a, b), (b, a)].map(|(entity_id, other_id)| {
+                        let (scene_id, path) = script_of(entity_id)?;
+                        Some((scene_id, entity_id, other_id, path, *event))
+                    })
                 })
+                .flatten()
                 .collect()
         };
 
-        for (entity_id, script_path, current) in contacts {
-            let previous = self
-                .previous_contacts
-                .remove(&entity_id)
-                .unwrap_or_default();
-            let new_contacts: Vec<Uuid> = current.difference(&previous).copied().collect();
-            self.previous_contacts.insert(entity_id, current);
-
-            if new_contacts.is_empty() {
-                continue;
-            }
-
+        for (scene_id, entity_id, other_id, script_path, event) in calls {
             let script = match self.get_or_load_script(&script_path) {
                 Ok(f) => f,
                 Err(_) => continue, // load errors already reported by update path
             };
-            let Some(on_collision) = &script.on_collision_fn else {
+            let (hook, hook_name) = match event {
+                ContactEvent::CollisionStarted(..) => (&script.on_collision_fn, "on_collision"),
+                ContactEvent::CollisionStopped(..) => {
+                    (&script.on_collision_end_fn, "on_collision_end")
+                }
+                ContactEvent::TriggerEntered(..) => {
+                    (&script.on_trigger_enter_fn, "on_trigger_enter")
+                }
+                ContactEvent::TriggerExited(..) => (&script.on_trigger_exit_fn, "on_trigger_exit"),
+            };
+            let Some(hook) = hook else {
                 continue;
             };
-
-            for other_id in new_contacts {
-                if let Err(e) = on_collision.call::<()>((
-                    active_scene_id.to_string(),
-                    entity_id.to_string(),
-                    other_id.to_string(),
-                )) {
-                    LOGGER.error(format!(
-                        "Script on_collision() error for entity {} ({}): {}",
-                        entity_id,
-                        script_path.display(),
-                        e
-                    ));
-                }
+            if let Err(e) = hook.call::<()>((
+                scene_id.to_string(),
+                entity_id.to_string(),
+                other_id.to_string(),
+            )) {
+                LOGGER.error(format!(
+                    "Script {}() error for entity {} ({}): {}",
+                    hook_name,
+                    entity_id,
+                    script_path.display(),
+                    e
+                ));
             }
         }
 
@@ -353,14 +642,50 @@ impl LuaScripting {
         })?;
         let init_fn: Option<LuaFunction> = env.get("init").ok();
         let on_collision_fn: Option<LuaFunction> = env.get("on_collision").ok();
+        let on_collision_end_fn: Option<LuaFunction> = env.get("on_collision_end").ok();
+        let on_trigger_enter_fn: Option<LuaFunction> = env.get("on_trigger_enter").ok();
+        let on_trigger_exit_fn: Option<LuaFunction> = env.get("on_trigger_exit").ok();
+        let on_enable_fn: Option<LuaFunction> = env.get("on_enable").ok();
+        let on_disable_fn: Option<LuaFunction> = env.get("on_disable").ok();
+        let on_scene_enter_fn: Option<LuaFunction> = env.get("on_scene_enter").ok();
+        let on_scene_exit_fn: Option<LuaFunction> = env.get("on_scene_exit").ok();
 
         let cached = CachedScript {
             modified,
             update_fn,
             init_fn,
             on_collision_fn,
+            on_collision_end_fn,
+            on_trigger_enter_fn,
+            on_trigger_exit_fn,
+            on_enable_fn,
+            on_disable_fn,
+            on_scene_enter_fn,
+            on_scene_exit_fn,
         };
         self.script_cache.insert(path.to_path_buf(), cached.clone());
         Ok(cached)
     }
 }
+
+// Call an optional `hook(scene_id, entity_id)`; errors are logged, not raised
+fn call_entity_hook(
+    hook: Option<&LuaFunction>,
+    hook_name: &str,
+    scene_id: Uuid,
+    entity_id: Uuid,
+    script_path: &Path,
+) {
+    let Some(hook) = hook else {
+        return;
+    };
+    if let Err(e) = hook.call::<()>((scene_id.to_string(), entity_id.to_string())) {
+        LOGGER.error(format!(
+            "Script {}() error for entity {} ({}): {}",
+            hook_name,
+            entity_id,
+            script_path.display(),
+            e
+        ));
+    }
+}
@@ -2,10 +2,14 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use rapier2d::prelude::*;
+use uuid::Uuid;
 
+use super::ecs_bindings::ids_to_lua;
 use super::{parse_uuid, LuaScripting};
-use crate::ecs::SceneManager;
-use crate::physics_engine::PhysicsEngine;
+use crate::ecs::{AttributeType, AttributeValue, SceneManager, WorldTransform};
+use crate::physics_engine::{
+    JointKind, JointSpec, PhysicsEngine, QueryHit, QueryOptions, QueryShape,
+};
 
 impl LuaScripting {
     pub(crate) fn register_physics_bindings(
@@ -72,14 +76,18 @@ impl LuaScripting {
             self.lua.create_function(move |_, entity_id: String| {
                 let uuid = parse_uuid(&entity_id, "entity")?;
                 let manager = manager.borrow();
-                if let Some(active_scene) = manager.get_active_scene() {
-                    if let Some(entity) = active_scene.entities.get(&uuid) {
-                        physics.borrow_mut().add_entity(entity);
-                        return Ok(());
-                    }
+                if let Some((scene_id, entity)) = manager.find_loaded_entity(uuid) {
+                    let transform = manager
+                        .get_scene(scene_id)
+                        .and_then(|scene| scene.world_transform(uuid).ok())
+                        .unwrap_or_else(|| WorldTransform::local(entity));
+                    physics
+                        .borrow_mut()
+                        .add_entity_with_transform(entity, &transform);
+                    return Ok(());
                 }
                 Err(mlua::Error::external(format!(
-                    "Entity '{}' not found in active scene",
+                    "Entity '{}' not found in a loaded scene",
                     uuid
                 )))
             })?;
@@ -119,6 +127,365 @@ impl LuaScripting {
         })?;
         globals.set("set_gravity", set_gravity)?;
 
+        // create_joint(entity_a, entity_b, "revolute" | "prismatic" | "fixed" |
+        //   "rope" | "spring", { anchor = {x, y}, target_anchor = {x, y},
+        //   axis = {x, y}, limits = {min, max}, length = n, stiffness = n,
+        //   damping = n, motor = {velocity, max_force} }) -> joint id
+        //   The options are optional. The joint is stored as entity_a's
+        //   joint_* attributes, so it's saved with the scene; it replaces a
+        //   joint entity_a declared before and has entity_a's id.
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let create_joint = self.lua.create_function(
+            move |_,
+                  (entity_a, entity_b, kind, options): (
+                String,
+                String,
+                String,
+                Option<mlua::Table>,
+            )| {
+                let entity_a = parse_uuid(&entity_a, "entity")?;
+                let entity_b = parse_uuid(&entity_b, "entity")?;
+                let kind = JointKind::parse(&kind).ok_or_else(|| {
+                    mlua::Error::external(format!("Unknown joint type '{}'", kind))
+                })?;
+                let mut spec = JointSpec::new(kind);
+                if let Some(options) = options {
+                    let pair = |key: &str| -> mlua::Result<Option<(f32, f32)>> {
+                        match options.get::<Option<Vec<f32>>>(key)? {
+                            None => Ok(None),
+                            Some(values) => match values[..] {
+                                [a, b] => Ok(Some((a, b))),
+                                _ => Err(mlua::Error::external(format!(
+                                    "{} must be {{ a, b }}",
+                                    key
+                                ))),
+                            },
+                        }
+                    };
+                    spec.anchor = pair("anchor")?.unwrap_or(spec.anchor);
+                    spec.target_anchor = pair("target_anchor")?.unwrap_or(spec.target_anchor);
+                    spec.axis = pair("axis")?.unwrap_or(spec.axis);
+                    spec.limits = pair("limits")?;
+                    spec.motor = pair("motor")?;
+                    spec.length = options.get("length")?;
+                    if let Some(stiffness) = options.get("stiffness")? {
+                        spec.stiffness = stiffness;
+                    }
+                    if let Some(damping) = options.get("damping")? {
+                        spec.damping = damping;
+                    }
+                }
+                let mut physics = physics.borrow_mut();
+                physics
+                    .check_joint_entities(entity_a, entity_b)
+                    .map_err(mlua::Error::external)?;
+                let mut manager = manager.borrow_mut();
+                let (_, entity) = manager.find_loaded_entity_mut(entity_a).ok_or_else(|| {
+                    mlua::Error::external(format!(
+                        "Entity '{}' not found in a loaded scene",
+                        entity_a
+                    ))
+                })?;
+                spec.write_to(entity, entity_b)
+                    .map_err(mlua::Error::external)?;
+                physics.declare_joint(entity);
+                Ok(entity_a.to_string())
+            },
+        )?;
+        globals.set("create_joint", create_joint)?;
+
+        // remove_joint(joint_id) -> true if there was such a joint. A joint
+        // declared by an entity's attributes loses its declaration too.
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let remove_joint = self.lua.create_function(move |_, joint_id: String| {
+            let joint_id = parse_uuid(&joint_id, "joint")?;
+            let removed = physics.borrow_mut().remove_joint(joint_id);
+            if let Some((_, entity)) = manager.borrow_mut().find_loaded_entity_mut(joint_id) {
+                let declaration: Vec<_> = entity
+                    .list_attribute()
+                    .into_iter()
+                    .filter(|(_, name)| name.starts_with("joint_"))
+                    .map(|(id, _)| id)
+                    .collect();
+                for id in declaration {
+                    entity.delete_attribute(id).map_err(mlua::Error::external)?;
+                }
+            }
+            Ok(removed)
+        })?;
+        globals.set("remove_joint", remove_joint)?;
+
+        // set_joint_motor(joint_id, velocity, max_force): revolute joints turn
+        // at `velocity` radians / s, prismatic ones slide at pixels / s.
+        // A joint declared by an entity's attributes has that entity's id.
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let set_joint_motor = self.lua.create_function(
+            move |_, (joint_id, velocity, max_force): (String, f32, f32)| {
+                let joint_id = parse_uuid(&joint_id, "joint")?;
+                physics
+                    .borrow_mut()
+                    .set_joint_motor(joint_id, velocity, max_force)
+                    .map_err(mlua::Error::external)?;
+                let motor = AttributeValue::Vector2(velocity, max_force);
+                update_declaration(&manager, joint_id, "joint_motor", motor)
+            },
+        )?;
+        globals.set("set_joint_motor", set_joint_motor)?;
+
+        // set_joint_limits(joint_id, min, max): radians (revolute) or pixels
+        // along the axis (prismatic)
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let set_joint_limits =
+            self.lua
+                .create_function(move |_, (joint_id, min, max): (String, f32, f32)| {
+                    let joint_id = parse_uuid(&joint_id, "joint")?;
+                    physics
+                        .borrow_mut()
+                        .set_joint_limits(joint_id, min, max)
+                        .map_err(mlua::Error::external)?;
+                    let limits = AttributeValue::Vector2(min, max);
+                    update_declaration(&manager, joint_id, "joint_limits", limits)
+                })?;
+        globals.set("set_joint_limits", set_joint_limits)?;
+
+        // set_collision_layers(entity_id, layers, mask?): the collision layers
+        //   the entity is in and the ones it collides with, each a bit mask,
+        //   a layer name or an array of names. Without a mask the entity
+        //   keeps colliding with what it did.
+        let manager = Rc::clone(scene_manager);
+        let set_collision_layers = self.lua.create_function(
+            move |_, (entity_id, layers, mask): (String, mlua::Value, mlua::Value)| {
+                let mut manager = manager.borrow_mut();
+                let entity_uuid = parse_uuid(&entity_id, "entity")?;
+                let layers = layer_mask(&manager, layers)?
+                    .ok_or_else(|| mlua::Error::external("layers are required"))?;
+                let mask = layer_mask(&manager, mask)?;
+                let (_, entity) = manager.find_loaded_entity_mut(entity_uuid).ok_or_else(|| {
+                    mlua::Error::external(format!(
+                        "Entity '{}' not found in a loaded scene",
+                        entity_uuid
+                    ))
+                })?;
+                let mask = mask.unwrap_or_else(|| {
+                    match entity
+                        .get_attribute_by_name("collision_mask")
+                        .map(|attr| &attr.value)
+                    {
+                        Ok(AttributeValue::Integer(mask)) => *mask as u32,
+                        _ => u32::MAX,
+                    }
+                });
+                entity
+                    .set_collision_layers(layers, mask)
+                    .map_err(mlua::Error::external)
+            },
+        )?;
+        globals.set("set_collision_layers", set_collision_layers)?;
+
+        // move_character(entity_id, dx, dy): move an `is_character` entity by
+        //   (dx, dy) pixels in the next physics step, climbing slopes and
+        //   steps and sliding along walls. Include the fall in dy; characters
+        //   have no gravity of their own.
+        let physics = Rc::clone(physics_engine);
+        let move_character =
+            self.lua
+                .create_function(move |_, (entity_id, dx, dy): (String, f32, f32)| {
+                    let uuid = parse_uuid(&entity_id, "entity")?;
+                    physics
+                        .borrow_mut()
+                        .move_character(&uuid, dx, dy)
+                        .map_err(mlua::Error::external)
+                })?;
+        globals.set("move_character", move_character)?;
+
+        // is_grounded(entity_id) -> whether the character stood on something
+        //   after the last physics step (false for non-characters)
+        let physics = Rc::clone(physics_engine);
+        let is_grounded = self.lua.create_function(move |_, entity_id: String| {
+            let uuid = parse_uuid(&entity_id, "entity")?;
+            Ok(physics.borrow().is_grounded(&uuid))
+        })?;
+        globals.set("is_grounded", is_grounded)?;
+
+        // Queries take an optional last argument { exclude = id or {ids},
+        //   layers = bit mask or layer name(s), include_sensors = bool } and
+        //   see the world as of the last physics step. Hits are { entity_id, point = {x, y},
+        //   normal = {x, y}, distance }, or nil.
+
+        // raycast(x, y, dx, dy, max_distance, options?) -> hit
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let raycast = self.lua.create_function(
+            move |lua,
+                  (x, y, dx, dy, max_distance, options): (
+                f32,
+                f32,
+                f32,
+                f32,
+                f32,
+                Option<mlua::Table>,
+            )| {
+                let options = query_options(&manager.borrow(), options)?;
+                let hit = physics
+                    .borrow()
+                    .raycast((x, y), (dx, dy), max_distance, &options);
+                hit.map(|hit| hit_to_lua(lua, &hit)).transpose()
+            },
+        )?;
+        globals.set("raycast", raycast)?;
+
+        // cast_shape({ radius = r } or { width = w, height = h }, x, y, dx, dy,
+        //   max_distance, options?) -> hit. The shape is centered on x, y.
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let cast_shape = self.lua.create_function(
+            move |lua,
+                  (shape, x, y, dx, dy, max_distance, options): (
+                mlua::Table,
+                f32,
+                f32,
+                f32,
+                f32,
+                f32,
+                Option<mlua::Table>,
+            )| {
+                let shape = match (
+                    shape.get::<Option<f32>>("radius")?,
+                    shape.get::<Option<f32>>("width")?,
+                    shape.get::<Option<f32>>("height")?,
+                ) {
+                    (Some(radius), None, None) => QueryShape::Circle(radius),
+                    (None, Some(width), Some(height)) => QueryShape::Rectangle(width, height),
+                    _ => {
+                        return Err(mlua::Error::external(
+                            "shape must be { radius = r } or { width = w, height = h }",
+                        ))
+                    }
+                };
+                let options = query_options(&manager.borrow(), options)?;
+                let hit =
+                    physics
+                        .borrow()
+                        .cast_shape(shape, (x, y), (dx, dy), max_distance, &options);
+                hit.map(|hit| hit_to_lua(lua, &hit)).transpose()
+            },
+        )?;
+        globals.set("cast_shape", cast_shape)?;
+
+        // intersect_point(x, y, options?) -> array of entity ids
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let intersect_point = self.lua.create_function(
+            move |lua, (x, y, options): (f32, f32, Option<mlua::Table>)| {
+                let options = query_options(&manager.borrow(), options)?;
+                ids_to_lua(lua, &physics.borrow().intersect_point((x, y), &options))
+            },
+        )?;
+        globals.set("intersect_point", intersect_point)?;
+
+        // intersect_aabb(x1, y1, x2, y2, options?) -> array of entity ids
+        let physics = Rc::clone(physics_engine);
+        let manager = Rc::clone(scene_manager);
+        let intersect_aabb = self.lua.create_function(
+            move |lua, (x1, y1, x2, y2, options): (f32, f32, f32, f32, Option<mlua::Table>)| {
+                let options = query_options(&manager.borrow(), options)?;
+                let entities = physics
+                    .borrow()
+                    .intersect_aabb((x1, y1), (x2, y2), &options);
+                ids_to_lua(lua, &entities)
+            },
+        )?;
+        globals.set("intersect_aabb", intersect_aabb)?;
+
         Ok(())
     }
 }
+
+// Keep the attributes of a joint an entity declares in step with a change
+// made from a script, so the joint is saved as it is now
+fn update_declaration(
+    manager: &RefCell<SceneManager>,
+    joint_id: Uuid,
+    name: &str,
+    value: AttributeValue,
+) -> mlua::Result<()> {
+    let mut manager = manager.borrow_mut();
+    let Some((_, entity)) = manager.find_loaded_entity_mut(joint_id) else {
+        return Ok(());
+    };
+    if entity.get_attribute_by_name("joint_type").is_err() {
+        return Ok(());
+    }
+    entity
+        .set_attribute(name, AttributeType::Vector2, value)
+        .map(|_| ())
+        .map_err(mlua::Error::external)
+}
+
+// A bit mask given as an integer, a layer name or an array of names; nil
+// for none given
+fn layer_mask(manager: &SceneManager, value: mlua::Value) -> mlua::Result<Option<u32>> {
+    let names: Vec<String> = match value {
+        mlua::Value::Nil => return Ok(None),
+        mlua::Value::Integer(mask) => return Ok(Some(mask as u32)),
+        mlua::Value::String(name) => vec![name.to_str()?.to_string()],
+        mlua::Value::Table(names) => names.sequence_values().collect::<mlua::Result<_>>()?,
+        _ => {
+            return Err(mlua::Error::external(
+                "layers must be a bit mask, a layer name or an array of them",
+            ))
+        }
+    };
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    manager
+        .collision_layer_mask(&names)
+        .map(Some)
+        .map_err(mlua::Error::external)
+}
+
+fn query_options(
+    manager: &SceneManager,
+    options: Option<mlua::Table>,
+) -> mlua::Result<QueryOptions> {
+    let mut query = QueryOptions::default();
+    let Some(options) = options else {
+        return Ok(query);
+    };
+    query.exclude = match options.get::<mlua::Value>("exclude")? {
+        mlua::Value::Nil => Vec::new(),
+        mlua::Value::String(id) => vec![parse_uuid(&id.to_str()?, "entity")?],
+        mlua::Value::Table(ids) => ids
+            .sequence_values::<String>()
+            .map(|id| parse_uuid(&id?, "entity"))
+            .collect::<mlua::Result<_>>()?,
+        _ => {
+            return Err(mlua::Error::external(
+                "exclude must be an entity id or an array of them",
+            ))
+        }
+    };
+    query.layers = layer_mask(manager, options.get("layers")?)?;
+    query.include_sensors = options
+        .get::<Option<bool>>("include_sensors")?
+        .unwrap_or(false);
+    Ok(query)
+}
+
+fn hit_to_lua(lua: &mlua::Lua, hit: &QueryHit) -> mlua::Result<mlua::Table> {
+    let vector = |(x, y): (f32, f32)| -> mlua::Result<mlua::Table> {
+        let table = lua.create_table()?;
+        table.set("x", x)?;
+        table.set("y", y)?;
+        Ok(table)
+    };
+    let table = lua.create_table()?;
+    table.set("entity_id", hit.entity.to_string())?;
+    table.set("point", vector(hit.point)?)?;
+    table.set("normal", vector(hit.normal)?)?;
+    table.set("distance", hit.distance)?;
+    Ok(table)
+}
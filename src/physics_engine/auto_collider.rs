@@ -0,0 +1,251 @@
+//! Colliders traced from a sprite's alpha channel (`collider_shape =
+//! "auto"`). Tracing is slow for big images, so the result is cached next
+//! to the image as `<image>.collider.json`: project imports write it up
+//! front, and a missing or stale cache is regenerated on first use.
+
+use rapier2d::parry::transformation::vhacd::{VHACDParameters, VHACD};
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Pixels at least this opaque are solid
+const ALPHA_THRESHOLD: u8 = 128;
+
+// How far (in pixels) a simplified outline may stray from the traced one
+const SIMPLIFY_TOLERANCE: f32 = 1.0;
+
+// Most convex parts a sprite is split into
+const MAX_PARTS: u32 = 8;
+
+// Bump when tracing changes so old caches are regenerated
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct AutoColliderCache {
+    version: u32,
+    source_hash: u64, // FNV-1a of the image file the polygons came from
+    polygons: Vec<Vec<(f32, f32)>>,
+}
+
+/// Where the traced collider of `image_path` is cached.
+pub fn auto_collider_cache_path(image_path: &Path) -> PathBuf {
+    let mut file_name = image_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".collider.json");
+    image_path.with_file_name(file_name)
+}
+
+/// Trace `image_path` and (re)write its cache. Returns the convex polygons,
+/// in pixels from the image's top-left.
+pub fn generate_auto_collider(image_path: &Path) -> Result<Vec<Vec<(f32, f32)>>, String> {
+    let bytes = fs::read(image_path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let cache = trace_cache(&bytes)?;
+    write_cache(image_path, &cache)?;
+    Ok(cache.polygons)
+}
+
+/// The traced collider of `image_path`, from its cache if that was made
+/// from the image as it is now. Otherwise the image is traced again and
+/// the cache rewritten (if it can be; the polygons are returned either way).
+pub fn auto_collider_polygons(image_path: &Path) -> Result<Vec<Vec<(f32, f32)>>, String> {
+    let bytes = fs::read(image_path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let source_hash = fnv1a(&bytes);
+    let cached = fs::read_to_string(auto_collider_cache_path(image_path))
+        .ok()
+        .and_then(|json| serde_json::from_str::<AutoColliderCache>(&json).ok())
+        .filter(|cache| cache.version == CACHE_VERSION && cache.source_hash == source_hash);
+    if let Some(cache) = cached {
+        return Ok(cache.polygons);
+    }
+
+    let cache = trace_cache(&bytes)?;
+    let _ = write_cache(image_path, &cache);
+    Ok(cache.polygons)
+}
+
+fn trace_cache(bytes: &[u8]) -> Result<AutoColliderCache, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_rgba8();
+    Ok(AutoColliderCache {
+        version: CACHE_VERSION,
+        source_hash: fnv1a(bytes),
+        polygons: trace(&image),
+    })
+}
+
+fn write_cache(image_path: &Path, cache: &AutoColliderCache) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(cache)
+        .map_err(|e| format!("Failed to serialize collider: {}", e))?;
+    fs::write(auto_collider_cache_path(image_path), json)
+        .map_err(|e| format!("Failed to write collider cache: {}", e))
+}
+
+/// Convex polygons covering the solid pixels of `image`, in pixels from
+/// its top-left. Empty if the image is fully transparent.
+fn trace(image: &image::RgbaImage) -> Vec<Vec<(f32, f32)>> {
+    let outlines: Vec<Vec<Vector>> = outlines(image)
+        .into_iter()
+        .map(|outline| simplify(&outline))
+        .filter(|outline| outline.len() >= 3)
+        .collect();
+    if outlines.is_empty() {
+        return Vec::new();
+    }
+
+    // Convex decomposition of the outlines, as one closed polyline set
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for outline in &outlines {
+        let start = vertices.len() as u32;
+        let count = outline.len() as u32;
+        vertices.extend_from_slice(outline);
+        indices.extend((0..count).map(|i| [start + i, start + (i + 1) % count]));
+    }
+    let params = VHACDParameters {
+        resolution: 64,
+        max_convex_hulls: MAX_PARTS,
+        ..Default::default()
+    };
+    let mut polygons: Vec<Vec<Vector>> = VHACD::decompose(&params, &vertices, &indices, true)
+        .compute_exact_convex_hulls(&vertices, &indices)
+        .into_iter()
+        .filter(|hull| hull.len() >= 3)
+        .collect();
+    if polygons.is_empty() {
+        polygons.push(rapier2d::parry::transformation::convex_hull(&vertices));
+    }
+
+    // Rounded so caches don't churn on float noise
+    let round = |value: f32| (value * 100.0).round() / 100.0;
+    polygons
+        .into_iter()
+        .map(|polygon| {
+            polygon
+                .into_iter()
+                .map(|point| (round(point.x), round(point.y)))
+                .collect()
+        })
+        .collect()
+}
+
+// Closed outlines along the pixel edges between solid and clear pixels,
+// solid on the right of their direction
+fn outlines(image: &image::RgbaImage) -> Vec<Vec<(i64, i64)>> {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let solid = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < width
+            && y < height
+            && image.get_pixel(x as u32, y as u32)[3] >= ALPHA_THRESHOLD
+    };
+
+    // Pixel-corner edges, start -> ends (two at corners where diagonal
+    // pixels touch); ordered so tracing is deterministic
+    let mut edges: BTreeMap<(i64, i64), Vec<(i64, i64)>> = BTreeMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !solid(x, y) {
+                continue;
+            }
+            let sides = [
+                (solid(x, y - 1), (x, y), (x + 1, y)),
+                (solid(x + 1, y), (x + 1, y), (x + 1, y + 1)),
+                (solid(x, y + 1), (x + 1, y + 1), (x, y + 1)),
+                (solid(x - 1, y), (x, y + 1), (x, y)),
+            ];
+            for (neighbour_solid, start, end) in sides {
+                if !neighbour_solid {
+                    edges.entry(start).or_default().push(end);
+                }
+            }
+        }
+    }
+
+    // Every corner has as many edges in as out, so following edges from
+    // any start always leads back to it
+    let mut outlines = Vec::new();
+    while let Some((&start, _)) = edges.iter().next() {
+        let mut outline = vec![start];
+        let mut at = start;
+        while let Some(ends) = edges.get_mut(&at) {
+            let end = ends.pop().unwrap_or(start);
+            if ends.is_empty() {
+                edges.remove(&at);
+            }
+            if end == start {
+                break;
+            }
+            outline.push(end);
+            at = end;
+        }
+        outlines.push(outline);
+    }
+    outlines
+}
+
+// Drop points along straight runs, then Douglas-Peucker the rest
+fn simplify(outline: &[(i64, i64)]) -> Vec<Vector> {
+    let count = outline.len();
+    let corners: Vec<Vector> = (0..count)
+        .filter(|&i| {
+            let (px, py) = outline[(i + count - 1) % count];
+            let (x, y) = outline[i];
+            let (nx, ny) = outline[(i + 1) % count];
+            (x - px) * (ny - y) != (y - py) * (nx - x)
+        })
+        .map(|i| Vector::new(outline[i].0 as f32, outline[i].1 as f32))
+        .collect();
+    if corners.len() <= 3 {
+        return corners;
+    }
+
+    // A closed outline is split at its first point and the point farthest
+    // from it, and each half simplified as an open line
+    let first = corners[0];
+    let far = (1..corners.len())
+        .max_by(|&a, &b| {
+            first
+                .distance_squared(corners[a])
+                .total_cmp(&first.distance_squared(corners[b]))
+        })
+        .unwrap_or(1);
+    let mut closed = corners.clone();
+    closed.push(first);
+    let mut simplified = douglas_peucker(&closed[..=far]);
+    simplified.pop();
+    simplified.extend(douglas_peucker(&closed[far..]));
+    simplified.pop();
+    simplified
+}
+
+fn douglas_peucker(line: &[Vector]) -> Vec<Vector> {
+    let (first, last) = (line[0], line[line.len() - 1]);
+    let distance = |point: Vector| {
+        let along = last - first;
+        match along.try_normalize() {
+            Some(direction) => (point - first).perp_dot(direction).abs(),
+            None => point.distance(first),
+        }
+    };
+    let farthest = (1..line.len().saturating_sub(1))
+        .max_by(|&a, &b| distance(line[a]).total_cmp(&distance(line[b])));
+    match farthest {
+        Some(index) if distance(line[index]) > SIMPLIFY_TOLERANCE => {
+            let mut simplified = douglas_peucker(&line[..=index]);
+            simplified.pop();
+            simplified.extend(douglas_peucker(&line[index..]));
+            simplified
+        }
+        _ => vec![first, last],
+    }
+}
+
+// Stable across builds, unlike std's hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,264 @@
+use rapier2d::control::{CharacterLength, KinematicCharacterController};
+use rapier2d::parry::bounding_volume::BoundingVolume;
+use rapier2d::parry::query::{DefaultQueryDispatcher, PersistentQueryDispatcher};
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{float_attribute, PhysicsEngine};
+use crate::ecs::Entity;
+
+// Gap kept between a character and what it touches, in pixels
+const SKIN: f32 = 0.5;
+
+// Things at most this far below a character are what it stands on
+const GROUND_DISTANCE: f32 = SKIN * 2.0;
+
+// A character entity (`is_character`): a kinematic body moved by rapier's
+// character controller instead of by velocities and forces.
+//
+// The controller measures slope angles with a sign (glam's `angle_to`), so
+// its slope limit and step climbing only see surfaces facing one way. They
+// are turned off there and done in `movement` instead; rapier still sweeps
+// the shape, slides it along surfaces, snaps it to the ground and tells
+// whether it's grounded.
+#[derive(Serialize, Deserialize)]
+pub(super) struct Character {
+    controller: KinematicCharacterController,
+    max_slope: f32,   // Radians from straight up
+    step_height: f32, // Pixels; 0 = no step climbing
+    pending: Vector,  // Movement asked for since the last step
+    grounded: bool,   // As of the last step
+}
+
+impl Character {
+    pub(super) fn new(entity: &Entity) -> Self {
+        let mut character = Self {
+            controller: KinematicCharacterController::default(),
+            max_slope: 0.0,
+            step_height: 0.0,
+            pending: Vector::ZERO,
+            grounded: false,
+        };
+        character.update(entity);
+        character
+    }
+
+    // Settings from the entity's attributes:
+    // - `character_max_slope`: steepest slope in degrees that can be walked
+    //   up or stood on (default 45); steeper ones are slid down;
+    // - `character_step_height`: steps up to this many pixels high are
+    //   climbed (default 0, off);
+    // - `character_snap_distance`: ground at most this far below is snapped
+    //   down to, so the character stays on it walking down slopes and steps
+    //   (default a fifth of the character's height; 0 turns it off).
+    pub(super) fn update(&mut self, entity: &Entity) {
+        self.max_slope = float_attribute(entity, "character_max_slope")
+            .unwrap_or(45.0)
+            .to_radians();
+        self.step_height = float_attribute(entity, "character_step_height")
+            .unwrap_or(0.0)
+            .max(0.0);
+        let snap_to_ground = match float_attribute(entity, "character_snap_distance") {
+            Some(distance) if distance <= 0.0 => None,
+            Some(distance) => Some(CharacterLength::Absolute(distance)),
+            None => Some(CharacterLength::Relative(0.2)),
+        };
+        self.controller = KinematicCharacterController {
+            // Screen space: +y is down
+            up: Vector::NEG_Y,
+            // Nothing is a wall or too steep to the controller (see above)
+            max_slope_climb_angle: std::f32::consts::PI,
+            min_slope_slide_angle: std::f32::consts::PI,
+            autostep: None,
+            offset: CharacterLength::Absolute(SKIN),
+            snap_to_ground,
+            ..Default::default()
+        };
+    }
+
+    // Where `desired` gets the shape at `pose` this step, and whether it
+    // ends up on the ground. The horizontal part (walking) goes first, then
+    // the vertical part (falling or jumping).
+    fn movement(
+        &self,
+        dt: f32,
+        pipeline: &QueryPipeline,
+        shape: &dyn Shape,
+        pose: &Pose,
+        desired: Vector,
+    ) -> (Vector, bool) {
+        let at = |translation: Vector| Pose::from_translation(translation) * *pose;
+        let sweep = |from: Vector, translation: Vector| {
+            let mut hits = Vec::new();
+            let movement =
+                self.controller
+                    .move_shape(dt, pipeline, shape, &at(from), translation, |hit| {
+                        hits.push(hit)
+                    });
+            (movement.translation, hits)
+        };
+        let ground = |translation: Vector| ground_normals(pipeline, shape, &at(translation));
+
+        // Walk, up slopes that aren't too steep. Stop at anything steeper,
+        // or climb onto it if it's a low enough step
+        let walk = Vector::new(desired.x, 0.0);
+        let (mut translation, hits) = sweep(Vector::ZERO, walk);
+        let held_back = (walk.x - translation.x).abs() > 0.01;
+        if let Some(blocked) = hits
+            .iter()
+            .find(|hit| held_back && self.is_steep(hit.hit.normal1))
+        {
+            translation = blocked.translation_applied;
+            let remaining = Vector::new(walk.x - translation.x, 0.0);
+            if self.step_height > 0.0 {
+                // Rise by the step height, carry on across and settle onto
+                // what's below, if there's room and the landing is walkable
+                let rise = self.controller.up * self.step_height;
+                let (up, _) = sweep(translation, rise);
+                let (across, _) = sweep(translation + up, remaining);
+                let (down, _) = sweep(translation + up + across, -rise);
+                let landed = translation + up + across + down;
+                if up.distance(rise) < 0.01
+                    && across.x.abs() > 0.01
+                    && ground(landed).iter().any(|normal| !self.is_steep(*normal))
+                {
+                    translation = landed;
+                }
+            }
+        }
+
+        // Fall (or jump). Ground too steep to stand on is slid down
+        let fall = Vector::new(0.0, desired.y);
+        let (fallen, _) = sweep(translation, fall);
+        let normals = ground(translation + fallen);
+        let grounded = normals.iter().any(|normal| !self.is_steep(*normal));
+        match normals.first() {
+            Some(slope) if !grounded && self.controller.up.dot(fall) < 0.0 => {
+                let (slid, _) = sweep(translation, fall - *slope * fall.dot(*slope));
+                (translation + slid, false)
+            }
+            _ => (translation + fallen, grounded),
+        }
+    }
+
+    // Normals more than `max_slope` from up: walls, ceilings and slopes too
+    // steep to stand on
+    fn is_steep(&self, normal: Vector) -> bool {
+        self.controller.up.dot(normal) < self.max_slope.cos() - 1.0e-4
+    }
+}
+
+// Surface normals of what the shape at `pose` is resting on. Sweep hits
+// between flat faces sometimes come with skewed normals, so standing is
+// judged by contacts instead.
+fn ground_normals(pipeline: &QueryPipeline, shape: &dyn Shape, pose: &Pose) -> Vec<Vector> {
+    let aabb = shape.compute_aabb(pose).loosened(GROUND_DISTANCE);
+    pipeline
+        .intersect_aabb_conservative(aabb)
+        .flat_map(|(_, collider)| {
+            // One manifold per touching face (or segment of a polyline)
+            let mut manifolds: Vec<ContactManifold> = Vec::new();
+            let pos12 = pose.inv_mul(collider.position());
+            let _ = DefaultQueryDispatcher.contact_manifolds(
+                &pos12,
+                shape,
+                collider.shape(),
+                GROUND_DISTANCE,
+                &mut manifolds,
+                &mut None,
+            );
+            manifolds
+                .into_iter()
+                .filter(|manifold| {
+                    manifold
+                        .points
+                        .iter()
+                        .any(|point| point.dist <= GROUND_DISTANCE)
+                })
+                // Manifold normals point from the character into the ground
+                .map(|manifold| -(pose.rotation * manifold.local_n1))
+                .filter(|normal| normal.dot(Vector::NEG_Y) > 1.0e-3)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+impl PhysicsEngine {
+    /// Move a character entity by (dx, dy) pixels during the next step,
+    /// stopping at and sliding along what's in the way. Moves asked for
+    /// before a step add up. Characters have no gravity of their own:
+    /// falling is part of the movement asked for.
+    pub fn move_character(&mut self, entity_id: &Uuid, dx: f32, dy: f32) -> Result<(), String> {
+        let character = self
+            .characters
+            .get_mut(entity_id)
+            .ok_or_else(|| format!("Entity '{}' is not a character", entity_id))?;
+        character.pending += Vector::new(dx, dy);
+        Ok(())
+    }
+
+    /// Whether a character entity was standing on something after the last
+    /// step. False for anything that isn't a character.
+    pub fn is_grounded(&self, entity_id: &Uuid) -> bool {
+        self.characters
+            .get(entity_id)
+            .is_some_and(|character| character.grounded)
+    }
+
+    pub fn is_character(&self, entity_id: &Uuid) -> bool {
+        self.characters.contains_key(entity_id)
+    }
+
+    // Run each character's pending movement through its controller and set
+    // where its body ends up this step. Every character is moved, even
+    // without a pending move, to keep it snapped to the ground and its
+    // grounded flag current.
+    pub(super) fn move_characters(&mut self) {
+        if self.characters.is_empty() {
+            return;
+        }
+        // Bodies added since the last step aren't in the query tree yet
+        self.refresh_query_bvh();
+
+        let dt = self.integration_parameters.dt;
+        let mut moves = Vec::new();
+        for (entity_id, character) in &mut self.characters {
+            let desired = std::mem::take(&mut character.pending);
+            let (Some(body_handle), Some(collider)) = (
+                self.entity_to_body.get(entity_id),
+                self.entity_to_collider
+                    .get(entity_id)
+                    .and_then(|handle| self.collider_set.get(*handle)),
+            ) else {
+                continue;
+            };
+            let filter = QueryFilter::new()
+                .exclude_sensors()
+                .exclude_rigid_body(*body_handle)
+                .groups(collider.collision_groups());
+            let pipeline = QueryPipeline {
+                dispatcher: &DefaultQueryDispatcher,
+                bvh: &self.query_bvh,
+                bodies: &self.rigid_body_set,
+                colliders: &self.collider_set,
+                filter,
+            };
+            let (translation, grounded) = character.movement(
+                dt,
+                &pipeline,
+                collider.shape(),
+                collider.position(),
+                desired,
+            );
+            character.grounded = grounded;
+            moves.push((*body_handle, translation));
+        }
+        for (body_handle, translation) in moves {
+            if let Some(body) = self.rigid_body_set.get_mut(body_handle) {
+                let target = body.translation() + translation;
+                body.set_next_kinematic_translation(target);
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use super::PhysicsEngine;
+
+/// Two entities' colliders started or stopped touching during a step.
+/// Trigger events have a sensor (`is_sensor`) on at least one side, listed
+/// first; collision events are between two solid colliders.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactEvent {
+    CollisionStarted(Uuid, Uuid),
+    CollisionStopped(Uuid, Uuid),
+    TriggerEntered(Uuid, Uuid), // sensor, other
+    TriggerExited(Uuid, Uuid),  // sensor, other
+}
+
+impl ContactEvent {
+    pub fn entities(&self) -> (Uuid, Uuid) {
+        match *self {
+            ContactEvent::CollisionStarted(a, b)
+            | ContactEvent::CollisionStopped(a, b)
+            | ContactEvent::TriggerEntered(a, b)
+            | ContactEvent::TriggerExited(a, b) => (a, b),
+        }
+    }
+
+    pub fn is_trigger(&self) -> bool {
+        matches!(
+            self,
+            ContactEvent::TriggerEntered(..) | ContactEvent::TriggerExited(..)
+        )
+    }
+
+    pub fn is_started(&self) -> bool {
+        matches!(
+            self,
+            ContactEvent::CollisionStarted(..) | ContactEvent::TriggerEntered(..)
+        )
+    }
+
+    fn same_pair(&self, other: &ContactEvent) -> bool {
+        let ((a, b), (c, d)) = (self.entities(), other.entities());
+        self.is_trigger() == other.is_trigger() && ((a, b) == (c, d) || (a, b) == (d, c))
+    }
+}
+
+// Gathers rapier's collision events while the pipeline steps
+#[derive(Default)]
+pub(super) struct EventCollector {
+    events: Mutex<Vec<CollisionEvent>>,
+}
+
+impl EventCollector {
+    pub(super) fn into_events(self) -> Vec<CollisionEvent> {
+        self.events.into_inner().unwrap_or_default()
+    }
+}
+
+impl EventHandler for EventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(event);
+        }
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
+impl PhysicsEngine {
+    /// Contact events since the last call, oldest first. The runtime drains
+    /// them once per frame for the scripts' collision and trigger hooks.
+    pub fn drain_contact_events(&mut self) -> Vec<ContactEvent> {
+        std::mem::take(&mut self.contact_events)
+    }
+
+    // Turn a step's rapier events into entity events. Colliders removed
+    // since the previous step are already gone from the set; their entity
+    // and sensor flag were kept by `remove_entity`.
+    pub(super) fn record_contact_events(&mut self, events: Vec<CollisionEvent>) {
+        let removed = std::mem::take(&mut self.removed_colliders);
+        let entity_of = |handle: ColliderHandle| {
+            self.collider_set
+                .get(handle)
+                .map(|collider| (Uuid::from_u128(collider.user_data), collider.is_sensor()))
+                .or_else(|| removed.get(&handle).copied())
+        };
+
+        // (event, caused by a removed collider)
+        let mut step_events: Vec<(ContactEvent, bool)> = Vec::new();
+        for event in events {
+            let (Some((entity1, sensor1)), Some((entity2, sensor2))) =
+                (entity_of(event.collider1()), entity_of(event.collider2()))
+            else {
+                continue;
+            };
+            // Sensor first
+            let (first, second) = if sensor2 && !sensor1 {
+                (entity2, entity1)
+            } else {
+                (entity1, entity2)
+            };
+            let contact = match (event.started(), event.sensor()) {
+                (true, false) => ContactEvent::CollisionStarted(first, second),
+                (false, false) => ContactEvent::CollisionStopped(first, second),
+                (true, true) => ContactEvent::TriggerEntered(first, second),
+                (false, true) => ContactEvent::TriggerExited(first, second),
+            };
+
+            // A body rebuilt this step (see `apply_scene_events`) ends its
+            // contacts and starts them again; that's no change to report
+            if contact.is_started() {
+                if let Some(index) = step_events
+                    .iter()
+                    .position(|(stopped, removed)| *removed && stopped.same_pair(&contact))
+                {
+                    step_events.remove(index);
+                    continue;
+                }
+            }
+            step_events.push((contact, event.removed()));
+        }
+        self.contact_events
+            .extend(step_events.into_iter().map(|(contact, _)| contact));
+    }
+}
@@ -0,0 +1,183 @@
+use rapier2d::parry::bounding_volume::Aabb;
+use rapier2d::parry::query::DefaultQueryDispatcher;
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{bool_attribute, float_attribute, PhysicsEngine};
+use crate::ecs::{AttributeValue, Entity};
+
+// Inverse-square fields pull with their full strength at this distance
+const REFERENCE_DISTANCE: f32 = 100.0;
+
+// Inverse-square fields pull as if nothing were closer than this, so bodies
+// passing through the source aren't flung away
+const MIN_DISTANCE: f32 = 10.0;
+
+const DEFAULT_STRENGTH: f32 = 50.0;
+const DEFAULT_RADIUS: f32 = 1000.0;
+
+/// How a gravity field weakens with distance from its source.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum GravityFalloff {
+    #[default]
+    InverseSquare,
+    Linear, // Down to nothing at the radius
+    Constant,
+}
+
+/// Which way a gravity field pulls.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum GravityMode {
+    #[default]
+    Point, // Toward the source's center
+    Directional, // Along `gravity_direction`, turned with the source
+}
+
+// The field of a `creates_gravity` entity. Settings from its attributes:
+// - `gravity_strength`: acceleration in pixels/s² (default 50, like the
+//   global gravity); an inverse-square field has it 100px from the center;
+// - `gravity_radius`: pixels from the center the field reaches (default
+//   1000; 0 or less reaches everywhere);
+// - `gravity_falloff`: "inverse_square" (default), "linear" or "constant";
+// - `gravity_mode`: "point" (default) or "directional";
+// - `gravity_direction`: a Vector2, the pull of a directional field before
+//   the source's rotation (default (0, 1), down).
+#[derive(Serialize, Deserialize)]
+pub(super) struct GravityField {
+    strength: f32,
+    radius: Option<f32>,
+    falloff: GravityFalloff,
+    mode: GravityMode,
+    direction: Vector,
+}
+
+impl GravityField {
+    // None unless the entity has `creates_gravity` set
+    pub(super) fn new(entity: &Entity) -> Option<Self> {
+        if !bool_attribute(entity, "creates_gravity").unwrap_or(false) {
+            return None;
+        }
+        let string = |name: &str| match entity.get_attribute_by_name(name).map(|attr| &attr.value) {
+            Ok(AttributeValue::String(value)) => value.to_lowercase(),
+            _ => String::new(),
+        };
+        let falloff = match string("gravity_falloff").as_str() {
+            "linear" => GravityFalloff::Linear,
+            "constant" => GravityFalloff::Constant,
+            _ => GravityFalloff::InverseSquare,
+        };
+        let mode = match string("gravity_mode").as_str() {
+            "directional" => GravityMode::Directional,
+            _ => GravityMode::Point,
+        };
+        let direction = match entity
+            .get_attribute_by_name("gravity_direction")
+            .map(|attr| &attr.value)
+        {
+            Ok(AttributeValue::Vector2(x, y)) => Vector::new(*x, *y).normalize_or_zero(),
+            _ => Vector::Y,
+        };
+        let radius = float_attribute(entity, "gravity_radius").unwrap_or(DEFAULT_RADIUS);
+        Some(Self {
+            strength: float_attribute(entity, "gravity_strength").unwrap_or(DEFAULT_STRENGTH),
+            radius: (radius > 0.0).then_some(radius),
+            falloff,
+            mode,
+            direction,
+        })
+    }
+
+    // The acceleration at `offset` from the center of a source turned by
+    // `rotation`, if it's within reach
+    fn acceleration(&self, offset: Vector, rotation: Rotation) -> Option<Vector> {
+        let distance = offset.length();
+        if self.radius.is_some_and(|radius| distance > radius) {
+            return None;
+        }
+        let strength = match (self.falloff, self.radius) {
+            (GravityFalloff::InverseSquare, _) => {
+                self.strength * (REFERENCE_DISTANCE / distance.max(MIN_DISTANCE)).powi(2)
+            }
+            (GravityFalloff::Linear, Some(radius)) => self.strength * (1.0 - distance / radius),
+            // Without a radius there's nothing to fall off to
+            (GravityFalloff::Linear, None) | (GravityFalloff::Constant, _) => self.strength,
+        };
+        let direction = match self.mode {
+            GravityMode::Point => (-offset).try_normalize()?,
+            GravityMode::Directional => rotation * self.direction,
+        };
+        Some(direction * strength)
+    }
+}
+
+impl PhysicsEngine {
+    // Add each field's pull to the dynamic bodies it reaches (scaled by
+    // their gravity scale, so only bodies with `has_gravity` feel it).
+    // Fields with a radius find those bodies in the query tree, as of the
+    // last step; ones without go through every body.
+    pub(super) fn apply_gravity_fields(&mut self) {
+        // In a fixed order, so forces add up the same way every run
+        let mut sources: Vec<_> = self.gravity_fields.iter().collect();
+        sources.sort_by_key(|(source_id, _)| **source_id);
+
+        let mut pulls: Vec<(RigidBodyHandle, Vector)> = Vec::new();
+        for (source_id, field) in sources {
+            let Some(source_handle) = self.entity_to_body.get(source_id) else {
+                continue;
+            };
+            let Some(source) = self.rigid_body_set.get(*source_handle) else {
+                continue;
+            };
+            let center = *source.position() * self.pivot(source_id);
+            let rotation = source.position().rotation;
+
+            let mut targets: Vec<RigidBodyHandle> = match field.radius {
+                Some(radius) => {
+                    let pipeline = QueryPipeline {
+                        dispatcher: &DefaultQueryDispatcher,
+                        bvh: &self.query_bvh,
+                        bodies: &self.rigid_body_set,
+                        colliders: &self.collider_set,
+                        filter: QueryFilter::new(),
+                    };
+                    let reach = Aabb::new(
+                        center - Vector::splat(radius),
+                        center + Vector::splat(radius),
+                    );
+                    pipeline
+                        .intersect_aabb_conservative(reach)
+                        .filter_map(|(_, collider)| collider.parent())
+                        .collect()
+                }
+                None => self.entity_to_body.values().copied().collect(),
+            };
+            targets.sort_by_key(|handle| handle.into_raw_parts());
+            targets.dedup();
+            for target_handle in targets {
+                if target_handle == *source_handle {
+                    continue;
+                }
+                let Some(target) = self.rigid_body_set.get(target_handle) else {
+                    continue;
+                };
+                if !target.is_dynamic() || target.gravity_scale() == 0.0 {
+                    continue;
+                }
+                let offset = target.center_of_mass() - center;
+                if let Some(acceleration) = field.acceleration(offset, rotation) {
+                    pulls.push((
+                        target_handle,
+                        acceleration * target.gravity_scale() * target.mass(),
+                    ));
+                }
+            }
+        }
+
+        // Forces are cleared after every step (see `step_scenes`)
+        for (handle, force) in pulls {
+            if let Some(body) = self.rigid_body_set.get_mut(handle) {
+                body.add_force(force, true);
+            }
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{float_attribute, PhysicsEngine};
+use crate::ecs::{AttributeType, AttributeValue, Entity};
+
+/// Debug info for one joint: world position of each anchor and the joint
+/// kind's name ("revolute", "rope", ...).
+pub type JointData = ((f32, f32), (f32, f32), String);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum JointKind {
+    Revolute,  // pin: bodies turn freely around a shared point
+    Prismatic, // slider along `axis`
+    Fixed,     // welded together
+    Rope,      // anchors at most `length` apart
+    Spring,    // pulled towards `length` apart
+}
+
+impl JointKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "revolute" => Some(Self::Revolute),
+            "prismatic" => Some(Self::Prismatic),
+            "fixed" => Some(Self::Fixed),
+            "rope" => Some(Self::Rope),
+            "spring" => Some(Self::Spring),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Revolute => "revolute",
+            Self::Prismatic => "prismatic",
+            Self::Fixed => "fixed",
+            Self::Rope => "rope",
+            Self::Spring => "spring",
+        }
+    }
+}
+
+/// How a joint connects its two bodies. Anchors are offsets from each
+/// body's origin (the entity's x/y), scaled by the entity's scale like its
+/// collider.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JointSpec {
+    pub kind: JointKind,
+    pub anchor: (f32, f32),        // on the first body
+    pub target_anchor: (f32, f32), // on the second body
+    pub axis: (f32, f32),          // prismatic: direction of travel (target's frame)
+    // Revolute: angle range in radians; prismatic: travel along the axis
+    pub limits: Option<(f32, f32)>,
+    // Rope: maximum distance; spring: rest length. None = the distance
+    // between the anchors when the joint is created
+    pub length: Option<f32>,
+    pub stiffness: f32, // spring, per unit of mass
+    pub damping: f32,   // spring
+    // Revolute / prismatic: target velocity and maximum force
+    pub motor: Option<(f32, f32)>,
+}
+
+impl JointSpec {
+    pub fn new(kind: JointKind) -> Self {
+        Self {
+            kind,
+            anchor: (0.0, 0.0),
+            target_anchor: (0.0, 0.0),
+            axis: (1.0, 0.0),
+            limits: None,
+            length: None,
+            stiffness: 50.0,
+            damping: 2.0,
+            motor: None,
+        }
+    }
+
+    /// The joint an entity declares with its `joint_*` attributes, and the
+    /// entity it connects to (`joint_type` and `joint_target` are required).
+    pub fn from_entity(entity: &Entity) -> Option<(Uuid, JointSpec)> {
+        let kind = match &entity.get_attribute_by_name("joint_type").ok()?.value {
+            AttributeValue::String(name) => JointKind::parse(name)?,
+            _ => return None,
+        };
+        let target = match entity.get_attribute_by_name("joint_target").ok()?.value {
+            AttributeValue::EntityRef(Some(target)) => target,
+            _ => return None,
+        };
+        let vector = |name: &str| match entity.get_attribute_by_name(name).ok()?.value {
+            AttributeValue::Vector2(x, y) => Some((x, y)),
+            _ => None,
+        };
+
+        let mut spec = JointSpec::new(kind);
+        spec.anchor = vector("joint_anchor").unwrap_or(spec.anchor);
+        spec.target_anchor = vector("joint_target_anchor").unwrap_or(spec.target_anchor);
+        spec.axis = vector("joint_axis").unwrap_or(spec.axis);
+        spec.limits = vector("joint_limits");
+        spec.length = float_attribute(entity, "joint_length");
+        spec.stiffness = float_attribute(entity, "joint_stiffness").unwrap_or(spec.stiffness);
+        spec.damping = float_attribute(entity, "joint_damping").unwrap_or(spec.damping);
+        spec.motor = vector("joint_motor");
+        Some((target, spec))
+    }
+
+    /// Store the joint as `entity`'s `joint_*` attributes, the reverse of
+    /// `from_entity`, replacing whatever joint the entity declared before.
+    pub fn write_to(&self, entity: &mut Entity, target: Uuid) -> Result<(), String> {
+        let vector = |(x, y): (f32, f32)| AttributeValue::Vector2(x, y);
+        let required = [
+            (
+                "joint_type",
+                AttributeType::String,
+                AttributeValue::String(self.kind.name().to_string()),
+            ),
+            (
+                "joint_target",
+                AttributeType::EntityRef,
+                AttributeValue::EntityRef(Some(target)),
+            ),
+            ("joint_anchor", AttributeType::Vector2, vector(self.anchor)),
+            (
+                "joint_target_anchor",
+                AttributeType::Vector2,
+                vector(self.target_anchor),
+            ),
+            ("joint_axis", AttributeType::Vector2, vector(self.axis)),
+            (
+                "joint_stiffness",
+                AttributeType::Float,
+                AttributeValue::Float(self.stiffness),
+            ),
+            (
+                "joint_damping",
+                AttributeType::Float,
+                AttributeValue::Float(self.damping),
+            ),
+        ];
+        for (name, data_type, value) in required {
+            entity.set_attribute(name, data_type, value)?;
+        }
+        let optional = [
+            (
+                "joint_limits",
+                AttributeType::Vector2,
+                self.limits.map(vector),
+            ),
+            (
+                "joint_motor",
+                AttributeType::Vector2,
+                self.motor.map(vector),
+            ),
+            (
+                "joint_length",
+                AttributeType::Float,
+                self.length.map(AttributeValue::Float),
+            ),
+        ];
+        for (name, data_type, value) in optional {
+            match value {
+                Some(value) => {
+                    entity.set_attribute(name, data_type, value)?;
+                }
+                None => {
+                    if let Ok(id) = entity.get_attribute_by_name(name).map(|attr| attr.id) {
+                        entity.delete_attribute(id)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The motorized / limited axis of revolute and prismatic joints
+    fn axis_kind(&self) -> Option<JointAxis> {
+        match self.kind {
+            JointKind::Revolute => Some(JointAxis::AngX),
+            JointKind::Prismatic => Some(JointAxis::LinX),
+            _ => None,
+        }
+    }
+
+    // Rapier's first body is the target, so the axis is in the target's
+    // frame and motors move the first entity relative to it. The anchors
+    // are already scaled
+    fn build(&self, anchor: Vector, target_anchor: Vector, length: f32) -> GenericJoint {
+        let (anchor1, anchor2) = (target_anchor, anchor);
+        let mut joint: GenericJoint = match self.kind {
+            JointKind::Revolute => RevoluteJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointKind::Prismatic => {
+                let axis = Vector::new(self.axis.0, self.axis.1).normalize_or(Vector::X);
+                PrismaticJointBuilder::new(axis)
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2)
+                    .into()
+            }
+            JointKind::Fixed => FixedJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointKind::Rope => RopeJointBuilder::new(length)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            // Mass-independent: bodies are pixel-sized, so their masses are
+            // in the thousands
+            JointKind::Spring => SpringJointBuilder::new(length, self.stiffness, self.damping)
+                .spring_model(MotorModel::AccelerationBased)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+        };
+        if let Some(axis) = self.axis_kind() {
+            if let Some((min, max)) = self.limits {
+                joint.set_limits(axis, [min, max]);
+            }
+            if let Some((velocity, max_force)) = self.motor {
+                joint.set_motor_velocity(axis, velocity, 1.0);
+                joint.set_motor_max_force(axis, max_force);
+            }
+        }
+        // Jointed bodies don't collide with each other (chains, ragdolls)
+        joint.set_contacts_enabled(false);
+        joint
+    }
+}
+
+// A joint between two entities. Its rapier joint exists while both bodies
+// do; it goes away with either body (rapier removes attached joints) and
+// is made again when the body is rebuilt (`attach_joints`).
+#[derive(Serialize, Deserialize)]
+pub(super) struct Joint {
+    entity1: Uuid,
+    entity2: Uuid,
+    spec: JointSpec,
+    handle: Option<ImpulseJointHandle>,
+}
+
+impl PhysicsEngine {
+    /// Connect two entities' bodies. Returns the new joint's id, for
+    /// `remove_joint` and the motor / limit setters.
+    pub fn create_joint(
+        &mut self,
+        entity1: Uuid,
+        entity2: Uuid,
+        spec: JointSpec,
+    ) -> Result<Uuid, String> {
+        self.check_joint_entities(entity1, entity2)?;
+        let joint_id = Uuid::new_v4();
+        self.insert_joint(joint_id, entity1, entity2, spec);
+        Ok(joint_id)
+    }
+
+    /// Whether two entities can be jointed now: both have bodies, and
+    /// they're different entities.
+    pub fn check_joint_entities(&self, entity1: Uuid, entity2: Uuid) -> Result<(), String> {
+        for entity_id in [entity1, entity2] {
+            if !self.entity_to_body.contains_key(&entity_id) {
+                return Err(format!(
+                    "Entity '{}' not found in physics engine",
+                    entity_id
+                ));
+            }
+        }
+        if entity1 == entity2 {
+            return Err("A joint needs two different entities".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn remove_joint(&mut self, joint_id: Uuid) -> bool {
+        let Some(joint) = self.joints.remove(&joint_id) else {
+            return false;
+        };
+        if let Some(handle) = joint.handle {
+            self.impulse_joint_set.remove(handle, true);
+        }
+        true
+    }
+
+    pub fn has_joint(&self, joint_id: Uuid) -> bool {
+        self.joints.contains_key(&joint_id)
+    }
+
+    /// Drive a revolute (radians / s) or prismatic (pixels / s) joint.
+    pub fn set_joint_motor(
+        &mut self,
+        joint_id: Uuid,
+        target_velocity: f32,
+        max_force: f32,
+    ) -> Result<(), String> {
+        self.modify_joint(joint_id, |spec| {
+            spec.motor = Some((target_velocity, max_force))
+        })
+    }
+
+    /// Restrict a revolute (radians) or prismatic (pixels) joint's travel.
+    pub fn set_joint_limits(&mut self, joint_id: Uuid, min: f32, max: f32) -> Result<(), String> {
+        if min > max {
+            return Err(format!("Joint limits {} > {}", min, max));
+        }
+        self.modify_joint(joint_id, |spec| spec.limits = Some((min, max)))
+    }
+
+    fn modify_joint(
+        &mut self,
+        joint_id: Uuid,
+        f: impl FnOnce(&mut JointSpec),
+    ) -> Result<(), String> {
+        let joint = self
+            .joints
+            .get_mut(&joint_id)
+            .ok_or_else(|| format!("Joint '{}' not found", joint_id))?;
+        let Some(axis) = joint.spec.axis_kind() else {
+            return Err(format!(
+                "A {} joint has no motor or limits",
+                joint.spec.kind.name()
+            ));
+        };
+        f(&mut joint.spec);
+        let spec = joint.spec.clone();
+        if let Some(rapier_joint) = joint
+            .handle
+            .and_then(|handle| self.impulse_joint_set.get_mut(handle, true))
+        {
+            if let Some((min, max)) = spec.limits {
+                rapier_joint.data.set_limits(axis, [min, max]);
+            }
+            if let Some((velocity, max_force)) = spec.motor {
+                rapier_joint.data.set_motor_velocity(axis, velocity, 1.0);
+                rapier_joint.data.set_motor_max_force(axis, max_force);
+            }
+        }
+        Ok(())
+    }
+
+    /// Anchors of every joint in the world, for debug drawing.
+    pub fn get_joint_data(&self) -> Vec<JointData> {
+        let mut joints = Vec::new();
+        for joint in self.joints.values() {
+            if joint.handle.is_none() {
+                continue;
+            }
+            if let Some((anchor1, anchor2)) = self.world_anchors(joint) {
+                joints.push((
+                    (anchor1.x, anchor1.y),
+                    (anchor2.x, anchor2.y),
+                    joint.spec.kind.name().to_string(),
+                ));
+            }
+        }
+        joints
+    }
+
+    /// (Re)read the joint `entity` declares with its attributes; its id is
+    /// the entity's. A joint it declared before is replaced (or removed, if
+    /// the attributes no longer describe one); an unchanged declaration
+    /// keeps its joint, and the length a rope or spring was made with.
+    pub fn declare_joint(&mut self, entity: &Entity) {
+        let declared = JointSpec::from_entity(entity).filter(|(target, _)| *target != entity.id);
+        if let (Some((target, spec)), Some(joint)) = (&declared, self.joints.get(&entity.id)) {
+            let spec = JointSpec {
+                length: spec.length.or(joint.spec.length),
+                ..spec.clone()
+            };
+            if joint.entity2 == *target && joint.spec == spec {
+                return;
+            }
+        }
+        self.remove_joint(entity.id);
+        if let Some((target, spec)) = declared {
+            self.insert_joint(entity.id, entity.id, target, spec);
+        }
+    }
+
+    fn insert_joint(&mut self, joint_id: Uuid, entity1: Uuid, entity2: Uuid, spec: JointSpec) {
+        self.joints.insert(
+            joint_id,
+            Joint {
+                entity1,
+                entity2,
+                spec,
+                handle: None,
+            },
+        );
+        self.attach_joints(entity1);
+    }
+
+    // Make the rapier joints of `entity_id`'s joints whose bodies both exist
+    pub(super) fn attach_joints(&mut self, entity_id: Uuid) {
+        for joint in self.joints.values_mut() {
+            if joint.handle.is_some() || (joint.entity1 != entity_id && joint.entity2 != entity_id)
+            {
+                continue;
+            }
+            let (Some(&body1), Some(&body2)) = (
+                self.entity_to_body.get(&joint.entity1),
+                self.entity_to_body.get(&joint.entity2),
+            ) else {
+                continue;
+            };
+            let scale =
+                |entity_id: &Uuid| self.scales.get(entity_id).copied().unwrap_or(Vector::ONE);
+            let anchor = scale(&joint.entity1) * Vector::from(joint.spec.anchor);
+            let target_anchor = scale(&joint.entity2) * Vector::from(joint.spec.target_anchor);
+            // Rope and spring lengths default to the distance at creation,
+            // kept from then on
+            let length = *joint.spec.length.get_or_insert_with(|| {
+                let world = |body: RigidBodyHandle, anchor| {
+                    self.rigid_body_set[body].position().transform_point(anchor)
+                };
+                world(body1, anchor).distance(world(body2, target_anchor))
+            });
+            let data = joint
+                .spec
+                .build(anchor, target_anchor, length.max(f32::EPSILON));
+            joint.handle = Some(self.impulse_joint_set.insert(body2, body1, data, true));
+        }
+    }
+
+    // Forget the rapier joints that went away with `entity_id`'s body
+    pub(super) fn detach_joints(&mut self, entity_id: Uuid) {
+        for joint in self.joints.values_mut() {
+            if joint.entity1 == entity_id || joint.entity2 == entity_id {
+                joint.handle = None;
+            }
+        }
+    }
+
+    // Drop the joints of an entity that left for good (deleted, unloaded)
+    pub(super) fn forget_joints(&mut self, entity_id: Uuid) {
+        let joint_ids: Vec<Uuid> = self
+            .joints
+            .iter()
+            .filter(|(_, joint)| joint.entity1 == entity_id || joint.entity2 == entity_id)
+            .map(|(joint_id, _)| *joint_id)
+            .collect();
+        for joint_id in joint_ids {
+            self.remove_joint(joint_id);
+        }
+    }
+
+    fn world_anchors(&self, joint: &Joint) -> Option<(Vector, Vector)> {
+        let body = |entity_id: &Uuid| {
+            self.entity_to_body
+                .get(entity_id)
+                .and_then(|handle| self.rigid_body_set.get(*handle))
+        };
+        let (body1, body2) = (body(&joint.entity1)?, body(&joint.entity2)?);
+        let scale = |entity_id: &Uuid| self.scales.get(entity_id).copied().unwrap_or(Vector::ONE);
+        let anchor1 = scale(&joint.entity1) * Vector::from(joint.spec.anchor);
+        let anchor2 = scale(&joint.entity2) * Vector::from(joint.spec.target_anchor);
+        Some((
+            body1.position().transform_point(anchor1),
+            body2.position().transform_point(anchor2),
+        ))
+    }
+}
@@ -0,0 +1,220 @@
+//! Physics materials: named surface and motion settings shared by every
+//! entity that references one (a `physics_material` AssetRef attribute).
+//! They're JSON files in the project (`assets/materials/<name>.material`,
+//! see `ProjectManager::material_path`), read when a body is made and read
+//! again whenever the file changes, so an edit reaches every user, also
+//! while the game is playing.
+
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{float_attribute, PhysicsEngine};
+use crate::ecs::{AttributeValue, Entity, Scene};
+use crate::logger::LOGGER;
+
+/// How the friction (or restitution) of two touching colliders is combined.
+/// When the two rules differ, the one further down the list wins.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub const ALL: [CombineRule; 4] = [
+        CombineRule::Average,
+        CombineRule::Min,
+        CombineRule::Multiply,
+        CombineRule::Max,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CombineRule::Average => "average",
+            CombineRule::Min => "min",
+            CombineRule::Multiply => "multiply",
+            CombineRule::Max => "max",
+        }
+    }
+
+    fn to_rapier(self) -> CoefficientCombineRule {
+        match self {
+            CombineRule::Average => CoefficientCombineRule::Average,
+            CombineRule::Min => CoefficientCombineRule::Min,
+            CombineRule::Multiply => CoefficientCombineRule::Multiply,
+            CombineRule::Max => CoefficientCombineRule::Max,
+        }
+    }
+}
+
+/// Settings a material gives its entities, in place of their own
+/// `friction`, `restitution` and `density` attributes. Missing fields
+/// default to those attributes' defaults, and no drag.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    pub restitution: f32,
+    pub density: f32,
+    pub friction_combine: CombineRule,
+    pub restitution_combine: CombineRule,
+    pub linear_damping: f32,  // Drag: how fast moving bodies slow down
+    pub angular_damping: f32, // How fast spinning bodies slow down
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            friction: 0.5,
+            restitution: 0.0,
+            density: 1.0,
+            friction_combine: CombineRule::Average,
+            restitution_combine: CombineRule::Average,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+        }
+    }
+}
+
+impl PhysicsMaterial {
+    /// What an entity without a material gets: its own `friction`,
+    /// `restitution` and `density` attributes, average combining, no drag.
+    pub fn from_attributes(entity: &Entity) -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: f32| float_attribute(entity, name).unwrap_or(default);
+        Self {
+            friction: number("friction", defaults.friction),
+            restitution: number("restitution", defaults.restitution),
+            density: number("density", defaults.density),
+            ..defaults
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read material '{}': {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse material '{}': {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize material: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write material: {}", e))
+    }
+
+    // Onto a collider; density changes the body's mass
+    pub(super) fn apply_to_collider(&self, collider: &mut Collider) {
+        collider.set_friction(self.friction);
+        collider.set_restitution(self.restitution);
+        collider.set_density(self.density);
+        collider.set_friction_combine_rule(self.friction_combine.to_rapier());
+        collider.set_restitution_combine_rule(self.restitution_combine.to_rapier());
+    }
+
+    pub(super) fn apply_to_body(&self, body: &mut RigidBody) {
+        body.set_linear_damping(self.linear_damping);
+        body.set_angular_damping(self.angular_damping);
+    }
+}
+
+// A material as last read, with the file's modification time then
+#[derive(Serialize, Deserialize)]
+pub(super) struct CachedMaterial {
+    modified: Option<SystemTime>,
+    material: PhysicsMaterial,
+}
+
+// The file an entity's `physics_material` attribute points at, if any
+pub(super) fn material_path(entity: &Entity) -> Option<&Path> {
+    match &entity.get_attribute_by_name("physics_material").ok()?.value {
+        AttributeValue::AssetRef(path) if !path.as_os_str().is_empty() => Some(path),
+        _ => None,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl PhysicsEngine {
+    /// The material an entity's body gets: the one it references, read
+    /// from its file the first time and cached, or else (also if the file
+    /// can't be read) one made of its own attributes.
+    pub(super) fn entity_material(&mut self, entity: &Entity) -> PhysicsMaterial {
+        let Some(path) = material_path(entity) else {
+            return PhysicsMaterial::from_attributes(entity);
+        };
+        if let Some(cached) = self.materials.get(path) {
+            return cached.material.clone();
+        }
+        match PhysicsMaterial::load(path) {
+            Ok(material) => {
+                self.materials.insert(
+                    path.to_path_buf(),
+                    CachedMaterial {
+                        modified: modified(path),
+                        material: material.clone(),
+                    },
+                );
+                material
+            }
+            Err(e) => {
+                LOGGER.warning(e);
+                PhysicsMaterial::from_attributes(entity)
+            }
+        }
+    }
+
+    /// Read again the materials whose files changed since they were last
+    /// read, and apply them to every body using them. Called at the start
+    /// of `step`.
+    pub fn reload_materials(&mut self, scenes: &[&Scene]) {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for (path, cached) in self.materials.iter_mut() {
+            let now = modified(path);
+            if now == cached.modified {
+                continue;
+            }
+            cached.modified = now;
+            match PhysicsMaterial::load(path) {
+                Ok(material) => {
+                    cached.material = material;
+                    changed.push(path.clone());
+                }
+                // Half-written or broken: keep using the last good one
+                Err(e) => LOGGER.warning(e),
+            }
+        }
+        if changed.is_empty() {
+            return;
+        }
+
+        let users: Vec<&Entity> = self
+            .entity_to_body
+            .keys()
+            .filter_map(|id| {
+                scenes
+                    .iter()
+                    .find_map(|scene| scene.entities.get(id))
+                    .filter(|entity| {
+                        material_path(entity).is_some_and(|path| changed.iter().any(|c| c == path))
+                    })
+            })
+            .collect();
+        for entity in users {
+            self.update_body_properties(entity);
+        }
+    }
+}
@@ -1,12 +1,31 @@
-use crate::ecs::{AttributeValue, Entity, Scene};
-use image::GenericImageView;
+use crate::ecs::{AttributeValue, Entity, ResourceKind, Scene, SceneEvent, WorldTransform};
+use rapier2d::parry::partitioning::Bvh;
 use rapier2d::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-/// Debug info for one collider: world position (x, y), size (w, h), and
-/// shape name ("Circle" / "Rectangle").
-pub type ColliderData = ((f32, f32), (f32, f32), String);
+mod auto_collider;
+mod character;
+mod events;
+mod gravity_fields;
+mod joints;
+mod material;
+mod queries;
+mod shapes;
+mod snapshot;
+
+pub use auto_collider::{auto_collider_cache_path, auto_collider_polygons, generate_auto_collider};
+pub use events::ContactEvent;
+pub use joints::{JointData, JointKind, JointSpec};
+pub use material::{CombineRule, PhysicsMaterial};
+pub use queries::{QueryHit, QueryOptions, QueryShape};
+
+/// Debug info for one collider (or part of a compound one): world position
+/// (x, y), size (w, h), shape name ("Circle" / "Rectangle" / "Capsule" /
+/// "Polygon" / "Polyline"), and the outline in world space for the last
+/// three (closed except for polylines; position and size are its bounds).
+/// Turned rectangles come as polygons.
+pub type ColliderData = ((f32, f32), (f32, f32), String, Vec<(f32, f32)>);
 
 pub struct PhysicsEngine {
     // Global gravity force applied to all dynamic bodies
@@ -35,7 +54,7 @@ pub struct PhysicsEngine {
     // Stores and manages all colliders (shapes that define how objects collide)
     collider_set: ColliderSet,
 
-    // For future: Handles physical joints/constraints between bodies
+    // Joints between bodies (see joints.rs)
     impulse_joint_set: ImpulseJointSet,
 
     // For future: Handles more complex joint systems
@@ -52,6 +71,38 @@ pub struct PhysicsEngine {
 
     // Store position attribute IDs for quick updates
     entity_position_attrs: HashMap<Uuid, Uuid>,
+
+    // Points bodies turn about, from their entity's x/y (see `body_pose`)
+    pivots: HashMap<Uuid, Vector>,
+
+    // World scale each body was made with; joint anchors are scaled by it
+    scales: HashMap<Uuid, Vector>,
+
+    // Entities left out of the world because they're disabled; their bodies
+    // are rebuilt once they're active again (see `sync_enabled`)
+    disabled_entities: HashSet<Uuid>,
+
+    // Joints by id: declared ones (`joint_*` attributes) have the declaring
+    // entity's id, ones made with `create_joint` a new one
+    joints: HashMap<Uuid, joints::Joint>,
+
+    // Collision and trigger events of the steps since the last drain
+    contact_events: Vec<ContactEvent>,
+    // Colliders removed since the last step, with their entity and sensor
+    // flag: rapier reports their ended contacts during the next step
+    removed_colliders: HashMap<ColliderHandle, (Uuid, bool)>,
+
+    // Collider bounds as of the last step, for queries (see queries.rs)
+    query_bvh: Bvh,
+
+    // Character controllers of `is_character` entities (see character.rs)
+    characters: HashMap<Uuid, character::Character>,
+
+    // Physics materials read so far, by file (see material.rs)
+    materials: HashMap<std::path::PathBuf, material::CachedMaterial>,
+
+    // Fields of `creates_gravity` entities (see gravity_fields.rs)
+    gravity_fields: HashMap<Uuid, gravity_fields::GravityField>,
 }
 
 impl Default for PhysicsEngine {
@@ -96,6 +147,16 @@ impl PhysicsEngine {
             entity_to_collider: HashMap::new(),
             time_step: 1.0 / 60.0, // Default 60Hz physics
             entity_position_attrs: HashMap::new(),
+            pivots: HashMap::new(),
+            scales: HashMap::new(),
+            disabled_entities: HashSet::new(),
+            joints: HashMap::new(),
+            contact_events: Vec::new(),
+            removed_colliders: HashMap::new(),
+            query_bvh: Bvh::new(),
+            characters: HashMap::new(),
+            materials: HashMap::new(),
+            gravity_fields: HashMap::new(),
         }
     }
 
@@ -132,85 +193,47 @@ impl PhysicsEngine {
         };
     }
 
-    fn create_collider(
-        &self,
-        entity: &Entity,
-        density: f32,
-        friction: f32,
-        restitution: f32,
-    ) -> Collider {
+    fn create_collider(&self, entity: &Entity, scale: (f32, f32)) -> Collider {
         // Collider size: explicit `collider_width`/`collider_height` float
         // attributes take priority; otherwise fall back to the first sprite
         // image's pixel dimensions (which over-approximates sprites with
-        // transparent padding).
-        let attr_float = |name: &str| {
-            entity.get_attribute_by_name(name).ok().and_then(|attr| {
-                if let AttributeValue::Float(v) = attr.value {
-                    Some(v)
-                } else {
-                    None
-                }
-            })
-        };
-
-        let explicit_size = match (attr_float("collider_width"), attr_float("collider_height")) {
-            (Some(w), Some(h)) => Some((w, h)),
-            _ => None,
+        // transparent padding; `collider_shape = "auto"` traces the sprite
+        // instead). Either is scaled like the sprite.
+        let size = explicit_size(entity).or_else(|| image_size(entity));
+
+        // Default if there's no usable shape (e.g. no explicit size and no
+        // loadable image)
+        let scale = Vector::new(scale.0, scale.1);
+        let collider_builder = match shapes::collider_shape(entity, size, scale) {
+            Some((shape, offset)) => ColliderBuilder::new(shape).translation(offset),
+            None => ColliderBuilder::ball(0.5),
         };
+        collider_builder.build()
+    }
 
-        let size = explicit_size.or_else(|| {
-            let image_path = entity.get_image(0).ok()?;
-            let img = image::open(image_path).ok()?;
-            let (width, height) = img.dimensions();
-            Some((width as f32, height as f32))
-        });
-
-        // Collider shape: explicit `collider_shape` string attribute
-        // ("circle" or "rectangle"), otherwise the legacy heuristic:
-        // near-square sprites become circles.
-        let explicit_shape = entity
-            .get_attribute_by_name("collider_shape")
-            .ok()
-            .and_then(|attr| match &attr.value {
-                AttributeValue::String(s) => Some(s.to_lowercase()),
-                _ => None,
-            });
-
-        let collider_builder = match size {
-            Some((width, height)) => {
-                // Colliders span from the entity's x/y (sprite top-left), so
-                // offset the shape's center by half its size
-                let offset = Vector::new(width / 2.0, height / 2.0);
-
-                let is_circle = match explicit_shape.as_deref() {
-                    Some("circle") => true,
-                    Some(_) => false,
-                    // Legacy heuristic: near-square sprites become circles
-                    None => {
-                        let ratio = width / height;
-                        ratio > 0.9 && ratio < 1.1
-                    }
-                };
+    /// Add a root entity, spawned at its x/y, `rotation` and `scale`
+    /// attributes. Entities with a parent should go through `load_scene` (or
+    /// `add_entity_with_transform`) so they spawn at their world transform
+    /// instead of their local one.
+    pub fn add_entity(&mut self, entity: &Entity) {
+        self.add_entity_with_transform(entity, &WorldTransform::local(entity));
+    }
 
-                if is_circle {
-                    ColliderBuilder::ball(width / 2.0).translation(offset)
-                } else {
-                    ColliderBuilder::cuboid(width / 2.0, height / 2.0).translation(offset)
-                }
-            }
-            // Default if there's no explicit size and no loadable image
-            None => ColliderBuilder::ball(0.5),
+    /// Add an entity with an explicit world-space spawn position, and its
+    /// own `rotation` and `scale`.
+    pub fn add_entity_at(&mut self, entity: &Entity, world_position: (f32, f32)) {
+        let transform = WorldTransform {
+            x: world_position.0,
+            y: world_position.1,
+            ..WorldTransform::local(entity)
         };
-
-        // Add physics properties
-        collider_builder
-            .density(density)
-            .friction(friction)
-            .restitution(restitution)
-            .build()
+        self.add_entity_with_transform(entity, &transform);
     }
 
-    pub fn add_entity(&mut self, entity: &Entity) {
+    /// Add an entity spawned at a world transform (`Scene::world_transform`):
+    /// its collider is scaled like the sprite, and the body starts turned by
+    /// the rotation about the sprite's center (see `body_pose`).
+    pub fn add_entity_with_transform(&mut self, entity: &Entity, transform: &WorldTransform) {
         let required_attributes = ["has_gravity", "has_collision", "creates_gravity"];
         let should_skip = required_attributes
             .iter()
@@ -233,11 +256,13 @@ impl PhysicsEngine {
             self.entity_position_attrs.insert(entity.id, pos_attr.id);
         }
 
-        // Spawn position comes from the x/y attributes: they exist on every
-        // entity, they're what the editor edits and the renderer draws from.
-        // (The optional "position" Vector2 attribute is only kept in sync on
-        // write-back; older scenes carry stale values in it.)
-        let position = Vector::new(entity.get_x(), entity.get_y());
+        // Spawn position comes from the x/y attributes (resolved through the
+        // parent chain by the caller): they exist on every entity, they're
+        // what the editor edits and the renderer draws from. (The optional
+        // "position" Vector2 attribute is only kept in sync on write-back;
+        // older scenes carry stale values in it.)
+        let pivot = pivot(entity, transform.scale);
+        let pose = body_pose((transform.x, transform.y), transform.rotation, pivot);
 
         let is_movable = entity
             .get_attribute_by_name("is_movable")
@@ -272,38 +297,9 @@ impl PhysicsEngine {
             })
             .unwrap_or(true);
 
-        let friction = entity
-            .get_attribute_by_name("friction")
-            .and_then(|attr| {
-                if let AttributeValue::Float(v) = attr.value {
-                    Ok(v)
-                } else {
-                    Err("Attribute value is not a float".to_string())
-                }
-            })
-            .unwrap_or(0.5);
-
-        let restitution = entity
-            .get_attribute_by_name("restitution")
-            .and_then(|attr| {
-                if let AttributeValue::Float(v) = attr.value {
-                    Ok(v)
-                } else {
-                    Err("Attribute value is not a float".to_string())
-                }
-            })
-            .unwrap_or(0.0);
-
-        let density = entity
-            .get_attribute_by_name("density")
-            .and_then(|attr| {
-                if let AttributeValue::Float(v) = attr.value {
-                    Ok(v)
-                } else {
-                    Err("Attribute value is not a float".to_string())
-                }
-            })
-            .unwrap_or(1.0);
+        // Friction, restitution, density and drag: from the entity's
+        // physics material if it has one, else its own attributes
+        let material = self.entity_material(entity);
 
         let can_rotate = entity
             .get_attribute_by_name("can_rotate")
@@ -340,28 +336,37 @@ impl PhysicsEngine {
             })
             .unwrap_or(1.0);
 
+        let is_character = bool_attribute(entity, "is_character").unwrap_or(false);
+
         // Create rigid body
         //
+        // - character: moved by its character controller (see character.rs)
         // - kinematic: moved only via set_velocity; ignores gravity, forces
         //   and collisions with dynamic bodies (script-driven obstacles)
         // - dynamic (is_movable): full simulation
         // - fixed: static geometry
-        let rigid_body = if is_kinematic {
+        let mut rigid_body = if is_character {
+            RigidBodyBuilder::kinematic_position_based()
+                .pose(pose)
+                .can_sleep(false)
+                .build()
+        } else if is_kinematic {
             RigidBodyBuilder::kinematic_velocity_based()
-                .translation(position)
+                .pose(pose)
                 // Script-driven bodies must never be put to sleep by the
                 // engine, or they freeze mid-motion when their velocity
                 // stays constant
                 .can_sleep(false)
                 .build()
         } else if is_movable {
-            let mut rb = RigidBodyBuilder::dynamic()
-                .translation(position)
-                .gravity_scale(if affected_by_gravity {
-                    gravity_scale
-                } else {
-                    0.0
-                });
+            let mut rb =
+                RigidBodyBuilder::dynamic()
+                    .pose(pose)
+                    .gravity_scale(if affected_by_gravity {
+                        gravity_scale
+                    } else {
+                        0.0
+                    });
 
             if !can_rotate {
                 rb = rb.lock_rotations();
@@ -369,14 +374,28 @@ impl PhysicsEngine {
 
             rb.build()
         } else {
-            RigidBodyBuilder::fixed().translation(position).build()
+            RigidBodyBuilder::fixed().pose(pose).build()
         };
 
+        material.apply_to_body(&mut rigid_body);
         let rb_handle = self.rigid_body_set.insert(rigid_body);
 
         // Create collider with automatic shape detection
         if has_collision {
-            let collider = self.create_collider(entity, density, friction, restitution);
+            let mut collider = self.create_collider(entity, transform.scale);
+            material.apply_to_collider(&mut collider);
+            // Queries and contact events map colliders back to entities
+            // through this
+            collider.user_data = entity.id.as_u128();
+            collider.set_active_events(ActiveEvents::COLLISION_EVENTS);
+            collider.set_collision_groups(collision_groups(entity));
+            // Sensors report overlaps (trigger events) without pushing back.
+            // They also see kinematic and fixed bodies, e.g. a fixed
+            // checkpoint and a kinematic player.
+            if bool_attribute(entity, "is_sensor").unwrap_or(false) {
+                collider.set_sensor(true);
+                collider.set_active_collision_types(ActiveCollisionTypes::all());
+            }
             let collider_handle =
                 self.collider_set
                     .insert_with_parent(collider, rb_handle, &mut self.rigid_body_set);
@@ -384,10 +403,34 @@ impl PhysicsEngine {
         }
 
         self.entity_to_body.insert(entity.id, rb_handle);
+        self.pivots.insert(entity.id, pivot);
+        self.scales
+            .insert(entity.id, Vector::new(transform.scale.0, transform.scale.1));
+        if is_character {
+            self.characters
+                .insert(entity.id, character::Character::new(entity));
+        }
+        if let Some(field) = gravity_fields::GravityField::new(entity) {
+            self.gravity_fields.insert(entity.id, field);
+        }
+        self.attach_joints(entity.id);
     }
 
     pub fn remove_entity(&mut self, entity_id: Uuid) {
         self.entity_position_attrs.remove(&entity_id);
+        self.pivots.remove(&entity_id);
+        self.scales.remove(&entity_id);
+        self.characters.remove(&entity_id);
+        self.gravity_fields.remove(&entity_id);
+        self.detach_joints(entity_id);
+        if let Some((handle, collider)) = self
+            .entity_to_collider
+            .get(&entity_id)
+            .and_then(|handle| Some((*handle, self.collider_set.get(*handle)?)))
+        {
+            self.removed_colliders
+                .insert(handle, (entity_id, collider.is_sensor()));
+        }
         if let Some(rb_handle) = self.entity_to_body.remove(&entity_id) {
             self.rigid_body_set.remove(
                 rb_handle,
@@ -410,49 +453,47 @@ impl PhysicsEngine {
     }
 
     pub fn step(&mut self, scene: &mut Scene) -> Vec<(Uuid, Uuid, AttributeValue)> {
-        // Process custom gravity fields
-        for (_, entity1) in &scene.entities {
-            if let Ok(creates_gravity) = entity1.get_attribute_by_name("creates_gravity") {
-                if let AttributeValue::Boolean(true) = creates_gravity.value {
-                    let pos1 = if let Ok(pos) = entity1.get_attribute_by_name("position") {
-                        if let AttributeValue::Vector2(x, y) = pos.value {
-                            Vector::new(x, y)
-                        } else {
-                            continue;
-                        }
-                    } else {
-                        continue;
-                    };
+        self.step_scenes(&[&*scene])
+    }
 
-                    // Apply gravity to other entities
-                    for (_, entity2) in &scene.entities {
-                        if entity1.id == entity2.id {
-                            continue;
-                        }
-
-                        if let Ok(affected_by_gravity) =
-                            entity2.get_attribute_by_name("has_gravity")
-                        {
-                            if let AttributeValue::Boolean(true) = affected_by_gravity.value {
-                                if let Some(rb_handle) = self.entity_to_body.get(&entity2.id) {
-                                    if let Some(rb) = self.rigid_body_set.get_mut(*rb_handle) {
-                                        let pos2 = rb.translation();
-                                        let direction = pos1 - pos2;
-                                        let distance = direction.length();
-                                        if distance > 0.0 {
-                                            let force = direction * (1.0 / (distance * distance));
-                                            rb.add_force(force * 10.0, true); // Scale force as needed
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Step one world holding the bodies of several scenes (additively loaded
+    /// scenes share it, so their entities collide with each other). Each
+    /// update is for an entity of exactly one of `scenes`.
+    pub fn step_scenes(&mut self, scenes: &[&Scene]) -> Vec<(Uuid, Uuid, AttributeValue)> {
+        self.sync_enabled(scenes);
+        self.reload_materials(scenes);
+
+        self.apply_gravity_fields();
+
+        // Non-dynamic bodies under a parent follow the parent (characters
+        // go where their controller takes them)
+        for (entity_id, rb_handle) in &self.entity_to_body {
+            if self.characters.contains_key(entity_id) {
+                continue;
+            }
+            let Some(scene) = scene_of(scenes, *entity_id) else {
+                continue;
+            };
+            if scene.get_parent(*entity_id).is_none() {
+                continue;
+            }
+            let Ok(world) = scene.world_transform(*entity_id) else {
+                continue;
+            };
+            let pose = body_pose((world.x, world.y), world.rotation, self.pivot(entity_id));
+            if let Some(rb) = self.rigid_body_set.get_mut(*rb_handle) {
+                if rb.is_kinematic() {
+                    rb.set_next_kinematic_position(pose);
+                } else if rb.is_fixed() {
+                    rb.set_position(pose, true);
                 }
             }
         }
 
+        self.move_characters();
+
         // Run physics simulation
+        let events = events::EventCollector::default();
         self.physics_pipeline.step(
             self.gravity,
             &self.integration_parameters,
@@ -465,49 +506,347 @@ impl PhysicsEngine {
             &mut self.multibody_joint_set,
             &mut self.ccd_solver,
             &(),
-            &(),
+            &events,
         );
+        self.record_contact_events(events.into_events());
+        self.refresh_query_bvh();
+
+        // User-applied and gravity-field forces are persistent in Rapier;
+        // clear them each step so they don't accumulate across frames.
+        for rb_handle in self.entity_to_body.values() {
+            if let Some(rb) = self.rigid_body_set.get_mut(*rb_handle) {
+                rb.reset_forces(true);
+            }
+        }
 
         // Update positions using stored attribute IDs
         let mut updates = Vec::new();
 
-        for (entity_id, rb_handle) in &self.entity_to_body {
-            if let Some(rb) = self.rigid_body_set.get_mut(*rb_handle) {
-                // User-applied and gravity-field forces are persistent in Rapier;
-                // clear them each step so they don't accumulate across frames.
-                rb.reset_forces(true);
+        for entity_id in self.entity_to_body.keys() {
+            let Some((world_position, world_rotation)) = self.body_placement(entity_id) else {
+                continue;
+            };
+
+            // Update the "position" Vector2 attribute if the entity has one
+            if let Some(pos_attr_id) = self.entity_position_attrs.get(entity_id) {
+                updates.push((
+                    *entity_id,
+                    *pos_attr_id,
+                    AttributeValue::Vector2(world_position.0, world_position.1),
+                ));
+            }
 
-                let position = rb.translation();
+            // Always sync the x/y attributes, these are used to render in the view.
+            // Bodies live in world space; x/y are local to the parent.
+            let Some(scene) = scene_of(scenes, *entity_id) else {
+                continue;
+            };
+            let ((x, y), rotation) =
+                self.world_to_local(scene, *entity_id, world_position, world_rotation);
+            if let Some(entity) = scene.entities.get(entity_id) {
+                if let Ok(x_attr) = entity.get_attribute_by_name("x") {
+                    updates.push((*entity_id, x_attr.id, AttributeValue::Float(x)));
+                }
+
+                if let Ok(y_attr) = entity.get_attribute_by_name("y") {
+                    updates.push((*entity_id, y_attr.id, AttributeValue::Float(y)));
+                }
 
-                // Update the "position" Vector2 attribute if the entity has one
-                if let Some(pos_attr_id) = self.entity_position_attrs.get(entity_id) {
+                // Rotation too, if the entity has a `rotation` attribute. It
+                // keeps counting past a full turn rather than jumping back
+                if let Ok(rotation_attr) = entity.get_attribute_by_name("rotation") {
+                    let current = match rotation_attr.value {
+                        AttributeValue::Float(current) => current,
+                        _ => 0.0,
+                    };
+                    let turned = (rotation - current + 180.0).rem_euclid(360.0) - 180.0;
                     updates.push((
                         *entity_id,
-                        *pos_attr_id,
-                        AttributeValue::Vector2(position.x, position.y),
+                        rotation_attr.id,
+                        AttributeValue::Float(current + turned),
                     ));
                 }
+            }
+        }
 
-                // Always sync the x/y attributes, these are used to render in the view
-                if let Some(entity) = scene.entities.get(entity_id) {
-                    if let Ok(x_attr) = entity.get_attribute_by_name("x") {
-                        updates.push((*entity_id, x_attr.id, AttributeValue::Float(position.x)));
-                    }
+        updates
+    }
+
+    pub fn load_scene(&mut self, scene: &Scene) {
+        // Joints are made as soon as both of their bodies are in the world
+        for entity in scene.entities.values() {
+            if entity.get_attribute_by_name("joint_type").is_ok() {
+                self.declare_joint(entity);
+            }
+        }
+        for (entity_id, entity) in &scene.entities {
+            if !scene.is_active(*entity_id) {
+                self.disabled_entities.insert(*entity_id);
+                continue;
+            }
+            let transform = scene
+                .world_transform(*entity_id)
+                .unwrap_or_else(|_| WorldTransform::local(entity));
+            self.add_entity_with_transform(entity, &transform);
+        }
+    }
+
+    /// Remove the bodies of entities that were disabled since the last call
+    /// and rebuild (at their current world transform, at rest) the bodies of
+    /// entities that were enabled again. Called at the start of `step`.
+    pub fn sync_enabled(&mut self, scenes: &[&Scene]) {
+        let disabled: Vec<Uuid> = self
+            .entity_to_body
+            .keys()
+            .filter(|id| scene_of(scenes, **id).is_some_and(|scene| !scene.is_active(**id)))
+            .copied()
+            .collect();
+        for entity_id in disabled {
+            self.remove_entity(entity_id);
+            self.disabled_entities.insert(entity_id);
+        }
 
-                    if let Ok(y_attr) = entity.get_attribute_by_name("y") {
-                        updates.push((*entity_id, y_attr.id, AttributeValue::Float(position.y)));
+        // Deleted entities don't come back
+        self.disabled_entities
+            .retain(|id| scene_of(scenes, *id).is_some());
+        let enabled: Vec<Uuid> = self
+            .disabled_entities
+            .iter()
+            .filter(|id| scene_of(scenes, **id).is_some_and(|scene| scene.is_active(**id)))
+            .copied()
+            .collect();
+        for entity_id in enabled {
+            self.disabled_entities.remove(&entity_id);
+            let Some(scene) = scene_of(scenes, entity_id) else {
+                continue;
+            };
+            let entity = &scene.entities[&entity_id];
+            let transform = scene
+                .world_transform(entity_id)
+                .unwrap_or_else(|_| WorldTransform::local(entity));
+            self.add_entity_with_transform(entity, &transform);
+        }
+    }
+
+    /// Bring the world up to date with entity changes (`Scene::drain_events`)
+    /// without reloading anything: deleted entities leave the world;
+    /// `friction`, `restitution`, `density`, `physics_material`,
+    /// `has_gravity`, `gravity_scale`, `can_rotate`, the collision layers
+    /// and the character settings are changed on the existing
+    /// body; `is_movable`, `is_kinematic`, `is_character`, `has_collision`,
+    /// the collider attributes, `scale` and the sprite (which size the
+    /// collider) rebuild the entity's body where it is, keeping its rotation
+    /// and velocity. Entities without a body are left alone.
+    pub fn apply_scene_events(&mut self, scenes: &[&Scene], events: &[SceneEvent]) {
+        let mut rebuild: Vec<Uuid> = Vec::new();
+        for event in events {
+            let entity_id = event.entity_id();
+            if let SceneEvent::EntityDeleted(_) = event {
+                self.remove_entity(entity_id);
+                self.disabled_entities.remove(&entity_id);
+                self.forget_joints(entity_id);
+                continue;
+            }
+            if event
+                .attribute_name()
+                .is_some_and(|name| name.starts_with("joint_"))
+            {
+                if let Some(entity) =
+                    scene_of(scenes, entity_id).and_then(|s| s.entities.get(&entity_id))
+                {
+                    self.declare_joint(entity);
+                }
+                continue;
+            }
+            if !self.entity_to_body.contains_key(&entity_id) {
+                continue;
+            }
+            let Some(entity) = scene_of(scenes, entity_id).and_then(|s| s.entities.get(&entity_id))
+            else {
+                continue;
+            };
+            let rebuilds = match event {
+                SceneEvent::ResourceAdded {
+                    kind: ResourceKind::Image,
+                    ..
+                }
+                | SceneEvent::ResourceRemoved {
+                    kind: ResourceKind::Image,
+                    ..
+                } => true,
+                _ => match event.attribute_name() {
+                    Some(
+                        "friction"
+                        | "restitution"
+                        | "density"
+                        | "has_gravity"
+                        | "gravity_scale"
+                        | "can_rotate"
+                        | "collision_layers"
+                        | "collision_mask"
+                        | "character_max_slope"
+                        | "character_step_height"
+                        | "character_snap_distance"
+                        | "physics_material"
+                        | "creates_gravity"
+                        | "gravity_strength"
+                        | "gravity_radius"
+                        | "gravity_falloff"
+                        | "gravity_mode"
+                        | "gravity_direction",
+                    ) => {
+                        self.update_body_properties(entity);
+                        false
                     }
+                    Some(
+                        "is_movable" | "is_kinematic" | "is_character" | "has_collision"
+                        | "is_sensor" | "collider_width" | "collider_height" | "collider_shape"
+                        | "collider_points" | "collider_shapes" | "scale",
+                    ) => true,
+                    _ => false,
+                },
+            };
+            if rebuilds && !rebuild.contains(&entity_id) {
+                rebuild.push(entity_id);
+            }
+        }
+
+        for entity_id in rebuild {
+            let Some(scene) = scene_of(scenes, entity_id) else {
+                continue;
+            };
+            let Some(entity) = scene.entities.get(&entity_id) else {
+                continue;
+            };
+            let (Some(((x, y), rotation)), Some(old)) = (
+                self.body_placement(&entity_id),
+                self.entity_to_body
+                    .get(&entity_id)
+                    .and_then(|handle| self.rigid_body_set.get(*handle)),
+            ) else {
+                continue;
+            };
+            let (linvel, angvel) = (old.linvel(), old.angvel());
+
+            // The scale may be what changed, so it comes from the scene
+            let scale = scene
+                .world_transform(entity_id)
+                .map_or(WorldTransform::local(entity).scale, |world| world.scale);
+            let transform = WorldTransform {
+                x,
+                y,
+                rotation,
+                scale,
+                ..Default::default()
+            };
+            self.add_entity_with_transform(entity, &transform);
+            if let Some(body) = self
+                .entity_to_body
+                .get(&entity_id)
+                .and_then(|handle| self.rigid_body_set.get_mut(*handle))
+            {
+                if !body.is_fixed() {
+                    body.set_linvel(linvel, true);
+                    body.set_angvel(angvel, true);
                 }
             }
         }
+    }
 
-        updates
+    // Material, gravity and rotation lock from the entity's attributes, with
+    // the same defaults as `add_entity_with_transform`
+    fn update_body_properties(&mut self, entity: &Entity) {
+        let flag = |name: &str, default: bool| bool_attribute(entity, name).unwrap_or(default);
+        let number = |name: &str, default: f32| float_attribute(entity, name).unwrap_or(default);
+
+        let material = self.entity_material(entity);
+        if let Some(collider) = self
+            .entity_to_collider
+            .get(&entity.id)
+            .and_then(|handle| self.collider_set.get_mut(*handle))
+        {
+            material.apply_to_collider(collider);
+            collider.set_collision_groups(collision_groups(entity));
+        }
+        if let Some(character) = self.characters.get_mut(&entity.id) {
+            character.update(entity);
+        }
+        match gravity_fields::GravityField::new(entity) {
+            Some(field) if self.entity_to_body.contains_key(&entity.id) => {
+                self.gravity_fields.insert(entity.id, field);
+            }
+            _ => {
+                self.gravity_fields.remove(&entity.id);
+            }
+        }
+        if let Some(body) = self
+            .entity_to_body
+            .get(&entity.id)
+            .and_then(|handle| self.rigid_body_set.get_mut(*handle))
+        {
+            material.apply_to_body(body);
+            if body.is_dynamic() {
+                let gravity_scale = if flag("has_gravity", false) {
+                    number("gravity_scale", 1.0)
+                } else {
+                    0.0
+                };
+                body.set_gravity_scale(gravity_scale, true);
+                body.lock_rotations(!flag("can_rotate", false), true);
+            }
+        }
     }
 
-    pub fn load_scene(&mut self, scene: &Scene) {
-        for (_, entity) in &scene.entities {
-            self.add_entity(entity);
+    /// Remove the bodies of a scene's entities, e.g. when the scene is
+    /// unloaded while others stay in the world.
+    pub fn unload_scene(&mut self, scene: &Scene) {
+        for entity_id in scene.entities.keys() {
+            self.remove_entity(*entity_id);
+            self.disabled_entities.remove(entity_id);
+            self.forget_joints(*entity_id);
+        }
+    }
+
+    // Convert a simulated (world) position and rotation into the entity's
+    // local x/y and rotation. A parent that is itself simulated has already
+    // moved this step, so where its body is now is used instead of its
+    // (stale) scene transform.
+    fn world_to_local(
+        &self,
+        scene: &Scene,
+        entity_id: Uuid,
+        world: (f32, f32),
+        rotation: f32,
+    ) -> ((f32, f32), f32) {
+        let Some(parent_id) = scene.get_parent(entity_id) else {
+            return (world, rotation);
+        };
+        let Ok(mut parent_world) = scene.world_transform(parent_id) else {
+            return (world, rotation);
+        };
+        if let Some(((x, y), parent_rotation)) = self.body_placement(&parent_id) {
+            parent_world.x = x;
+            parent_world.y = y;
+            parent_world.rotation = parent_rotation;
         }
+        (
+            parent_world.inverse_transform_point(world),
+            rotation - parent_world.rotation,
+        )
+    }
+
+    // Where an entity's body has it: the world x/y of its top-left and its
+    // rotation in degrees
+    fn body_placement(&self, entity_id: &Uuid) -> Option<((f32, f32), f32)> {
+        let body = self
+            .entity_to_body
+            .get(entity_id)
+            .and_then(|handle| self.rigid_body_set.get(*handle))?;
+        Some(entity_placement(body.position(), self.pivot(entity_id)))
+    }
+
+    fn pivot(&self, entity_id: &Uuid) -> Vector {
+        self.pivots.get(entity_id).copied().unwrap_or(Vector::ZERO)
     }
 
     // We should also add cleanup for scene switching
@@ -516,6 +855,16 @@ impl PhysicsEngine {
         self.entity_to_body.clear();
         self.entity_to_collider.clear();
         self.entity_position_attrs.clear();
+        self.pivots.clear();
+        self.scales.clear();
+        self.disabled_entities.clear();
+        self.joints.clear();
+        self.contact_events.clear();
+        self.removed_colliders.clear();
+        self.query_bvh = Bvh::new();
+        self.characters.clear();
+        self.materials.clear();
+        self.gravity_fields.clear();
 
         // Remove all physics objects
         self.rigid_body_set = RigidBodySet::new();
@@ -597,41 +946,6 @@ impl PhysicsEngine {
         colliding
     }
 
-    // Get all colliders and gives a shape for rendering
-    // Returns:
-    // - (f32, f32): The world coordinate of the collider (x, y).
-    // - (f32, f32): The size of the collider in world coordinate (width, height).
-    // - String: The shape of the collider (e.g., "Circle", "Rectangle").
-    pub fn get_collider_data(&self) -> Vec<ColliderData> {
-        let mut colliders = Vec::new();
-
-        for (entity_id, collider_handle) in &self.entity_to_collider {
-            if let Some(collider) = self.collider_set.get(*collider_handle) {
-                if let Some(rb_handle) = self.entity_to_body.get(entity_id) {
-                    if let Some(_rb) = self.rigid_body_set.get(*rb_handle) {
-                        let position = (collider.translation().x, collider.translation().y);
-
-                        if let Some(ball) = collider.shape().as_ball() {
-                            colliders.push((
-                                position,
-                                (ball.radius * 2.0, ball.radius * 2.0),
-                                "Circle".to_string(),
-                            ));
-                        } else if let Some(cuboid) = collider.shape().as_cuboid() {
-                            colliders.push((
-                                position,
-                                (cuboid.half_extents.x * 2.0, cuboid.half_extents.y * 2.0),
-                                "Rectangle".to_string(),
-                            ));
-                        }
-                    }
-                }
-            }
-        }
-
-        colliders
-    }
-
     // Angular motion
     pub fn get_angular_velocity(&self, entity_id: &Uuid) -> Option<Real> {
         self.entity_to_body
@@ -692,3 +1006,114 @@ impl PhysicsEngine {
         self.entity_to_collider.contains_key(entity_id)
     }
 }
+
+// Bodies are placed so that the entity turns about its pivot, the center of
+// its sprite as drawn, like the renderer turns sprites. Colliders, joint
+// anchors and collider points stay relative to the unturned top-left (the
+// entity's x/y), so a body's local space is the same whatever its rotation.
+// This is the pose of a body whose entity has its top-left at `top_left`
+// and is turned by `rotation` degrees.
+fn body_pose(top_left: (f32, f32), rotation: f32, pivot: Vector) -> Pose {
+    let rotation = Rotation::new(rotation.to_radians());
+    let center = Vector::new(top_left.0, top_left.1) + pivot;
+    Pose::from_parts(center - rotation * pivot, rotation)
+}
+
+// The inverse of `body_pose`: the top-left and rotation (degrees) of the
+// entity of a body at `pose`
+fn entity_placement(pose: &Pose, pivot: Vector) -> ((f32, f32), f32) {
+    let top_left = *pose * pivot - pivot;
+    ((top_left.x, top_left.y), pose.rotation.angle().to_degrees())
+}
+
+/// Where a point of an entity's body (collider points, joint anchors:
+/// offsets from its x/y before its scale) is in the world, for an entity
+/// at `world` (`Scene::world_transform`). Scaled and turned about the
+/// pivot as its body is.
+pub fn body_point_to_world(
+    entity: &Entity,
+    world: &WorldTransform,
+    point: (f32, f32),
+) -> (f32, f32) {
+    let (about, pivot) = pivot_transform(entity, world);
+    about.transform_point((point.0 - pivot.0, point.1 - pivot.1))
+}
+
+/// The inverse of `body_point_to_world`.
+pub fn world_to_body_point(
+    entity: &Entity,
+    world: &WorldTransform,
+    point: (f32, f32),
+) -> (f32, f32) {
+    let (about, pivot) = pivot_transform(entity, world);
+    let (x, y) = about.inverse_transform_point(point);
+    (x + pivot.0, y + pivot.1)
+}
+
+// `world` moved onto the (scaled) pivot, and the pivot before scaling
+fn pivot_transform(entity: &Entity, world: &WorldTransform) -> (WorldTransform, (f32, f32)) {
+    let pivot = pivot(entity, (1.0, 1.0));
+    let about = WorldTransform {
+        x: world.x + pivot.x * world.scale.0,
+        y: world.y + pivot.y * world.scale.1,
+        ..*world
+    };
+    (about, (pivot.x, pivot.y))
+}
+
+// The pivot, from the entity's x/y: the center of its first image at
+// `scale`, or without one of its collider size
+fn pivot(entity: &Entity, scale: (f32, f32)) -> Vector {
+    let (width, height) = image_size(entity)
+        .or_else(|| explicit_size(entity))
+        .unwrap_or((0.0, 0.0));
+    Vector::new(width * scale.0, height * scale.1) / 2.0
+}
+
+// `collider_width` / `collider_height`, if both are set
+fn explicit_size(entity: &Entity) -> Option<(f32, f32)> {
+    float_attribute(entity, "collider_width").zip(float_attribute(entity, "collider_height"))
+}
+
+// Pixel size of the entity's first image, read from its header
+fn image_size(entity: &Entity) -> Option<(f32, f32)> {
+    let image_path = entity.get_image(0).ok()?;
+    let (width, height) = image::image_dimensions(image_path).ok()?;
+    Some((width as f32, height as f32))
+}
+
+// The scene (of those being stepped) an entity belongs to
+fn scene_of<'a>(scenes: &[&'a Scene], entity_id: Uuid) -> Option<&'a Scene> {
+    scenes
+        .iter()
+        .find(|scene| scene.entities.contains_key(&entity_id))
+        .copied()
+}
+
+fn bool_attribute(entity: &Entity, name: &str) -> Option<bool> {
+    match entity.get_attribute_by_name(name).ok()?.value {
+        AttributeValue::Boolean(value) => Some(value),
+        _ => None,
+    }
+}
+
+// Two colliders interact when each one's layers (`collision_layers`) are in
+// the other's `collision_mask`; both default to every layer
+fn collision_groups(entity: &Entity) -> InteractionGroups {
+    let bits = |name: &str| match entity.get_attribute_by_name(name).map(|attr| &attr.value) {
+        Ok(AttributeValue::Integer(value)) => Group::from_bits_retain(*value as u32),
+        _ => Group::ALL,
+    };
+    InteractionGroups::new(
+        bits("collision_layers"),
+        bits("collision_mask"),
+        InteractionTestMode::And,
+    )
+}
+
+fn float_attribute(entity: &Entity, name: &str) -> Option<f32> {
+    match entity.get_attribute_by_name(name).ok()?.value {
+        AttributeValue::Float(value) => Some(value),
+        _ => None,
+    }
+}
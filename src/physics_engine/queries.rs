@@ -0,0 +1,179 @@
+use rapier2d::parry::query::{DefaultQueryDispatcher, ShapeCastOptions};
+use rapier2d::prelude::*;
+use uuid::Uuid;
+
+use super::PhysicsEngine;
+
+/// What a query may hit. The default hits every solid collider.
+#[derive(Clone, Debug, Default)]
+pub struct QueryOptions {
+    pub exclude: Vec<Uuid>, // Entities to ignore, e.g. the one asking
+    // Bit mask: only colliders in one of these collision layers
+    pub layers: Option<u32>,
+    pub include_sensors: bool,
+}
+
+/// The first collider a ray or shape cast reaches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryHit {
+    pub entity: Uuid,
+    pub point: (f32, f32),  // World position of the hit
+    pub normal: (f32, f32), // Surface normal there, pointing out of the hit collider
+    pub distance: f32,      // Travelled along the (normalized) direction
+}
+
+/// Shape swept by `cast_shape`, centered on its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryShape {
+    Circle(f32),         // radius
+    Rectangle(f32, f32), // width, height
+}
+
+// Queries run against the world as of the last step: bodies added since
+// then are found after the next one.
+impl PhysicsEngine {
+    /// First collider along the ray from `origin` in `direction`, up to
+    /// `max_distance` pixels away. A ray starting inside a collider hits it
+    /// at distance 0.
+    pub fn raycast(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+        options: &QueryOptions,
+    ) -> Option<QueryHit> {
+        let direction = Vector::new(direction.0, direction.1).try_normalize()?;
+        let ray = Ray::new(Vector::new(origin.0, origin.1), direction);
+        self.query(options, |pipeline| {
+            let (handle, hit) = pipeline.cast_ray_and_get_normal(&ray, max_distance, true)?;
+            let point = ray.point_at(hit.time_of_impact);
+            Some(QueryHit {
+                entity: entity_of(pipeline.colliders, handle)?,
+                point: (point.x, point.y),
+                normal: (hit.normal.x, hit.normal.y),
+                distance: hit.time_of_impact,
+            })
+        })
+    }
+
+    /// First collider `shape`, moved from `position` in `direction`, would
+    /// touch within `max_distance` pixels. `point` and `normal` are on the
+    /// collider hit.
+    pub fn cast_shape(
+        &self,
+        shape: QueryShape,
+        position: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+        options: &QueryOptions,
+    ) -> Option<QueryHit> {
+        let direction = Vector::new(direction.0, direction.1).try_normalize()?;
+        let pose = Pose::translation(position.0, position.1);
+        let shape = query_shape(shape);
+        let cast_options = ShapeCastOptions {
+            max_time_of_impact: max_distance,
+            compute_impact_geometry_on_penetration: true,
+            ..Default::default()
+        };
+        self.query(options, |pipeline| {
+            let (handle, hit) =
+                pipeline.cast_shape(&pose, direction, shape.as_ref(), cast_options)?;
+            Some(QueryHit {
+                entity: entity_of(pipeline.colliders, handle)?,
+                point: (hit.witness1.x, hit.witness1.y),
+                normal: (hit.normal1.x, hit.normal1.y),
+                distance: hit.time_of_impact,
+            })
+        })
+    }
+
+    /// Entities whose collider contains `point` (e.g. under the mouse).
+    pub fn intersect_point(&self, point: (f32, f32), options: &QueryOptions) -> Vec<Uuid> {
+        self.query(options, |pipeline| {
+            entities_of(
+                pipeline
+                    .intersect_point(Vector::new(point.0, point.1))
+                    .map(|(_, collider)| collider),
+            )
+        })
+    }
+
+    /// Entities whose collider overlaps the rectangle between the corners
+    /// `min` and `max`.
+    pub fn intersect_aabb(
+        &self,
+        min: (f32, f32),
+        max: (f32, f32),
+        options: &QueryOptions,
+    ) -> Vec<Uuid> {
+        let (min_x, max_x) = (min.0.min(max.0), min.0.max(max.0));
+        let (min_y, max_y) = (min.1.min(max.1), min.1.max(max.1));
+        let center = Pose::translation((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let rectangle = Cuboid::new(Vector::new((max_x - min_x) / 2.0, (max_y - min_y) / 2.0));
+        self.query(options, |pipeline| {
+            entities_of(
+                pipeline
+                    .intersect_shape(center, &rectangle)
+                    .map(|(_, collider)| collider),
+            )
+        })
+    }
+
+    fn query<T>(&self, options: &QueryOptions, f: impl FnOnce(&QueryPipeline) -> T) -> T {
+        let predicate = |_: ColliderHandle, collider: &Collider| {
+            let entity = Uuid::from_u128(collider.user_data);
+            let in_layers = options
+                .layers
+                .is_none_or(|layers| collider.collision_groups().memberships.bits() & layers != 0);
+            in_layers && !options.exclude.contains(&entity)
+        };
+        let mut filter = QueryFilter::new().predicate(&predicate);
+        if !options.include_sensors {
+            filter = filter.exclude_sensors();
+        }
+        let pipeline = QueryPipeline {
+            dispatcher: &DefaultQueryDispatcher,
+            bvh: &self.query_bvh,
+            bodies: &self.rigid_body_set,
+            colliders: &self.collider_set,
+            filter,
+        };
+        f(&pipeline)
+    }
+
+    // The broad phase's own tree stops finding bodies a while after they
+    // come to rest, so queries go by one rebuilt from the colliders' current
+    // bounds after each step. Leaves are collider indices, as rapier's are.
+    pub(super) fn refresh_query_bvh(&mut self) {
+        self.query_bvh = Bvh::new();
+        for (handle, collider) in self.collider_set.iter() {
+            self.query_bvh
+                .insert(collider.compute_aabb(), handle.into_raw_parts().0);
+        }
+    }
+}
+
+fn query_shape(shape: QueryShape) -> SharedShape {
+    match shape {
+        QueryShape::Circle(radius) => SharedShape::ball(radius),
+        QueryShape::Rectangle(width, height) => SharedShape::cuboid(width / 2.0, height / 2.0),
+    }
+}
+
+// Colliders carry their entity's id as user data (see `add_entity_at`)
+fn entity_of(colliders: &ColliderSet, handle: ColliderHandle) -> Option<Uuid> {
+    colliders
+        .get(handle)
+        .map(|collider| Uuid::from_u128(collider.user_data))
+}
+
+fn entities_of<'a>(colliders: impl Iterator<Item = &'a Collider>) -> Vec<Uuid> {
+    let mut entities = Vec::new();
+    for collider in colliders {
+        let entity = Uuid::from_u128(collider.user_data);
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    }
+    entities
+}
@@ -0,0 +1,277 @@
+use rapier2d::prelude::*;
+
+use super::{auto_collider_polygons, ColliderData, PhysicsEngine};
+use crate::ecs::{AttributeValue, Entity};
+use crate::logger::LOGGER;
+
+// Points per rounded end of a capsule outline
+const CAPSULE_OUTLINE_SUBDIVISIONS: u32 = 8;
+
+/// The shape of an entity's collider and the offset of its origin from the
+/// entity's x/y. `size` is the explicit or image size, if any, and `scale`
+/// the entity's (world) scale: sizes, points and part offsets are all
+/// scaled by it, as the sprite is when drawn.
+///
+/// A non-empty `collider_shapes` list makes a compound collider out of its
+/// parts; otherwise `collider_shape` picks the kind:
+/// - "circle" / "rectangle" / "capsule" fill `size` (a capsule is rounded
+///   along its shorter side);
+/// - "polygon" is the convex hull of `collider_points`;
+/// - "polyline" / "segment" run along `collider_points` (terrain; they
+///   have no area, so they belong on fixed bodies);
+/// - "auto" is traced from the alpha of the entity's first image (see
+///   `auto_collider.rs`), or fills `size` if that fails.
+///
+/// Without `collider_shape`, near-square sizes become circles.
+pub(super) fn collider_shape(
+    entity: &Entity,
+    size: Option<(f32, f32)>,
+    scale: Vector,
+) -> Option<(SharedShape, Vector)> {
+    if let Ok(AttributeValue::List(parts)) = entity
+        .get_attribute_by_name("collider_shapes")
+        .map(|attr| &attr.value)
+    {
+        let parts: Vec<(Pose, SharedShape)> = parts
+            .iter()
+            .filter_map(|part| compound_part(&entity.name, part, scale))
+            .collect();
+        if !parts.is_empty() {
+            return Some((SharedShape::compound(parts), Vector::ZERO));
+        }
+    }
+
+    let kind = match entity
+        .get_attribute_by_name("collider_shape")
+        .map(|attr| &attr.value)
+    {
+        Ok(AttributeValue::String(kind)) => Some(kind.to_lowercase()),
+        _ => None,
+    };
+    if kind.as_deref() == Some("auto") {
+        if let Some(shape) = auto_shape(entity, scale) {
+            return Some((shape, Vector::ZERO));
+        }
+    }
+    let points = entity
+        .get_attribute_by_name("collider_points")
+        .map(|attr| points(&attr.value))
+        .unwrap_or_default();
+    shape(&entity.name, kind.as_deref(), size, &points, scale)
+}
+
+// `entity_name` is only for the warning about shapes short of points
+fn shape(
+    entity_name: &str,
+    kind: Option<&str>,
+    size: Option<(f32, f32)>,
+    points: &[Vector],
+    scale: Vector,
+) -> Option<(SharedShape, Vector)> {
+    let points: Vec<Vector> = points.iter().map(|point| *point * scale).collect();
+    let from_points = match kind {
+        Some("polygon") => SharedShape::convex_hull(&points),
+        Some("polyline") if points.len() >= 2 => Some(SharedShape::polyline(points.to_vec(), None)),
+        Some("segment") if points.len() >= 2 => Some(SharedShape::segment(points[0], points[1])),
+        _ => None,
+    };
+    if let Some(shape) = from_points {
+        return Some((shape, Vector::ZERO));
+    }
+    if let Some(kind @ ("polygon" | "polyline" | "segment")) = kind {
+        LOGGER.warning(format!(
+            "The {} collider of {} has too few usable points ({}); give Vector2s, [x, y] lists or {{x, y}} maps",
+            kind,
+            entity_name,
+            points.len()
+        ));
+    }
+
+    // Sized shapes span from the entity's x/y (sprite top-left), so their
+    // center is offset by half the size (to the left or up for a negative,
+    // mirroring, scale)
+    let (width, height) = size?;
+    let (width, height) = (width * scale.x.abs(), height * scale.y.abs());
+    let offset = Vector::new(width / 2.0, height / 2.0) * scale.signum();
+    let is_circle = match kind {
+        Some(kind) => kind == "circle",
+        // Legacy heuristic: near-square sprites become circles
+        None => {
+            let ratio = width / height;
+            ratio > 0.9 && ratio < 1.1
+        }
+    };
+    let shape = if is_circle {
+        SharedShape::ball(width / 2.0)
+    } else if kind == Some("capsule") && height >= width {
+        SharedShape::capsule_y((height - width) / 2.0, width / 2.0)
+    } else if kind == Some("capsule") {
+        SharedShape::capsule_x((width - height) / 2.0, height / 2.0)
+    } else {
+        SharedShape::cuboid(width / 2.0, height / 2.0)
+    };
+    Some((shape, offset))
+}
+
+// The traced outline of the entity's first image, in pixels from its
+// top-left (the entity's x/y), scaled like the sprite
+fn auto_shape(entity: &Entity, scale: Vector) -> Option<SharedShape> {
+    let image_path = entity.get_image(0).ok()?;
+    let polygons = auto_collider_polygons(image_path)
+        .map_err(|e| {
+            LOGGER.warning(format!(
+                "Failed to trace collider of {}: {}",
+                image_path.display(),
+                e
+            ))
+        })
+        .ok()?;
+    let mut parts: Vec<(Pose, SharedShape)> = polygons
+        .iter()
+        .filter_map(|polygon| {
+            let points: Vec<Vector> = polygon
+                .iter()
+                .map(|&(x, y)| Vector::new(x, y) * scale)
+                .collect();
+            SharedShape::convex_hull(&points)
+        })
+        .map(|part| (Pose::IDENTITY, part))
+        .collect();
+    match parts.len() {
+        0 => None,
+        1 => parts.pop().map(|(_, part)| part),
+        _ => Some(SharedShape::compound(parts)),
+    }
+}
+
+// One `collider_shapes` entry: a map with "shape", "x" / "y" (offset from
+// the entity's x/y), "width" / "height" and "points". Polylines can't be
+// parts of a compound shape and are left out.
+fn compound_part(
+    entity_name: &str,
+    part: &AttributeValue,
+    scale: Vector,
+) -> Option<(Pose, SharedShape)> {
+    let AttributeValue::Map(part) = part else {
+        return None;
+    };
+    let field = |name: &str| part.get(name).and_then(number);
+    let kind = match part.get("shape") {
+        Some(AttributeValue::String(kind)) => Some(kind.to_lowercase()),
+        _ => None,
+    };
+    let size = field("width").zip(field("height"));
+    let points = part.get("points").map(points).unwrap_or_default();
+    let (shape, offset) = shape(entity_name, kind.as_deref(), size, &points, scale)?;
+    if shape.as_composite_shape().is_some() {
+        return None;
+    }
+    let position =
+        Vector::new(field("x").unwrap_or(0.0), field("y").unwrap_or(0.0)) * scale + offset;
+    Some((Pose::from_translation(position), shape))
+}
+
+fn number(value: &AttributeValue) -> Option<f32> {
+    match value {
+        AttributeValue::Float(value) => Some(*value),
+        AttributeValue::Integer(value) => Some(*value as f32),
+        _ => None,
+    }
+}
+
+// A list of points: Vector2s, or the [x, y] lists and {x, y} maps that Lua
+// tables and JSON make; anything else in it is skipped
+fn points(value: &AttributeValue) -> Vec<Vector> {
+    let point = |item: &AttributeValue| match item {
+        AttributeValue::Vector2(x, y) => Some(Vector::new(*x, *y)),
+        AttributeValue::List(xy) => match xy.as_slice() {
+            [x, y] => Some(Vector::new(number(x)?, number(y)?)),
+            _ => None,
+        },
+        AttributeValue::Map(xy) => Some(Vector::new(number(xy.get("x")?)?, number(xy.get("y")?)?)),
+        _ => None,
+    };
+    match value {
+        AttributeValue::List(items) => items.iter().filter_map(point).collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl PhysicsEngine {
+    /// Debug outlines of every collider, one entry per part of a compound
+    /// collider (see `ColliderData`).
+    pub fn get_collider_data(&self) -> Vec<ColliderData> {
+        let mut colliders = Vec::new();
+        for (entity_id, collider_handle) in &self.entity_to_collider {
+            if !self.entity_to_body.contains_key(entity_id) {
+                continue;
+            }
+            let Some(collider) = self.collider_set.get(*collider_handle) else {
+                continue;
+            };
+            match collider.shape().as_compound() {
+                Some(compound) => {
+                    for (pose, part) in compound.shapes() {
+                        colliders
+                            .extend(shape_data(&(*collider.position() * *pose), part.as_ref()));
+                    }
+                }
+                None => colliders.extend(shape_data(collider.position(), collider.shape())),
+            }
+        }
+        colliders
+    }
+}
+
+fn shape_data(pose: &Pose, shape: &dyn Shape) -> Option<ColliderData> {
+    let center = (pose.translation.x, pose.translation.y);
+    if let Some(ball) = shape.as_ball() {
+        let diameter = ball.radius * 2.0;
+        return Some((
+            center,
+            (diameter, diameter),
+            "Circle".to_string(),
+            Vec::new(),
+        ));
+    }
+    if let Some(cuboid) = shape.as_cuboid().filter(|_| pose.rotation.angle() == 0.0) {
+        let size = (cuboid.half_extents.x * 2.0, cuboid.half_extents.y * 2.0);
+        return Some((center, size, "Rectangle".to_string(), Vec::new()));
+    }
+
+    // Turned rectangles are outlined like polygons
+    let (name, local_points) = if let Some(cuboid) = shape.as_cuboid() {
+        ("Polygon", cuboid.to_polyline())
+    } else if let Some(capsule) = shape.as_capsule() {
+        ("Capsule", capsule.to_polyline(CAPSULE_OUTLINE_SUBDIVISIONS))
+    } else if let Some(polygon) = shape.as_convex_polygon() {
+        ("Polygon", polygon.points().to_vec())
+    } else if let Some(polyline) = shape.as_polyline() {
+        ("Polyline", polyline.vertices().to_vec())
+    } else if let Some(segment) = shape.as_segment() {
+        ("Polyline", vec![segment.a, segment.b])
+    } else {
+        return None;
+    };
+    let outline: Vec<(f32, f32)> = local_points
+        .into_iter()
+        .map(|point| {
+            let point = *pose * point;
+            (point.x, point.y)
+        })
+        .collect();
+
+    // Position and size are the outline's bounds
+    let first = *outline.first()?;
+    let (mut min, mut max) = (first, first);
+    for &(x, y) in &outline {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    Some((
+        ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0),
+        (max.0 - min.0, max.1 - min.1),
+        name.to_string(),
+        outline,
+    ))
+}
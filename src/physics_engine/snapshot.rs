@@ -0,0 +1,153 @@
+//! Snapshots of the whole physics world at a step boundary: rapier's state
+//! (bodies, colliders, joints, islands, broad and narrow phase with their
+//! contacts, CCD) and the engine's own bookkeeping. Restoring one and
+//! stepping again gives bit-identical results, for stepping back in a
+//! debugger, deterministic replays and rollback netcode.
+
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::{character, gravity_fields, joints, material, ContactEvent, PhysicsEngine};
+
+// Bump when what a snapshot holds changes; older ones are refused
+const SNAPSHOT_VERSION: u32 = 2;
+
+// What a snapshot is written from, after its version. `World` below has
+// the same fields in the same order, owned, and is what it's read back into.
+#[derive(Serialize)]
+struct WorldRef<'a> {
+    gravity: Vector,
+    integration_parameters: &'a IntegrationParameters,
+    island_manager: &'a IslandManager,
+    broad_phase: &'a DefaultBroadPhase,
+    narrow_phase: &'a NarrowPhase,
+    rigid_body_set: &'a RigidBodySet,
+    collider_set: &'a ColliderSet,
+    impulse_joint_set: &'a ImpulseJointSet,
+    multibody_joint_set: &'a MultibodyJointSet,
+    ccd_solver: &'a CCDSolver,
+    entity_to_body: &'a HashMap<Uuid, RigidBodyHandle>,
+    entity_to_collider: &'a HashMap<Uuid, ColliderHandle>,
+    time_step: f32,
+    entity_position_attrs: &'a HashMap<Uuid, Uuid>,
+    pivots: &'a HashMap<Uuid, Vector>,
+    scales: &'a HashMap<Uuid, Vector>,
+    disabled_entities: &'a HashSet<Uuid>,
+    joints: &'a HashMap<Uuid, joints::Joint>,
+    contact_events: &'a Vec<ContactEvent>,
+    removed_colliders: &'a HashMap<ColliderHandle, (Uuid, bool)>,
+    characters: &'a HashMap<Uuid, character::Character>,
+    materials: &'a HashMap<PathBuf, material::CachedMaterial>,
+    gravity_fields: &'a HashMap<Uuid, gravity_fields::GravityField>,
+}
+
+#[derive(Deserialize)]
+struct World {
+    gravity: Vector,
+    integration_parameters: IntegrationParameters,
+    island_manager: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    rigid_body_set: RigidBodySet,
+    collider_set: ColliderSet,
+    impulse_joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    entity_to_body: HashMap<Uuid, RigidBodyHandle>,
+    entity_to_collider: HashMap<Uuid, ColliderHandle>,
+    time_step: f32,
+    entity_position_attrs: HashMap<Uuid, Uuid>,
+    pivots: HashMap<Uuid, Vector>,
+    scales: HashMap<Uuid, Vector>,
+    disabled_entities: HashSet<Uuid>,
+    joints: HashMap<Uuid, joints::Joint>,
+    contact_events: Vec<ContactEvent>,
+    removed_colliders: HashMap<ColliderHandle, (Uuid, bool)>,
+    characters: HashMap<Uuid, character::Character>,
+    materials: HashMap<PathBuf, material::CachedMaterial>,
+    gravity_fields: HashMap<Uuid, gravity_fields::GravityField>,
+}
+
+impl PhysicsEngine {
+    /// The whole physics state as bytes, to be handed to `restore`. Take
+    /// it between steps; the scenes aren't part of it.
+    pub fn snapshot(&self) -> Result<Vec<u8>, String> {
+        let world = WorldRef {
+            gravity: self.gravity,
+            integration_parameters: &self.integration_parameters,
+            island_manager: &self.island_manager,
+            broad_phase: &self.broad_phase,
+            narrow_phase: &self.narrow_phase,
+            rigid_body_set: &self.rigid_body_set,
+            collider_set: &self.collider_set,
+            impulse_joint_set: &self.impulse_joint_set,
+            multibody_joint_set: &self.multibody_joint_set,
+            ccd_solver: &self.ccd_solver,
+            entity_to_body: &self.entity_to_body,
+            entity_to_collider: &self.entity_to_collider,
+            time_step: self.time_step,
+            entity_position_attrs: &self.entity_position_attrs,
+            pivots: &self.pivots,
+            scales: &self.scales,
+            disabled_entities: &self.disabled_entities,
+            joints: &self.joints,
+            contact_events: &self.contact_events,
+            removed_colliders: &self.removed_colliders,
+            characters: &self.characters,
+            materials: &self.materials,
+            gravity_fields: &self.gravity_fields,
+        };
+        bincode::serialize(&(SNAPSHOT_VERSION, world))
+            .map_err(|e| format!("Failed to write physics snapshot: {}", e))
+    }
+
+    /// Put the world back as it was when `snapshot` was taken, in this
+    /// engine or another one. Entity attributes catch up on the next step's
+    /// write-back. On error the engine is left as it was.
+    pub fn restore(&mut self, snapshot: &[u8]) -> Result<(), String> {
+        // The version comes first, so a snapshot of another layout is
+        // refused before it's read as this one
+        let read_error = |e| format!("Failed to read physics snapshot: {}", e);
+        let version: u32 = bincode::deserialize(snapshot).map_err(read_error)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "Physics snapshot version {} is not supported (expected {})",
+                version, SNAPSHOT_VERSION
+            ));
+        }
+        let (_, world): (u32, World) = bincode::deserialize(snapshot).map_err(read_error)?;
+
+        self.gravity = world.gravity;
+        self.integration_parameters = world.integration_parameters;
+        self.island_manager = world.island_manager;
+        self.broad_phase = world.broad_phase;
+        self.narrow_phase = world.narrow_phase;
+        self.rigid_body_set = world.rigid_body_set;
+        self.collider_set = world.collider_set;
+        self.impulse_joint_set = world.impulse_joint_set;
+        self.multibody_joint_set = world.multibody_joint_set;
+        self.ccd_solver = world.ccd_solver;
+        self.entity_to_body = world.entity_to_body;
+        self.entity_to_collider = world.entity_to_collider;
+        self.time_step = world.time_step;
+        self.entity_position_attrs = world.entity_position_attrs;
+        self.pivots = world.pivots;
+        self.scales = world.scales;
+        self.disabled_entities = world.disabled_entities;
+        self.joints = world.joints;
+        self.contact_events = world.contact_events;
+        self.removed_colliders = world.removed_colliders;
+        self.characters = world.characters;
+        self.materials = world.materials;
+        self.gravity_fields = world.gravity_fields;
+
+        // The query tree is made from the colliders, and the pipeline only
+        // holds scratch space between steps
+        self.refresh_query_bvh();
+        self.physics_pipeline = PhysicsPipeline::new();
+        Ok(())
+    }
+}
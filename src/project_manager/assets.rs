@@ -2,6 +2,8 @@ use std::fs;
 use std::path::Path;
 
 use super::ProjectManager;
+use crate::logger::LOGGER;
+use crate::physics_engine::generate_auto_collider;
 
 impl ProjectManager {
     pub fn import_asset(
@@ -52,6 +54,15 @@ impl ProjectManager {
         // Copy the asset file
         fs::copy(asset_path, &target_path).map_err(|e| format!("Failed to copy asset: {}", e))?;
 
+        // Trace images' colliders now (for `collider_shape = "auto"`) rather
+        // than when a game first needs them; a failure only means the game
+        // traces them itself
+        if let AssetType::Image = asset_type {
+            if let Err(e) = generate_auto_collider(&target_path) {
+                LOGGER.warning(format!("Failed to trace collider of {}: {}", file_name, e));
+            }
+        }
+
         // Return relative path from project root
         Ok(target_path
             .strip_prefix(project_path)
@@ -0,0 +1,118 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// Floats are written with this many significant digits, so values that
+// only differ by f32 noise (physics, viewport drags) don't show up in diffs
+const SIGNIFICANT_DIGITS: i32 = 6;
+
+/// Canonical text of a scene file or the scene index: fields in declaration
+/// order, entities and attributes in their own (scene / creation) order,
+/// floats rounded, and entity and attribute maps keyed by name instead of
+/// UUID wherever the name is unique. Every entry keeps its `id`, which is
+/// what `read_keyed_by_id` goes by, so the same data always gives the same
+/// bytes and a rename is a one-line diff of the key plus its `name`.
+pub(super) fn to_canonical_string<T: Serialize>(value: &T) -> Result<String, String> {
+    let mut json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    canonicalize(&mut json);
+    serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+}
+
+// Scene files and the index have entity maps in these places; every
+// entity has an attribute map
+fn canonicalize(json: &mut Value) {
+    round_floats(json);
+    let Value::Object(root) = json else {
+        return;
+    };
+    if let Some(Value::Object(entities)) = root.get_mut("entities") {
+        key_entities_by_name(entities);
+    }
+    if let Some(Value::Object(shared)) = root.get_mut("shared_entities") {
+        key_entities_by_name(shared);
+    }
+}
+
+fn key_entities_by_name(entities: &mut Map<String, Value>) {
+    for entity in entities.values_mut() {
+        if let Some(Value::Object(attributes)) = entity.get_mut("attributes") {
+            key_by_name(attributes);
+        }
+    }
+    key_by_name(entities);
+}
+
+// Re-key `map` by each entry's `name`, keeping the UUID key for names that
+// are shared (or look like a UUID themselves)
+fn key_by_name(map: &mut Map<String, Value>) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in map.values() {
+        if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            *counts.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    let entries = std::mem::take(map);
+    for (key, entry) in entries {
+        let name = entry
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| counts[*name] == 1 && uuid::Uuid::parse_str(name).is_err())
+            .map(str::to_string);
+        map.insert(name.unwrap_or(key), entry);
+    }
+}
+
+fn round_floats(json: &mut Value) {
+    match json {
+        Value::Number(number) if number.is_f64() => {
+            if let Some(rounded) = number
+                .as_f64()
+                .map(round_float)
+                .and_then(serde_json::Number::from_f64)
+            {
+                *number = rounded;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(round_floats),
+        Value::Object(entries) => entries.values_mut().for_each(round_floats),
+        _ => {}
+    }
+}
+
+fn round_float(x: f64) -> f64 {
+    if x == 0.0 || !x.is_finite() {
+        return x;
+    }
+    let magnitude = x.abs().log10().floor() as i32;
+    let scale = 10f64.powi(SIGNIFICANT_DIGITS - 1 - magnitude);
+    // Dividing (rather than multiplying by 1/scale) gives the closest f64
+    // to the rounded decimal, which prints without noise
+    (x * scale).round() / scale
+}
+
+/// Undo the name keys of `to_canonical_string`: entity and attribute maps
+/// at the places it writes them go back to being keyed by each entry's
+/// `id`. Files written with UUID keys pass through unchanged.
+pub(super) fn read_keyed_by_id(json: &mut Value) {
+    let Value::Object(root) = json else {
+        return;
+    };
+    for field in ["entities", "shared_entities"] {
+        if let Some(Value::Object(entities)) = root.get_mut(field) {
+            for entity in entities.values_mut() {
+                if let Some(Value::Object(attributes)) = entity.get_mut("attributes") {
+                    key_by_id(attributes);
+                }
+            }
+            key_by_id(entities);
+        }
+    }
+}
+
+fn key_by_id(map: &mut Map<String, Value>) {
+    let entries = std::mem::take(map);
+    for (key, entry) in entries {
+        let id = entry.get("id").and_then(Value::as_str).map(str::to_string);
+        map.insert(id.unwrap_or(key), entry);
+    }
+}
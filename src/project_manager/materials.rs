@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+use super::prefabs::{file_stem, list_files};
+use super::ProjectManager;
+use crate::physics_engine::PhysicsMaterial;
+
+/// Physics materials live in `assets/materials/<name>.material` as JSON
+/// (see `PhysicsMaterial`). Entities refer to one with a `physics_material`
+/// AssetRef attribute.
+impl ProjectManager {
+    pub const MATERIAL_DIR: &'static str = "assets/materials";
+    pub const MATERIAL_EXTENSION: &'static str = "material";
+
+    /// Project-relative path for a material named `name`
+    /// (`assets/materials/<name>.material`), made safe like `prefab_path`.
+    pub fn material_path(name: &str) -> Result<PathBuf, String> {
+        let file_stem =
+            file_stem(name).ok_or_else(|| format!("Invalid material name '{}'", name))?;
+        Ok(Path::new(Self::MATERIAL_DIR).join(format!(
+            "{}.{}",
+            file_stem,
+            Self::MATERIAL_EXTENSION
+        )))
+    }
+
+    /// Write a new material named `name` with default settings and return
+    /// its project-relative path. Fails if there already is one.
+    pub fn create_material(project_path: &Path, name: &str) -> Result<PathBuf, String> {
+        let relative_path = Self::material_path(name)?;
+        let file_path = project_path.join(&relative_path);
+        if file_path.exists() {
+            return Err(format!("Material '{}' already exists", name));
+        }
+        PhysicsMaterial::default().save(&file_path)?;
+        Ok(relative_path)
+    }
+
+    /// Project-relative paths of every material, sorted.
+    pub fn list_materials(project_path: &Path) -> Vec<PathBuf> {
+        list_files(project_path, Self::MATERIAL_DIR, Self::MATERIAL_EXTENSION)
+    }
+}
@@ -0,0 +1,346 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::scene_io::{parse_scene, scene_file_string};
+use super::ProjectManager;
+use crate::ecs::{Attribute, Entity, Scene, SceneManager};
+
+/// A spot both sides of a merge changed in different ways. The merged
+/// result keeps our side there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    pub location: String, // e.g. `scene "level_1" / entity "bird" / attribute "x"`
+    pub description: String,
+}
+
+#[derive(Debug)]
+pub struct SceneMerge {
+    pub merged: SceneManager,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Three-way merges of scene edits, matching scenes, entities and
+/// attributes by id. A change made on one side only is taken; the same
+/// change on both sides is fine; different changes to the same field,
+/// attribute value or entity list (images, tags, ...) are conflicts and
+/// keep ours. Deleting something the other side changed is a conflict
+/// too (ours wins again: kept if we changed it, gone if we deleted it).
+/// Entries added by them come after ours.
+impl ProjectManager {
+    /// Merge two edited versions of a project's scenes. Scenes that are
+    /// deferred (not read) on our side stay as they are.
+    pub fn merge_scene_managers(
+        base: &SceneManager,
+        ours: &SceneManager,
+        theirs: &SceneManager,
+    ) -> SceneMerge {
+        let mut merger = Merger::default();
+        let mut merged = ours.clone();
+        merged.scenes = merger.map(
+            "",
+            "scene",
+            &base.scenes,
+            &ours.scenes,
+            &theirs.scenes,
+            |scene| scene.name.clone(),
+            Merger::scene,
+        );
+        merged.shared_entities = merger.map(
+            "shared entities",
+            "entity",
+            &base.shared_entities,
+            &ours.shared_entities,
+            &theirs.shared_entities,
+            |entity| entity.name.clone(),
+            Merger::entity,
+        );
+        merged.active_scene = merger.field(
+            "project",
+            "active scene",
+            &base.active_scene,
+            &ours.active_scene,
+            &theirs.active_scene,
+        );
+        merged.additive_scenes = merger.field(
+            "project",
+            "additive scenes",
+            &base.additive_scenes,
+            &ours.additive_scenes,
+            &theirs.additive_scenes,
+        );
+        merged.collision_layers = merger.field(
+            "project",
+            "collision layers",
+            &base.collision_layers,
+            &ours.collision_layers,
+            &theirs.collision_layers,
+        );
+        SceneMerge {
+            merged,
+            conflicts: merger.conflicts,
+        }
+    }
+
+    /// Merge two edited versions of one `scenes/<name>.scene.json` (what a
+    /// git merge driver gets as base, ours and theirs). Returns the merged
+    /// file in canonical form and the conflicts.
+    pub fn merge_scene_files(
+        base: &str,
+        ours: &str,
+        theirs: &str,
+    ) -> Result<(String, Vec<MergeConflict>), String> {
+        let parse = |side: &str, json: &str| {
+            parse_scene(json).map_err(|e| format!("Failed to parse {} scene file: {}", side, e))
+        };
+        let (base, ours, theirs) = (
+            parse("base", base)?,
+            parse("our", ours)?,
+            parse("their", theirs)?,
+        );
+        let mut merger = Merger::default();
+        let merged = merger.scene("", &base, &ours, &theirs);
+        let json = scene_file_string(&merged)
+            .map_err(|e| format!("Failed to serialize merged scene: {}", e))?;
+        Ok((json, merger.conflicts))
+    }
+}
+
+#[derive(Default)]
+struct Merger {
+    conflicts: Vec<MergeConflict>,
+}
+
+// Values are compared in their stored (serialized) form
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+fn show<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn nested(location: &str, what: &str, name: &str) -> String {
+    if location.is_empty() {
+        format!("{} \"{}\"", what, name)
+    } else {
+        format!("{} / {} \"{}\"", location, what, name)
+    }
+}
+
+impl Merger {
+    fn conflict(&mut self, location: &str, description: String) {
+        self.conflicts.push(MergeConflict {
+            location: location.to_string(),
+            description,
+        });
+    }
+
+    fn field<T: Serialize + Clone>(
+        &mut self,
+        location: &str,
+        what: &str,
+        base: &T,
+        ours: &T,
+        theirs: &T,
+    ) -> T {
+        if same(theirs, base) || same(ours, theirs) {
+            ours.clone()
+        } else if same(ours, base) {
+            theirs.clone()
+        } else {
+            self.conflict(
+                location,
+                format!(
+                    "{} changed on both sides (ours: {}, theirs: {})",
+                    what,
+                    show(ours),
+                    show(theirs)
+                ),
+            );
+            ours.clone()
+        }
+    }
+
+    // Entries keyed by id: additions and deletions from either side, and
+    // `merge_entry` for entries both sides changed
+    #[allow(clippy::too_many_arguments)]
+    fn map<V: Serialize + Clone>(
+        &mut self,
+        location: &str,
+        what: &str,
+        base: &IndexMap<Uuid, V>,
+        ours: &IndexMap<Uuid, V>,
+        theirs: &IndexMap<Uuid, V>,
+        name: impl Fn(&V) -> String,
+        mut merge_entry: impl FnMut(&mut Self, &str, &V, &V, &V) -> V,
+    ) -> IndexMap<Uuid, V> {
+        let mut merged = IndexMap::new();
+        for (id, our) in ours {
+            let entry_location = nested(location, what, &name(our));
+            match (base.get(id), theirs.get(id)) {
+                (Some(base), Some(their)) => {
+                    let entry = if same(our, base) {
+                        their.clone()
+                    } else if same(their, base) {
+                        our.clone()
+                    } else {
+                        merge_entry(self, location, base, our, their)
+                    };
+                    merged.insert(*id, entry);
+                }
+                (Some(base), None) => {
+                    // Deleted by them
+                    if !same(our, base) {
+                        self.conflict(
+                            &entry_location,
+                            "deleted by theirs but changed by ours; kept".to_string(),
+                        );
+                        merged.insert(*id, our.clone());
+                    }
+                }
+                (None, their) => {
+                    if their.is_some_and(|their| !same(our, their)) {
+                        self.conflict(
+                            &entry_location,
+                            "added on both sides with different contents".to_string(),
+                        );
+                    }
+                    merged.insert(*id, our.clone());
+                }
+            }
+        }
+        for (id, their) in theirs {
+            if ours.contains_key(id) {
+                continue;
+            }
+            match base.get(id) {
+                None => {
+                    merged.insert(*id, their.clone());
+                }
+                // Deleted by us
+                Some(base) if !same(their, base) => self.conflict(
+                    &nested(location, what, &name(their)),
+                    "deleted by ours but changed by theirs; left deleted".to_string(),
+                ),
+                Some(_) => {}
+            }
+        }
+        merged
+    }
+
+    fn scene(&mut self, location: &str, base: &Scene, ours: &Scene, theirs: &Scene) -> Scene {
+        let location = nested(location, "scene", &ours.name);
+        let mut merged = ours.clone();
+        merged.name = self.field(&location, "name", &base.name, &ours.name, &theirs.name);
+        merged.entities = self.map(
+            &location,
+            "entity",
+            &base.entities,
+            &ours.entities,
+            &theirs.entities,
+            |entity| entity.name.clone(),
+            Merger::entity,
+        );
+        merged.shared_entity_refs = self.field(
+            &location,
+            "shared entity refs",
+            &base.shared_entity_refs,
+            &ours.shared_entity_refs,
+            &theirs.shared_entity_refs,
+        );
+        merged.default_camera = self.field(
+            &location,
+            "default camera",
+            &base.default_camera,
+            &ours.default_camera,
+            &theirs.default_camera,
+        );
+        merged
+    }
+
+    fn entity(&mut self, location: &str, base: &Entity, ours: &Entity, theirs: &Entity) -> Entity {
+        let location = nested(location, "entity", &ours.name);
+        let mut merged = ours.clone();
+        merged.name = self.field(&location, "name", &base.name, &ours.name, &theirs.name);
+        merged.images = self.field(
+            &location,
+            "images",
+            &base.images,
+            &ours.images,
+            &theirs.images,
+        );
+        merged.sounds = self.field(
+            &location,
+            "sounds",
+            &base.sounds,
+            &ours.sounds,
+            &theirs.sounds,
+        );
+        merged.script = self.field(
+            &location,
+            "script",
+            &base.script,
+            &ours.script,
+            &theirs.script,
+        );
+        merged.parent = self.field(
+            &location,
+            "parent",
+            &base.parent,
+            &ours.parent,
+            &theirs.parent,
+        );
+        merged.prefab = self.field(
+            &location,
+            "prefab",
+            &base.prefab,
+            &ours.prefab,
+            &theirs.prefab,
+        );
+        merged.tags = self.field(&location, "tags", &base.tags, &ours.tags, &theirs.tags);
+        merged.enabled = self.field(
+            &location,
+            "enabled",
+            &base.enabled,
+            &ours.enabled,
+            &theirs.enabled,
+        );
+        merged.attributes = self
+            .map(
+                &location,
+                "attribute",
+                &base.attributes,
+                &ours.attributes,
+                &theirs.attributes,
+                |attribute| attribute.name.clone(),
+                Merger::attribute,
+            )
+            .into_iter()
+            .collect();
+        merged
+    }
+
+    fn attribute(
+        &mut self,
+        location: &str,
+        base: &Attribute,
+        ours: &Attribute,
+        theirs: &Attribute,
+    ) -> Attribute {
+        let location = nested(location, "attribute", &ours.name);
+        let mut merged = ours.clone();
+        merged.name = self.field(&location, "name", &base.name, &ours.name, &theirs.name);
+        // Type and value change together (set_attribute keeps the type)
+        let (data_type, value) = self.field(
+            &location,
+            "value",
+            &(&base.data_type, &base.value),
+            &(&ours.data_type, &ours.value),
+            &(&theirs.data_type, &theirs.value),
+        );
+        merged.data_type = data_type.clone();
+        merged.value = value.clone();
+        merged
+    }
+}
@@ -0,0 +1,283 @@
+use serde_json::Value;
+
+/// Layout version written into `scenes/scene_manager.json` and every
+/// `scenes/<name>.scene.json` (as a top-level `format_version`) and
+/// `project.epm`. Bump it together with a new entry
+/// in the matching migration list whenever the stored layout changes in a
+/// way `#[serde(default)]` can't absorb.
+///
+/// Files without the field are version 0, i.e. everything saved before
+/// formats were versioned. Scene files came with version 2, so one without
+/// the field is version 2.
+pub const SCENE_FORMAT_VERSION: u32 = 4;
+pub const PROJECT_FORMAT_VERSION: u32 = 1;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// Turns an entity id into the id of the `rotation` attribute v3 -> v4 adds
+const ROTATION_ID_MASK: u128 = 0x5f0e_a2c1_7b3d_4e96_8a1f_c4d2_e6b8_9073;
+
+// SCENE_MIGRATIONS[n] upgrades a version n file to version n + 1
+const SCENE_MIGRATIONS: [Migration; SCENE_FORMAT_VERSION as usize] = [
+    scene_v0_to_v1,
+    scene_v1_to_v2,
+    scene_v2_to_v3,
+    scene_v3_to_v4,
+];
+const SCENE_FILE_MIGRATIONS: [Migration; SCENE_FORMAT_VERSION as usize] = [
+    no_scene_files,
+    no_scene_files,
+    scene_v2_to_v3,
+    scene_v3_to_v4,
+];
+const PROJECT_MIGRATIONS: [Migration; PROJECT_FORMAT_VERSION as usize] = [project_v0_to_v1];
+
+/// Bring a parsed `scene_manager.json` up to `SCENE_FORMAT_VERSION`.
+/// Files from a newer engine are refused.
+pub(super) fn migrate_scene_index(json: &mut Value) -> Result<(), String> {
+    migrate(
+        json,
+        "scene_manager.json",
+        0,
+        SCENE_FORMAT_VERSION,
+        &SCENE_MIGRATIONS,
+    )
+}
+
+/// Bring a parsed `.scene.json` up to `SCENE_FORMAT_VERSION`, like
+/// `migrate_scene_index`.
+pub(super) fn migrate_scene_file(json: &mut Value) -> Result<(), String> {
+    migrate(
+        json,
+        "Scene file",
+        2,
+        SCENE_FORMAT_VERSION,
+        &SCENE_FILE_MIGRATIONS,
+    )
+}
+
+/// Bring a parsed `project.epm` up to `PROJECT_FORMAT_VERSION`.
+pub(super) fn migrate_project_file(json: &mut Value) -> Result<(), String> {
+    migrate(
+        json,
+        "project.epm",
+        0,
+        PROJECT_FORMAT_VERSION,
+        &PROJECT_MIGRATIONS,
+    )
+}
+
+fn migrate(
+    json: &mut Value,
+    file_name: &str,
+    unversioned: u32, // The version of files without the field
+    current: u32,
+    migrations: &[Migration],
+) -> Result<(), String> {
+    let root = json
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not a JSON object", file_name))?;
+    let version = match root.get("format_version") {
+        None => unversioned,
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("{} has an invalid format_version", file_name))?,
+    };
+    if version > current {
+        return Err(format!(
+            "{} was saved by a newer engine (format version {}, this engine reads up to {}). \
+             Update the engine to open this project.",
+            file_name, version, current
+        ));
+    }
+
+    for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+        migration(json).map_err(|e| {
+            format!(
+                "Failed to upgrade {} from format version {}: {}",
+                file_name, from, e
+            )
+        })?;
+    }
+    if let Some(root) = json.as_object_mut() {
+        root.insert("format_version".to_string(), Value::from(current));
+    }
+    Ok(())
+}
+
+// =============== Scene file migrations ===============
+
+// v0 -> v1: early files stored absolute, machine-specific resource paths.
+// Make them project-relative via their `assets/` segment (paths outside
+// assets/ are left as they are).
+fn scene_v0_to_v1(json: &mut Value) -> Result<(), String> {
+    for_each_entity(json, |entity| {
+        for key in ["images", "sounds"] {
+            if let Some(Value::Array(paths)) = entity.get_mut(key) {
+                paths.iter_mut().for_each(relativize_path);
+            }
+        }
+        if let Some(script) = entity.get_mut("script") {
+            relativize_path(script);
+        }
+        if let Some(Value::Object(attributes)) = entity.get_mut("attributes") {
+            for attribute in attributes.values_mut() {
+                if let Some(value) = attribute.get_mut("value") {
+                    relativize_asset_refs(value);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+// v1 -> v2: scene_manager.json became an index, each scene getting its own
+// file. Old scenes stay inline in their index entry until the next save
+// writes them out.
+fn scene_v1_to_v2(json: &mut Value) -> Result<(), String> {
+    let root = json.as_object_mut().ok_or("not a JSON object")?;
+    let scenes = match root.shift_remove("scenes") {
+        Some(Value::Object(scenes)) => scenes,
+        None => Default::default(),
+        Some(_) => return Err("scenes is not a map".to_string()),
+    };
+    let entries = scenes
+        .into_iter()
+        .map(|(id, scene)| {
+            let name = scene.get("name").cloned().unwrap_or_default();
+            serde_json::json!({ "id": id, "name": name, "scene": scene })
+        })
+        .collect();
+    root.insert("scenes".to_string(), Value::Array(entries));
+    Ok(())
+}
+
+// v2 -> v3: entity and attribute maps are keyed by name where unique (see
+// canonical.rs). Readers go by the ids inside the entries, so UUID-keyed
+// files read as they are; the version keeps older engines from misreading
+// name keys
+fn scene_v2_to_v3(_json: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+// v3 -> v4: physics writes a body's rotation back to the `rotation`
+// attribute, which physical entities saved before didn't have. Add it (0
+// degrees) to every entity with a body. Its id is made from the entity's, so
+// every copy of a file is upgraded the same way and merges cleanly.
+fn scene_v3_to_v4(json: &mut Value) -> Result<(), String> {
+    let root = json.as_object_mut().ok_or("not a JSON object")?;
+    let mut entity_maps: Vec<&mut Value> = Vec::new();
+    for (field, value) in root.iter_mut() {
+        match (field.as_str(), value) {
+            ("entities" | "shared_entities", value) => entity_maps.push(value),
+            // Scenes still inline in the index (see scene_v1_to_v2)
+            ("scenes", Value::Array(entries)) => entity_maps.extend(
+                entries
+                    .iter_mut()
+                    .filter_map(|entry| entry.get_mut("scene")?.get_mut("entities")),
+            ),
+            _ => {}
+        }
+    }
+    for entities in entity_maps {
+        if let Value::Object(entities) = entities {
+            entities.values_mut().for_each(add_rotation);
+        }
+    }
+    Ok(())
+}
+
+fn add_rotation(entity: &mut Value) {
+    let Some(entity_id) = entity
+        .get("id")
+        .and_then(Value::as_str)
+        .and_then(|id| uuid::Uuid::parse_str(id).ok())
+    else {
+        return;
+    };
+    let Some(Value::Object(attributes)) = entity.get_mut("attributes") else {
+        return;
+    };
+    let named = |name: &str| {
+        attributes
+            .values()
+            .any(|attribute| attribute.get("name").and_then(Value::as_str) == Some(name))
+    };
+    // The attributes that give an entity a body (see PhysicsEngine::add_entity)
+    let has_body = ["has_gravity", "has_collision", "creates_gravity"]
+        .iter()
+        .any(|name| named(name));
+    if !has_body || named("rotation") {
+        return;
+    }
+    let id = uuid::Uuid::from_u128(entity_id.as_u128() ^ ROTATION_ID_MASK).to_string();
+    attributes.insert(
+        id.clone(),
+        serde_json::json!({
+            "id": id,
+            "name": "rotation",
+            "data_type": "Float",
+            "value": { "Float": 0.0 },
+        }),
+    );
+}
+
+// Scene files start at version 2; one claiming an older version is broken
+fn no_scene_files(_json: &mut Value) -> Result<(), String> {
+    Err("there were no scene files before format version 2".to_string())
+}
+
+// Call `f` on every entity object: each scene's and the shared ones
+// (v0/v1 layout, scenes inline)
+fn for_each_entity(json: &mut Value, mut f: impl FnMut(&mut Value)) {
+    if let Some(Value::Object(scenes)) = json.get_mut("scenes") {
+        for scene in scenes.values_mut() {
+            if let Some(Value::Object(entities)) = scene.get_mut("entities") {
+                entities.values_mut().for_each(&mut f);
+            }
+        }
+    }
+    if let Some(Value::Object(shared)) = json.get_mut("shared_entities") {
+        shared.values_mut().for_each(&mut f);
+    }
+}
+
+fn relativize_path(path: &mut Value) {
+    if let Some(relative) = path.as_str().and_then(legacy_relative_path) {
+        *path = Value::String(relative);
+    }
+}
+
+// AssetRef values, also nested in List / Map values
+fn relativize_asset_refs(value: &mut Value) {
+    let Value::Object(variant) = value else {
+        return;
+    };
+    if let Some(path) = variant.get_mut("AssetRef") {
+        relativize_path(path);
+    } else if let Some(Value::Array(items)) = variant.get_mut("List") {
+        items.iter_mut().for_each(relativize_asset_refs);
+    } else if let Some(Value::Object(entries)) = variant.get_mut("Map") {
+        entries.values_mut().for_each(relativize_asset_refs);
+    }
+}
+
+/// `assets/...` part of an absolute path saved on some machine, if it has
+/// one. Relative paths give None, they already are project-relative.
+pub(super) fn legacy_relative_path(stored: &str) -> Option<String> {
+    if !stored.starts_with('/') && !stored.contains(":\\") && !stored.contains(":/") {
+        return None;
+    }
+    let normalized = stored.replace('\\', "/");
+    normalized
+        .rfind("/assets/")
+        .map(|pos| normalized[pos + 1..].to_string())
+}
+
+// =============== Project file migrations ===============
+
+// v0 -> v1: no layout change, the version field itself is new
+fn project_v0_to_v1(_json: &mut Value) -> Result<(), String> {
+    Ok(())
+}
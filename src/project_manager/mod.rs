@@ -11,10 +11,17 @@ use uuid::Uuid;
 
 mod assets;
 mod build;
+mod canonical;
+mod materials;
+mod merge;
+mod migrations;
+mod prefabs;
 mod scaffold;
 mod scene_io;
 
 pub use assets::AssetType;
+pub use merge::{MergeConflict, SceneMerge};
+pub use migrations::{PROJECT_FORMAT_VERSION, SCENE_FORMAT_VERSION};
 
 use std::sync::RwLock;
 static PROJECT_PATH: RwLock<Option<String>> = RwLock::new(None);
@@ -23,6 +30,8 @@ static PROJECT_PATH: RwLock<Option<String>> = RwLock::new(None);
 // This is serialized to/from project.json
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProjectMetadata {
+    #[serde(default)]
+    pub format_version: u32, // Layout of this file (PROJECT_FORMAT_VERSION)
     pub project_name: String,          // Name of the game project
     pub version: String,               // Project version (e.g., "1.0.0")
     pub project_path: String,          // Absolute path to project directory
@@ -55,7 +64,8 @@ impl ProjectManager {
         project_path_lock.clone()
     }
 
-    // Loads project metadata from project.epm.
+    // Loads project metadata from project.epm, upgrading files saved by
+    // older engines and refusing newer ones (see migrations.rs).
     //
     // The stored project_path is ignored and replaced with the actual
     // location the project was opened from (in memory only - loading must
@@ -65,8 +75,11 @@ impl ProjectManager {
         let file_path = project_path.join(Self::PROJECT_FILE_NAME);
         let file = File::open(&file_path).map_err(|e| format!("Failed to open file: {}", e))?;
 
-        let mut metadata: ProjectMetadata =
+        let mut json: serde_json::Value =
             serde_json::from_reader(file).map_err(|e| format!("Failed to read metadata: {}", e))?;
+        migrations::migrate_project_file(&mut json)?;
+        let mut metadata: ProjectMetadata =
+            serde_json::from_value(json).map_err(|e| format!("Failed to read metadata: {}", e))?;
 
         // Always update project_path to current path
         metadata.project_path = project_path
@@ -98,7 +111,7 @@ impl ProjectManager {
         }
 
         //valid project structure
-        if !Self::validate_project_structure(project_path).is_ok() {
+        if Self::validate_project_structure(project_path).is_err() {
             return Err("Project structure is invalid".to_string());
         }
 
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::scene_io::{resolve_path, to_relative_string};
+use super::ProjectManager;
+use crate::ecs::{Prefab, Scene, SceneManager};
+use crate::logger::LOGGER;
+
+/// Prefabs live in `assets/prefabs/<name>.prefab` as JSON. Like the scene
+/// file, resource paths inside are stored relative to the project root and
+/// resolved on load; instances refer to their prefab by the same relative
+/// path (`PrefabLink::source`).
+impl ProjectManager {
+    pub const PREFAB_DIR: &'static str = "assets/prefabs";
+    pub const PREFAB_EXTENSION: &'static str = "prefab";
+
+    /// Project-relative path for a prefab named `name`
+    /// (`assets/prefabs/<name>.prefab`). Characters that aren't safe in file
+    /// names are replaced with `_`.
+    pub fn prefab_path(name: &str) -> Result<PathBuf, String> {
+        let file_stem = file_stem(name).ok_or_else(|| format!("Invalid prefab name '{}'", name))?;
+        Ok(Path::new(Self::PREFAB_DIR).join(format!("{}.{}", file_stem, Self::PREFAB_EXTENSION)))
+    }
+
+    /// Write a prefab to its project-relative path (see `prefab_path`),
+    /// replacing any existing file.
+    pub fn save_prefab(
+        project_path: &Path,
+        relative_path: &Path,
+        prefab: &Prefab,
+    ) -> Result<(), String> {
+        let file_path = project_path.join(relative_path);
+        if let Some(prefab_dir) = file_path.parent() {
+            fs::create_dir_all(prefab_dir)
+                .map_err(|e| format!("Failed to create {}: {}", prefab_dir.display(), e))?;
+        }
+
+        let mut portable = prefab.clone();
+        for entity in portable.entities.iter_mut() {
+            let relative = |path: &Path| {
+                to_relative_string(path, project_path)
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| path.to_path_buf())
+            };
+            entity.images = entity.images.iter().map(|p| relative(p)).collect();
+            entity.sounds = entity.sounds.iter().map(|p| relative(p)).collect();
+            entity.script = entity.script.as_deref().map(relative);
+            for attr in entity.attributes.values_mut() {
+                attr.value.map_asset_refs(&mut |p| relative(p));
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&portable)
+            .map_err(|e| format!("Failed to serialize prefab: {}", e))?;
+        fs::write(&file_path, json).map_err(|e| format!("Failed to write prefab: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Read a prefab by its project-relative path, with resource paths
+    /// resolved to absolute ones.
+    pub fn load_prefab(project_path: &Path, relative_path: &Path) -> Result<Prefab, String> {
+        let json = fs::read_to_string(project_path.join(relative_path))
+            .map_err(|e| format!("Failed to read prefab '{}': {}", relative_path.display(), e))?;
+        let mut prefab: Prefab = serde_json::from_str(&json).map_err(|e| {
+            format!(
+                "Failed to parse prefab '{}': {}",
+                relative_path.display(),
+                e
+            )
+        })?;
+        prefab.root()?;
+
+        for entity in prefab.entities.iter_mut() {
+            for image in entity.images.iter_mut() {
+                *image = resolve_path(image, project_path);
+            }
+            for sound in entity.sounds.iter_mut() {
+                *sound = resolve_path(sound, project_path);
+            }
+            if let Some(script) = entity.script.as_mut() {
+                *script = resolve_path(script, project_path);
+            }
+            for attr in entity.attributes.values_mut() {
+                attr.value
+                    .map_asset_refs(&mut |p| resolve_path(p, project_path));
+            }
+        }
+
+        Ok(prefab)
+    }
+
+    /// Project-relative paths of every prefab, sorted.
+    pub fn list_prefabs(project_path: &Path) -> Vec<PathBuf> {
+        list_files(project_path, Self::PREFAB_DIR, Self::PREFAB_EXTENSION)
+    }
+
+    /// Re-apply prefab files to their instances in every scene read so far
+    /// (deferred scenes get it when read). Called on load so edits to a
+    /// prefab reach scenes saved before the edit. Instances whose prefab
+    /// file is missing are left untouched.
+    pub fn sync_prefab_instances(project_path: &Path, scene_manager: &mut SceneManager) {
+        Self::sync_prefabs_in(project_path, scene_manager.scenes.values_mut().collect());
+    }
+
+    pub(super) fn sync_prefabs_in(project_path: &Path, mut scenes: Vec<&mut Scene>) {
+        let mut sources: Vec<PathBuf> = Vec::new();
+        for scene in &scenes {
+            for entity in scene.entities.values() {
+                if let Some(link) = &entity.prefab {
+                    if !sources.contains(&link.source) {
+                        sources.push(link.source.clone());
+                    }
+                }
+            }
+        }
+
+        for source in sources {
+            match Self::load_prefab(project_path, &source) {
+                Ok(prefab) => {
+                    for scene in scenes.iter_mut() {
+                        scene.sync_prefab_instances(&prefab, &source);
+                    }
+                }
+                Err(e) => LOGGER.warning(format!("Prefab instances not synced: {}", e)),
+            }
+        }
+    }
+}
+
+// Project-relative paths of the files in `dir` with `extension`, sorted
+pub(super) fn list_files(project_path: &Path, dir: &str, extension: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(project_path.join(dir)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .filter_map(|path| path.file_name().map(|name| Path::new(dir).join(name)))
+        .collect();
+    files.sort();
+    files
+}
+
+// `name` with the characters that aren't safe in file names replaced with
+// `_`; None if that leaves nothing
+pub(super) fn file_stem(name: &str) -> Option<String> {
+    let file_stem: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    (!file_stem.is_empty()).then_some(file_stem)
+}
@@ -2,7 +2,7 @@ use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
 
-use super::{LoadedProject, ProjectManager, ProjectMetadata};
+use super::{LoadedProject, ProjectManager, ProjectMetadata, PROJECT_FORMAT_VERSION};
 use crate::ecs::SceneManager;
 
 impl ProjectManager {
@@ -15,6 +15,7 @@ impl ProjectManager {
 
         // Create initial project metadata
         let metadata = ProjectMetadata {
+            format_version: PROJECT_FORMAT_VERSION,
             project_name: project_name.to_string(),
             version: "1.0.0".to_string(),
             project_path: project_path.to_str().unwrap().to_string(),
@@ -50,12 +51,14 @@ impl ProjectManager {
     // Creates the standard folder structure for a new project
     fn create_folder_structure(base_path: &Path) -> Result<(), String> {
         let folders = [
-            "assets/images",  // For image assets (textures, sprites)
-            "assets/sounds",  // For audio assets
-            "assets/fonts",   // For font files
-            "assets/scripts", // For game scripts
-            "scenes",         // For scene data files
-            "src",            // For Rust source files
+            "assets/images",    // For image assets (textures, sprites)
+            "assets/sounds",    // For audio assets
+            "assets/fonts",     // For font files
+            "assets/scripts",   // For game scripts
+            "assets/prefabs",   // For entity templates (.prefab)
+            "assets/materials", // For physics materials (.material)
+            "scenes",           // For scene data files
+            "src",              // For Rust source files
         ];
 
         // Create each folder in the structure
@@ -1,37 +1,133 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::canonical::{read_keyed_by_id, to_canonical_string};
+use super::migrations::{self, SCENE_FORMAT_VERSION};
 use super::ProjectManager;
-use crate::ecs::{Entity, SceneManager};
+use crate::ecs::{Entity, Scene, SceneManager};
+
+const INDEX_FILE: &str = "scenes/scene_manager.json";
+const SCENE_FILE_SUFFIX: &str = ".scene.json";
+
+// scene_manager.json: the scenes of the project and their files, plus what
+// doesn't belong to any one scene. Each scene is stored in its own file
+// under scenes/, so edits to different scenes touch different files.
+#[derive(Serialize, Deserialize)]
+struct SceneIndex {
+    format_version: u32,
+    scenes: Vec<SceneEntry>, // Sorted by file name
+    shared_entities: IndexMap<Uuid, Entity>,
+    active_scene: Option<Uuid>,
+    #[serde(default)]
+    additive_scenes: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    collision_layers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SceneEntry {
+    id: Uuid,
+    name: String,
+    // Relative to scenes/
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    // Scenes upgraded from the single-file layout, until the next save
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scene: Option<Scene>,
+}
 
 /// Asset/script paths are stored **relative to the project root** (with `/`
-/// separators) in `scene_manager.json`, and resolved back to absolute paths
+/// separators) in the scene files, and resolved back to absolute paths
 /// against the opened project's location at load time. This keeps projects
 /// portable across machines and operating systems.
 ///
 /// In memory, entities always hold absolute paths (renderer/audio/physics
 /// open them directly).
 impl ProjectManager {
+    /// Write `scenes/scene_manager.json` (the index) and one
+    /// `scenes/<name>.scene.json` per scene. A scene keeps its file when
+    /// renamed, files are only rewritten when their contents change, and
+    /// files of deleted scenes are removed. Deferred scenes were never read,
+    /// so their files are left alone.
     pub fn save_scene_hierarchy(
         project_path: &Path,
         scene_manager: &SceneManager,
     ) -> Result<(), String> {
-        let scene_file = project_path.join("scenes").join("scene_manager.json");
-
-        // Serialize a copy whose resource paths are relative to the project
-        // root, so the file stays machine-independent.
-        let mut portable = scene_manager.clone();
-        map_entity_paths(&mut portable, |path| {
-            to_relative_string(path, project_path)
-                .map(PathBuf::from)
-                .unwrap_or_else(|| path.to_path_buf())
-        });
-
-        let json = serde_json::to_string_pretty(&portable)
+        let previous = scene_files(project_path);
+        let mut entries: Vec<SceneEntry> = Vec::new();
+        let mut used: Vec<String> = previous
+            .iter()
+            .filter(|(id, _)| scene_manager.has_scene(**id))
+            .map(|(_, file)| file.clone())
+            .collect();
+
+        for (id, scene) in &scene_manager.scenes {
+            let file = match previous.get(id) {
+                Some(file) => file.clone(),
+                None => {
+                    let file = new_scene_file_name(project_path, &scene.name, *id, &used);
+                    used.push(file.clone());
+                    file
+                }
+            };
+
+            // Serialize a copy whose resource paths are relative to the
+            // project root, so the file stays machine-independent.
+            let mut portable = scene.clone();
+            for entity in portable.entities.values_mut() {
+                map_paths(entity, |path| relative_path(path, project_path));
+            }
+            let json = scene_file_string(&portable)
+                .map_err(|e| format!("Failed to serialize scene '{}': {}", scene.name, e))?;
+            write_if_changed(&project_path.join("scenes").join(&file), &json)?;
+
+            entries.push(SceneEntry {
+                id: *id,
+                name: scene.name.clone(),
+                file: Some(file),
+                scene: None,
+            });
+        }
+        for (id, name) in &scene_manager.deferred_scenes {
+            let file = previous
+                .get(id)
+                .ok_or_else(|| format!("Scene '{}' was never read and has no file", name))?;
+            entries.push(SceneEntry {
+                id: *id,
+                name: name.clone(),
+                file: Some(file.clone()),
+                scene: None,
+            });
+        }
+        entries.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let mut shared_entities = scene_manager.shared_entities.clone();
+        for entity in shared_entities.values_mut() {
+            map_paths(entity, |path| relative_path(path, project_path));
+        }
+        let index = SceneIndex {
+            format_version: SCENE_FORMAT_VERSION,
+            scenes: entries,
+            shared_entities,
+            active_scene: scene_manager.active_scene,
+            additive_scenes: scene_manager.additive_scenes.clone(),
+            collision_layers: scene_manager.collision_layers.clone(),
+        };
+        let json = to_canonical_string(&index)
             .map_err(|e| format!("Failed to serialize scene hierarchy: {}", e))?;
+        write_if_changed(&project_path.join(INDEX_FILE), &json)?;
 
-        fs::write(&scene_file, json)
-            .map_err(|e| format!("Failed to write scene hierarchy: {}", e))?;
+        // Scenes deleted since the last save
+        for file in previous.values() {
+            if !used.contains(file) {
+                let _ = fs::remove_file(project_path.join("scenes").join(file));
+            }
+        }
 
         // Update project metadata with active scene
         if let Ok(mut metadata) = Self::load_project(project_path) {
@@ -42,57 +138,224 @@ impl ProjectManager {
         Ok(())
     }
 
-    // Loads the scene hierarchy from scene_manager.json
+    // Loads the scene hierarchy from scenes/scene_manager.json, upgrading
+    // files saved by older engines (see migrations.rs) and refusing newer
+    // ones. The upgrade happens in memory; the files are rewritten on the
+    // next save.
+    //
+    // Only the active scene and the additive ones are read; the others are
+    // left in `SceneManager::deferred_scenes` until `load_deferred_scene`.
     pub fn load_scene_hierarchy(project_path: &Path) -> Result<SceneManager, String> {
-        let scene_file = project_path.join("scenes").join("scene_manager.json");
-
         // Return new scene manager if file doesn't exist
-        if !scene_file.exists() {
+        let Some(index) = read_scene_index(project_path)? else {
             return Ok(SceneManager::new());
-        }
+        };
 
-        let json = fs::read_to_string(&scene_file)
-            .map_err(|e| format!("Failed to read scene hierarchy: {}", e))?;
+        let mut scene_manager = SceneManager::new();
+        let mut files = HashMap::new();
+        for entry in index.scenes {
+            match (entry.scene, entry.file) {
+                (Some(mut scene), _) => {
+                    for entity in scene.entities.values_mut() {
+                        map_paths(entity, |path| resolve_path(path, project_path));
+                    }
+                    scene_manager.scenes.insert(entry.id, scene);
+                }
+                (None, Some(file)) => {
+                    scene_manager.deferred_scenes.insert(entry.id, entry.name);
+                    files.insert(entry.id, file);
+                }
+                (None, None) => return Err(format!("Scene '{}' has no file", entry.name)),
+            }
+        }
+        scene_manager.shared_entities = index.shared_entities;
+        for entity in scene_manager.shared_entities.values_mut() {
+            map_paths(entity, |path| resolve_path(path, project_path));
+        }
+        scene_manager.active_scene = index.active_scene;
+        scene_manager.additive_scenes = index.additive_scenes;
+        scene_manager.collision_layers = index.collision_layers;
 
-        let mut scene_manager: SceneManager = serde_json::from_str(&json)
-            .map_err(|e| format!("Failed to parse scene hierarchy: {}", e))?;
+        // What plays right away is read now
+        let loaded: Vec<Uuid> = index
+            .active_scene
+            .into_iter()
+            .chain(scene_manager.additive_scenes.iter().copied())
+            .filter(|id| files.contains_key(id))
+            .collect();
+        for id in loaded {
+            let scene = read_scene_file(project_path, &files[&id])?;
+            scene_manager.insert_deferred_scene(id, scene)?;
+        }
 
-        // Resolve stored paths against this machine's project location
-        map_entity_paths(&mut scene_manager, |path| resolve_path(path, project_path));
+        // Pick up prefab edits made since the scenes were saved
+        Self::sync_prefab_instances(project_path, &mut scene_manager);
 
         Ok(scene_manager)
     }
-}
 
-/// Apply `f` to every resource path (images, sounds, script) of every entity,
-/// in all scenes and in the shared-entity registry.
-fn map_entity_paths(scene_manager: &mut SceneManager, mut f: impl FnMut(&Path) -> PathBuf) {
-    let mut apply = |entity: &mut Entity| {
-        for image in entity.images.iter_mut() {
-            *image = f(image);
+    /// Read a scene `load_scene_hierarchy` left deferred (does nothing if
+    /// it has been read already). Prefab edits are applied to it like to
+    /// the scenes read up front.
+    pub fn load_deferred_scene(
+        project_path: &Path,
+        scene_manager: &mut SceneManager,
+        scene_id: Uuid,
+    ) -> Result<(), String> {
+        if !scene_manager.is_scene_deferred(scene_id) {
+            return Ok(());
         }
-        for sound in entity.sounds.iter_mut() {
-            *sound = f(sound);
+        let file = scene_files(project_path)
+            .remove(&scene_id)
+            .ok_or_else(|| format!("Scene {} is not in the scene index", scene_id))?;
+        let scene = read_scene_file(project_path, &file)?;
+        scene_manager.insert_deferred_scene(scene_id, scene)?;
+        if let Some(scene) = scene_manager.scenes.get_mut(&scene_id) {
+            Self::sync_prefabs_in(project_path, vec![scene]);
         }
-        if let Some(script) = entity.script.as_mut() {
-            *script = f(script);
+        Ok(())
+    }
+
+    /// Read every deferred scene, for operations that need to see all of
+    /// them (e.g. deleting a shared entity).
+    pub fn load_all_scenes(
+        project_path: &Path,
+        scene_manager: &mut SceneManager,
+    ) -> Result<(), String> {
+        let deferred: Vec<Uuid> = scene_manager.deferred_scenes.keys().copied().collect();
+        for scene_id in deferred {
+            Self::load_deferred_scene(project_path, scene_manager, scene_id)?;
         }
+        Ok(())
+    }
+}
+
+// The index, upgraded to the current layout; None if the project has none
+fn read_scene_index(project_path: &Path) -> Result<Option<SceneIndex>, String> {
+    let index_file = project_path.join(INDEX_FILE);
+    if !index_file.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(&index_file)
+        .map_err(|e| format!("Failed to read scene hierarchy: {}", e))?;
+    let mut json: serde_json::Value = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse scene hierarchy: {}", e))?;
+    migrations::migrate_scene_index(&mut json)?;
+    read_keyed_by_id(&mut json);
+    serde_json::from_value(json)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse scene hierarchy: {}", e))
+}
+
+// Scene id -> file name, as recorded in the index on disk
+fn scene_files(project_path: &Path) -> HashMap<Uuid, String> {
+    let Ok(Some(index)) = read_scene_index(project_path) else {
+        return HashMap::new();
     };
+    index
+        .scenes
+        .into_iter()
+        .filter_map(|entry| entry.file.map(|file| (entry.id, file)))
+        .collect()
+}
 
-    for (_, scene) in scene_manager.scenes.iter_mut() {
-        for (_, entity) in scene.entities.iter_mut() {
-            apply(entity);
-        }
+fn read_scene_file(project_path: &Path, file: &str) -> Result<Scene, String> {
+    let path = project_path.join("scenes").join(file);
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read scene file '{}': {}", file, e))?;
+    let mut scene =
+        parse_scene(&json).map_err(|e| format!("Failed to parse scene file '{}': {}", file, e))?;
+    for entity in scene.entities.values_mut() {
+        map_paths(entity, |path| resolve_path(path, project_path));
     }
-    for (_, entity) in scene_manager.shared_entities.iter_mut() {
-        apply(entity);
+    Ok(scene)
+}
+
+// A `.scene.json`: the scene plus the layout version it's written in
+#[derive(Serialize)]
+struct SceneFile<'a> {
+    format_version: u32,
+    #[serde(flatten)]
+    scene: &'a Scene,
+}
+
+// Canonical text of a scene file (see canonical.rs), at the current version
+pub(super) fn scene_file_string(scene: &Scene) -> Result<String, String> {
+    to_canonical_string(&SceneFile {
+        format_version: SCENE_FORMAT_VERSION,
+        scene,
+    })
+}
+
+// A scene file, upgraded from older versions; newer ones are refused
+pub(super) fn parse_scene(json: &str) -> Result<Scene, String> {
+    let mut json: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    migrations::migrate_scene_file(&mut json)?;
+    if let Some(root) = json.as_object_mut() {
+        root.shift_remove("format_version");
     }
+    read_keyed_by_id(&mut json);
+    serde_json::from_value(json).map_err(|e| e.to_string())
+}
+
+// `<name>.scene.json`, made file-system safe; the scene id is appended when
+// another scene has (or had) that name
+fn new_scene_file_name(project_path: &Path, name: &str, id: Uuid, used: &[String]) -> String {
+    let mut stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        stem = "scene".to_string();
+    }
+    let file = format!("{}{}", stem, SCENE_FILE_SUFFIX);
+    if used.contains(&file) || project_path.join("scenes").join(&file).exists() {
+        format!("{}-{}{}", stem, id.simple(), SCENE_FILE_SUFFIX)
+    } else {
+        file
+    }
+}
+
+fn write_if_changed(path: &Path, contents: &str) -> Result<(), String> {
+    if fs::read_to_string(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Apply `f` to every resource path (images, sounds, script, AssetRef
+/// attributes) of an entity.
+fn map_paths(entity: &mut Entity, mut f: impl FnMut(&Path) -> PathBuf) {
+    for image in entity.images.iter_mut() {
+        *image = f(image);
+    }
+    for sound in entity.sounds.iter_mut() {
+        *sound = f(sound);
+    }
+    if let Some(script) = entity.script.as_mut() {
+        *script = f(script);
+    }
+    for attr in entity.attributes.values_mut() {
+        attr.value.map_asset_refs(&mut f);
+    }
+}
+
+fn relative_path(path: &Path, project_path: &Path) -> PathBuf {
+    to_relative_string(path, project_path)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.to_path_buf())
 }
 
 /// Express `path` relative to `project_path`, using `/` separators so the
 /// stored form is identical on every OS. Returns None if the path isn't
 /// under the project root.
-fn to_relative_string(path: &Path, project_path: &Path) -> Option<String> {
+pub(super) fn to_relative_string(path: &Path, project_path: &Path) -> Option<String> {
     let relative = path.strip_prefix(project_path).ok()?;
     let joined = relative
         .components()
@@ -105,18 +368,17 @@ fn to_relative_string(path: &Path, project_path: &Path) -> Option<String> {
 /// Turn a stored path into an absolute one for this machine.
 ///
 /// - Relative paths (the current format) are joined onto the project root.
-/// - Absolute paths (legacy scene files) are remapped by locating the
+/// - Absolute paths (legacy prefab files; scene files get the same
+///   treatment as a format migration) are remapped by locating the
 ///   `assets/` directory in the stored string, so projects saved on another
 ///   machine still open.
-fn resolve_path(stored: &Path, project_path: &Path) -> PathBuf {
+pub(super) fn resolve_path(stored: &Path, project_path: &Path) -> PathBuf {
     if stored.is_relative() {
         return project_path.join(stored);
     }
 
-    let original = stored.to_string_lossy();
-    if let Some(pos) = original.rfind("/assets/") {
-        return project_path.join(&original[pos + 1..]);
+    match migrations::legacy_relative_path(&stored.to_string_lossy()) {
+        Some(relative) => project_path.join(relative),
+        None => stored.to_path_buf(),
     }
-
-    stored.to_path_buf()
 }
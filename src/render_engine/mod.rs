@@ -1,4 +1,4 @@
-use crate::ecs::{AttributeValue, Scene};
+use crate::ecs::Scene;
 use image::GenericImageView;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -29,13 +29,25 @@ pub fn paint_sprite(
     texture_id: egui::TextureId,
     rect: egui::Rect,
     rotation: f32,
+) {
+    paint_sprite_tinted(painter, texture_id, rect, rotation, egui::Color32::WHITE);
+}
+
+/// `paint_sprite` with the texture multiplied by `tint` (e.g. a translucent
+/// white to fade a sprite out).
+pub fn paint_sprite_tinted(
+    painter: &egui::Painter,
+    texture_id: egui::TextureId,
+    rect: egui::Rect,
+    rotation: f32,
+    tint: egui::Color32,
 ) {
     if rotation == 0.0 {
         painter.image(
             texture_id,
             rect,
             egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-            egui::Color32::WHITE,
+            tint,
         );
         return;
     }
@@ -62,15 +74,16 @@ pub fn paint_sprite(
         mesh.vertices.push(egui::epaint::Vertex {
             pos: center + rotated,
             uv,
-            color: egui::Color32::WHITE,
+            color: tint,
         });
     }
     mesh.indices.extend([0, 1, 2, 0, 2, 3]);
     painter.add(mesh);
 }
 
-/// One collider debug shape: screen position, screen size, shape name.
-pub type ColliderRenderData = ((f32, f32), (f32, f32), String);
+/// One collider debug shape: screen position, screen size, shape name, and
+/// the outline in screen space (see `physics_engine::ColliderData`).
+pub type ColliderRenderData = ((f32, f32), (f32, f32), String, Vec<(f32, f32)>);
 
 #[derive(Debug, Clone)]
 pub struct TextureInfo {
@@ -153,6 +166,10 @@ impl RenderEngine {
         let mut render_queue = Vec::new();
 
         for (entity_id, entity) in &scene.entities {
+            // Disabled entities (or children of disabled ones) aren't drawn
+            if !scene.is_active(*entity_id) {
+                continue;
+            }
             if let Ok(image_path) = entity.get_image(0) {
                 let texture_id = Self::path_to_uuid(Path::new(image_path));
 
@@ -163,29 +180,14 @@ impl RenderEngine {
                         .debug(format!("Loaded texture: {}", image_path.to_string_lossy()));
                 }
 
-                // Get position including z coordinate
-                let x = entity.get_x();
-                let y = entity.get_y();
-                let z = entity.get_z();
+                // Children are positioned relative to their parent
+                let world = scene.world_transform(*entity_id).unwrap_or_default();
+                let z = world.z;
 
                 let transform = Transform {
-                    position: (x, y),
-                    rotation: entity
-                        .get_attribute_by_name("rotation")
-                        .and_then(|attr| match attr.value {
-                            AttributeValue::Float(r) => Ok(r),
-                            _ => Err("Invalid rotation attribute type".to_string()),
-                        })
-                        .unwrap_or(0.0)
-                        .to_radians(),
-
-                    scale: entity
-                        .get_attribute_by_name("scale")
-                        .and_then(|attr| match attr.value {
-                            AttributeValue::Vector2(sx, sy) => Ok((sx, sy)),
-                            _ => Err("Invalid scale attribute type".to_string()),
-                        })
-                        .unwrap_or((1.0, 1.0)),
+                    position: (world.x, world.y),
+                    rotation: world.rotation.to_radians(),
+                    scale: world.scale,
                 };
 
                 if let Some(texture_info) = self.texture_cache.get(&texture_id) {
@@ -223,13 +225,15 @@ impl RenderEngine {
     // - (f32, f32): The world coordinate of the collider (x, y).
     // - (f32, f32): The size of the collider in world coordinate (width, height).
     // - String: The shape of the collider (e.g., "Circle", "Rectangle").
+    // - Vec<(f32, f32)>: The outline of capsules, polygons and polylines.
     pub fn render_colliders(
         &mut self,
         collider_data: &[crate::physics_engine::ColliderData],
     ) -> Vec<ColliderRenderData> {
         let mut render_queue = Vec::new();
 
-        for &(world_position, world_size, ref shape) in collider_data {
+        for (world_position, world_size, shape, outline) in collider_data {
+            let (world_position, world_size) = (*world_position, *world_size);
             // Transform position to screen space
             let screen_position = self.camera.world_to_screen(world_position);
 
@@ -245,13 +249,35 @@ impl RenderEngine {
                 && screen_position.1 + screen_size.1 >= 0.0
                 && screen_position.1 <= self.viewport_size.1
             {
-                render_queue.push((screen_position, screen_size, shape.clone()));
+                let outline = outline
+                    .iter()
+                    .map(|point| self.camera.world_to_screen(*point))
+                    .collect();
+                render_queue.push((screen_position, screen_size, shape.clone(), outline));
             }
         }
 
         render_queue
     }
 
+    // joint_data: world positions of each joint's two anchors and its kind.
+    // Returns the anchors in screen space; not culled, as the line between
+    // them can cross the viewport with both ends outside.
+    pub fn render_joints(
+        &self,
+        joint_data: &[crate::physics_engine::JointData],
+    ) -> Vec<((f32, f32), (f32, f32))> {
+        joint_data
+            .iter()
+            .map(|(anchor1, anchor2, _)| {
+                (
+                    self.camera.world_to_screen(*anchor1),
+                    self.camera.world_to_screen(*anchor2),
+                )
+            })
+            .collect()
+    }
+
     pub fn new() -> Self {
         Self {
             viewport_size: (0.0, 0.0),
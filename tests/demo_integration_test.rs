@@ -47,7 +47,9 @@ mod tests {
                 }
 
                 // Mirror the runtime: collision hooks fire after physics
-                self.lua.dispatch_collision_events(self.scene_id).unwrap();
+                self.lua
+                    .dispatch_collision_events(&[self.scene_id])
+                    .unwrap();
             }
         }
 
@@ -113,4 +113,833 @@ mod tests {
         // Verify shared entity reference
         assert!(scene.shared_entity_refs.contains(&shared_id));
     }
+
+    #[test]
+    fn test_shared_entities_play_in_referencing_scenes() {
+        use rust_2d_game_engine::physics_engine::PhysicsEngine;
+        use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+        use std::path::Path;
+
+        let mut scene_manager = SceneManager::new();
+        let texture = Path::new("player.png");
+        let mut player = Entity::new_physical(
+            uuid::Uuid::new_v4(),
+            "player",
+            (10.0, 0.0, 0.0),
+            PhysicsProperties {
+                is_movable: true,
+                affected_by_gravity: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        player.images.push(texture.to_path_buf());
+        let player_id = player.id;
+        scene_manager.shared_entities.insert(player_id, player);
+
+        let level_1 = scene_manager.create_scene("level_1").unwrap();
+        let level_2 = scene_manager.create_scene("level_2").unwrap();
+        for scene_id in [level_1, level_2] {
+            scene_manager
+                .get_scene_mut(scene_id)
+                .unwrap()
+                .add_shared_entity_ref(player_id)
+                .unwrap();
+        }
+
+        // Mounted, the shared entity is drawn and simulated with the scene
+        assert_eq!(
+            scene_manager.mount_shared_entities(level_1).unwrap(),
+            vec![player_id]
+        );
+        assert!(scene_manager.get_shared_entity(player_id).is_none());
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+        renderer.texture_cache.insert(
+            RenderEngine::path_to_uuid(texture),
+            TextureInfo {
+                data: vec![255; 4],
+                dimensions: (1, 1),
+                aspect_ratio: 1.0,
+            },
+        );
+        let scene = scene_manager.get_scene_mut(level_1).unwrap();
+        assert!(renderer
+            .render(scene)
+            .iter()
+            .any(|entry| entry.entity_id == player_id));
+        let mut physics = PhysicsEngine::new();
+        physics.load_scene(scene);
+        assert!(physics.has_rigid_body(&player_id));
+        for _ in 0..10 {
+            let updates = physics.step(scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        let fallen_y = scene.get_entity(player_id).unwrap().get_y();
+        assert!(fallen_y > 0.0);
+
+        // Its state carries over to the next scene that references it
+        scene_manager.unmount_shared_entities(level_1).unwrap();
+        assert!(!scene_manager
+            .get_scene(level_1)
+            .unwrap()
+            .entities
+            .contains_key(&player_id));
+        assert_eq!(
+            scene_manager.get_shared_entity(player_id).unwrap().get_y(),
+            fallen_y
+        );
+        scene_manager.mount_shared_entities(level_2).unwrap();
+        let player = scene_manager
+            .get_scene(level_2)
+            .unwrap()
+            .get_entity(player_id)
+            .unwrap();
+        assert_eq!((player.get_x(), player.get_y()), (10.0, fallen_y));
+    }
+
+    #[test]
+    fn test_shared_entity_stays_in_play_across_loaded_scenes() {
+        let mut scene_manager = SceneManager::new();
+        let hud_id = scene_manager.create_shared_entity("hud").unwrap();
+        let level = scene_manager.create_scene("level").unwrap();
+        let overlay = scene_manager.create_scene("overlay").unwrap();
+        let menu = scene_manager.create_scene("menu").unwrap();
+        for scene_id in [level, overlay] {
+            scene_manager
+                .get_scene_mut(scene_id)
+                .unwrap()
+                .add_shared_entity_ref(hud_id)
+                .unwrap();
+        }
+        scene_manager.set_active_scene(level).unwrap();
+        scene_manager.load_scene_additive(overlay).unwrap();
+
+        // One live copy, mounted in the first scene and shared by the other
+        assert_eq!(
+            scene_manager.mount_shared_entities(level).unwrap(),
+            vec![hud_id]
+        );
+        assert!(scene_manager
+            .mount_shared_entities(overlay)
+            .unwrap()
+            .is_empty());
+        assert_eq!(scene_manager.find_loaded_entity(hud_id).unwrap().0, level);
+        scene_manager
+            .find_loaded_entity_mut(hud_id)
+            .unwrap()
+            .1
+            .set_position(5.0, 7.0, 0.0)
+            .unwrap();
+
+        // Leaving the holding scene hands it over, state and all
+        assert!(scene_manager
+            .unmount_shared_entities(level)
+            .unwrap()
+            .is_empty());
+        scene_manager.set_active_scene(menu).unwrap();
+        assert!(scene_manager.get_shared_entity(hud_id).is_none());
+        let (holder, hud) = scene_manager.find_loaded_entity(hud_id).unwrap();
+        assert_eq!(holder, overlay);
+        assert_eq!((hud.get_x(), hud.get_y()), (5.0, 7.0));
+
+        // The last scene referencing it puts it back in the pool
+        assert_eq!(
+            scene_manager.unmount_shared_entities(overlay).unwrap(),
+            vec![hud_id]
+        );
+        assert_eq!(
+            scene_manager.get_shared_entity(hud_id).unwrap().get_x(),
+            5.0
+        );
+    }
+
+    #[test]
+    fn test_world_transform_follows_parent() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent_id = scene.create_entity("parent").unwrap();
+        let child_id = scene.create_entity("child").unwrap();
+
+        let parent = scene.get_entity_mut(parent_id).unwrap();
+        parent.set_position(100.0, 50.0, 1.0).unwrap();
+        parent
+            .create_attribute(
+                "rotation",
+                AttributeType::Float,
+                AttributeValue::Float(90.0),
+            )
+            .unwrap();
+        parent
+            .create_attribute(
+                "scale",
+                AttributeType::Vector2,
+                AttributeValue::Vector2(2.0, 2.0),
+            )
+            .unwrap();
+
+        // Attach at the parent's origin, then move to a local offset
+        scene
+            .get_entity_mut(child_id)
+            .unwrap()
+            .set_position(100.0, 50.0, 1.0)
+            .unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        let child = scene.get_entity_mut(child_id).unwrap();
+        assert_eq!(
+            (child.get_x(), child.get_y(), child.get_z()),
+            (0.0, 0.0, 0.0)
+        );
+        child.set_position(10.0, 0.0, 2.0).unwrap();
+
+        // (10, 0) scaled by 2 and rotated 90 degrees -> (0, 20)
+        let world = scene.world_transform(child_id).unwrap();
+        assert!((world.x - 100.0).abs() < 1e-4);
+        assert!((world.y - 70.0).abs() < 1e-4);
+        assert_eq!(world.z, 3.0);
+        assert_eq!(world.rotation, 90.0);
+        assert_eq!(world.scale, (2.0, 2.0));
+
+        // Detaching keeps the world position
+        scene.set_parent(child_id, None).unwrap();
+        let child = scene.get_entity(child_id).unwrap();
+        assert!((child.get_x() - 100.0).abs() < 1e-4);
+        assert!((child.get_y() - 70.0).abs() < 1e-4);
+        assert_eq!(child.get_z(), 3.0);
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let a = scene.create_entity("a").unwrap();
+        let b = scene.create_entity("b").unwrap();
+        let c = scene.create_entity("c").unwrap();
+
+        scene.set_parent(b, Some(a)).unwrap();
+        scene.set_parent(c, Some(b)).unwrap();
+
+        assert!(scene.set_parent(a, Some(a)).is_err(), "Self-parenting");
+        assert!(
+            scene.set_parent(a, Some(c)).is_err(),
+            "Grandchild as parent"
+        );
+        assert!(scene.set_parent(a, Some(uuid::Uuid::new_v4())).is_err());
+        assert_eq!(scene.get_parent(a), None);
+
+        assert_eq!(scene.get_children(a), vec![b]);
+        assert_eq!(scene.get_descendants(a), vec![b, c]);
+        assert!(scene.is_ancestor(a, c));
+    }
+
+    #[test]
+    fn test_delete_entity_cascades_to_children() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent = scene.create_entity("parent").unwrap();
+        let child = scene.create_entity("child").unwrap();
+        let grandchild = scene.create_entity("grandchild").unwrap();
+        let other = scene.create_entity("other").unwrap();
+        scene.set_parent(child, Some(parent)).unwrap();
+        scene.set_parent(grandchild, Some(child)).unwrap();
+
+        assert!(scene.delete_entity(parent).unwrap());
+        assert!(scene.get_entity(child).is_err());
+        assert!(scene.get_entity(grandchild).is_err());
+        assert!(scene.get_entity(other).is_ok());
+
+        // A subtree containing the default camera can't be deleted
+        let holder = scene.create_entity("holder").unwrap();
+        let camera = scene.default_camera.unwrap();
+        scene.set_parent(camera, Some(holder)).unwrap();
+        assert!(scene.delete_entity(holder).is_err());
+        assert!(scene.get_entity(holder).is_ok());
+    }
+
+    #[test]
+    fn test_prefab_instances_keep_overrides_on_sync() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let root = scene.create_entity("turret").unwrap();
+        let barrel = scene.create_entity("barrel").unwrap();
+        scene.set_parent(barrel, Some(root)).unwrap();
+        scene
+            .get_entity_mut(root)
+            .unwrap()
+            .create_attribute(
+                "health",
+                AttributeType::Integer,
+                AttributeValue::Integer(10),
+            )
+            .unwrap();
+
+        let source = std::path::Path::new("assets/prefabs/turret.prefab");
+        let mut prefab = scene.create_prefab(root, source).unwrap();
+        assert_eq!(prefab.entities.len(), 2);
+
+        let instance = scene
+            .instantiate_prefab(&prefab, Some(source), (50.0, 60.0))
+            .unwrap();
+        assert_eq!(scene.world_position(instance).unwrap(), (50.0, 60.0, 0.0));
+        assert_eq!(scene.get_children(instance).len(), 1);
+
+        // Per-instance override
+        let health_id = scene
+            .get_entity(instance)
+            .unwrap()
+            .get_attribute_by_name("health")
+            .unwrap()
+            .id;
+        scene
+            .get_entity_mut(instance)
+            .unwrap()
+            .modify_attribute(health_id, None, None, Some(AttributeValue::Integer(99)))
+            .unwrap();
+
+        // Edit the prefab: new attribute, changed value, extra child
+        let root_template = prefab.root().unwrap().id;
+        let template = &mut prefab.entities[0];
+        template
+            .create_attribute("armor", AttributeType::Integer, AttributeValue::Integer(3))
+            .unwrap();
+        let health_template = template.get_attribute_by_name("health").unwrap().id;
+        template
+            .modify_attribute(
+                health_template,
+                None,
+                None,
+                Some(AttributeValue::Integer(20)),
+            )
+            .unwrap();
+        let mut light = Entity::new(uuid::Uuid::new_v4(), "light").unwrap();
+        light.parent = Some(root_template);
+        prefab.entities.push(light);
+
+        scene.sync_prefab_instances(&prefab, source);
+
+        let synced = scene.get_entity(instance).unwrap();
+        assert_eq!(
+            synced.get_attribute_by_name("health").unwrap().value,
+            AttributeValue::Integer(99),
+            "overridden value must survive the sync"
+        );
+        assert_eq!(
+            synced.get_attribute_by_name("armor").unwrap().value,
+            AttributeValue::Integer(3)
+        );
+        assert_eq!(synced.get_x(), 50.0, "instance placement is an override");
+        assert_eq!(
+            scene.get_children(instance).len(),
+            2,
+            "new prefab child spawned"
+        );
+
+        // The original (now an instance too) takes the prefab's value
+        assert_eq!(
+            scene
+                .get_entity(root)
+                .unwrap()
+                .get_attribute_by_name("health")
+                .unwrap()
+                .value,
+            AttributeValue::Integer(20)
+        );
+    }
+
+    #[test]
+    fn test_attribute_index_follows_renames_and_deletes() {
+        let mut entity = Entity::new(uuid::Uuid::new_v4(), "test_entity").unwrap();
+        let id = entity
+            .create_attribute("speed", AttributeType::Float, AttributeValue::Float(1.0))
+            .unwrap();
+
+        entity
+            .modify_attribute(id, Some("velocity".to_string()), None, None)
+            .unwrap();
+        assert!(entity.get_attribute_by_name("speed").is_err());
+        assert_eq!(entity.get_attribute_by_name("velocity").unwrap().id, id);
+        // The old name is free again
+        assert!(entity
+            .create_attribute("speed", AttributeType::Float, AttributeValue::Float(2.0))
+            .is_ok());
+
+        entity.delete_attribute(id).unwrap();
+        assert!(entity.get_attribute_by_name("velocity").is_err());
+
+        // Deserialization rebuilds the index
+        let json = serde_json::to_string(&entity).unwrap();
+        let loaded: Entity = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.get_attribute_by_name("speed").unwrap().value,
+            AttributeValue::Float(2.0)
+        );
+        assert_eq!(loaded.get_x(), 0.0);
+    }
+
+    #[test]
+    fn test_attribute_index_matches_a_linear_scan() {
+        let mut entity = Entity::new(uuid::Uuid::new_v4(), "test_entity").unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("attr_{}", i)).collect();
+        let check = |entity: &Entity| {
+            for name in names.iter().map(String::as_str).chain(["x", "y", "z"]) {
+                let scanned = entity
+                    .attributes
+                    .values()
+                    .find(|attr| attr.name == name)
+                    .map(|attr| attr.id);
+                let indexed = entity.get_attribute_by_name(name).ok().map(|attr| attr.id);
+                assert_eq!(indexed, scanned, "lookup of '{}'", name);
+            }
+        };
+
+        let ids: Vec<uuid::Uuid> = names[..20]
+            .iter()
+            .map(|name| {
+                entity
+                    .create_attribute(name, AttributeType::Integer, AttributeValue::Integer(0))
+                    .unwrap()
+            })
+            .collect();
+        check(&entity);
+
+        // Rename every third attribute to a name not used yet
+        for (i, id) in ids.iter().enumerate().step_by(3) {
+            entity
+                .modify_attribute(*id, Some(names[20 + i].clone()), None, None)
+                .unwrap();
+        }
+        check(&entity);
+
+        // Delete every fourth, then give freed names to others
+        for id in ids.iter().step_by(4) {
+            entity.delete_attribute(*id).unwrap();
+        }
+        check(&entity);
+        for (i, id) in ids.iter().enumerate().skip(1).step_by(4).take(3) {
+            entity
+                .modify_attribute(*id, Some(names[(i - 1) * 3 % 20].clone()), None, None)
+                .unwrap();
+        }
+        check(&entity);
+
+        // And through a save and load
+        let json = serde_json::to_string(&entity).unwrap();
+        let loaded: Entity = serde_json::from_str(&json).unwrap();
+        check(&loaded);
+    }
+
+    #[test]
+    fn test_attribute_types_are_enforced() {
+        let mut entity = Entity::new(uuid::Uuid::new_v4(), "typed").unwrap();
+
+        // Mismatched value is rejected, lossless numbers are coerced
+        assert_eq!(
+            entity.create_attribute(
+                "speed",
+                AttributeType::Float,
+                AttributeValue::String("fast".to_string())
+            ),
+            Err(AttributeError::TypeMismatch {
+                expected: AttributeType::Float,
+                found: AttributeType::String,
+            })
+        );
+        let speed = entity
+            .create_attribute("speed", AttributeType::Float, AttributeValue::Integer(3))
+            .unwrap();
+        assert_eq!(
+            entity.get_attribute(speed).unwrap().value,
+            AttributeValue::Float(3.0)
+        );
+
+        // Changing only the type converts the current value
+        entity
+            .modify_attribute(speed, None, Some(AttributeType::Integer), None)
+            .unwrap();
+        let attr = entity.get_attribute(speed).unwrap();
+        assert_eq!(attr.data_type, AttributeType::Integer);
+        assert_eq!(attr.value, AttributeValue::Integer(3));
+
+        // A failed modify changes nothing (not even the name)
+        assert!(matches!(
+            entity.modify_attribute(
+                speed,
+                Some("velocity".to_string()),
+                None,
+                Some(AttributeValue::Float(2.5))
+            ),
+            Err(AttributeError::Lossy { .. })
+        ));
+        let attr = entity.get_attribute(speed).unwrap();
+        assert_eq!(attr.name, "speed");
+        assert_eq!(attr.value, AttributeValue::Integer(3));
+
+        // The most negative integer has no positive counterpart (and is a
+        // power of two, so a float holds it exactly)
+        assert_eq!(
+            AttributeValue::Integer(i32::MIN).coerce_to(&AttributeType::Float),
+            Ok(AttributeValue::Float(i32::MIN as f32))
+        );
+        assert!(matches!(
+            AttributeValue::Integer(16_777_217).coerce_to(&AttributeType::Float),
+            Err(AttributeError::Lossy { .. })
+        ));
+        // Numbers are checked at the precision they come in
+        assert_eq!(
+            AttributeValue::coerce_number(16_777_217.0, &AttributeType::Integer),
+            Ok(AttributeValue::Integer(16_777_217))
+        );
+        assert!(AttributeValue::coerce_number(16_777_217.0, &AttributeType::Float).is_err());
+        assert!(AttributeValue::coerce_number(1e40, &AttributeType::Float).is_err());
+        assert_eq!(
+            AttributeValue::coerce_number(0.1, &AttributeType::Float),
+            Ok(AttributeValue::Float(0.1))
+        );
+        assert_eq!(
+            AttributeValue::parse("16777217", &AttributeType::Integer),
+            Ok(AttributeValue::Integer(16_777_217))
+        );
+        assert_eq!(
+            AttributeValue::Integer(i32::MIN).coerce_to(&AttributeType::Integer),
+            Ok(AttributeValue::Integer(i32::MIN))
+        );
+
+        // Text input parses as the declared type
+        assert_eq!(
+            AttributeValue::parse("4.0", &AttributeType::Integer),
+            Ok(AttributeValue::Integer(4))
+        );
+        assert_eq!(
+            AttributeValue::parse(" 1, 2 ", &AttributeType::Vector2),
+            Ok(AttributeValue::Vector2(1.0, 2.0))
+        );
+        assert!(AttributeValue::parse("yes", &AttributeType::Boolean).is_err());
+    }
+
+    #[test]
+    fn test_list_text_round_trip_keeps_item_types() {
+        let target = uuid::Uuid::new_v4();
+        let mut waypoint = indexmap::IndexMap::new();
+        waypoint.insert("at".to_string(), AttributeValue::Vector2(1.0, 2.5));
+        waypoint.insert("wait".to_string(), AttributeValue::Float(2.0));
+        let value = AttributeValue::List(vec![
+            AttributeValue::Vector2(0.0, 0.0),
+            AttributeValue::EntityRef(Some(target)),
+            AttributeValue::AssetRef("/project/sounds/step.ogg".into()),
+            AttributeValue::Color(1.0, 0.5, 0.0, 1.0),
+            AttributeValue::Map(waypoint),
+        ]);
+
+        // What the inspector shows reads back as the same value
+        let text = value.to_string();
+        assert_eq!(AttributeValue::parse_like(&text, &value), Ok(value.clone()));
+        // ...where plain JSON loses the types
+        assert_ne!(
+            AttributeValue::parse(&text, &AttributeType::List),
+            Ok(value.clone())
+        );
+
+        // Added items take the first item's type; ones that don't fit it
+        // are read as plain JSON
+        let points = AttributeValue::List(vec![AttributeValue::Vector2(0.0, 0.0)]);
+        assert_eq!(
+            AttributeValue::parse_like("[[0, 0], [10, 5], \"up\"]", &points),
+            Ok(AttributeValue::List(vec![
+                AttributeValue::Vector2(0.0, 0.0),
+                AttributeValue::Vector2(10.0, 5.0),
+                AttributeValue::String("up".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_entity_refs_cleared_when_target_deleted() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let target = scene.create_entity("target").unwrap();
+        let child = scene.create_entity("child").unwrap();
+        scene.set_parent(child, Some(target)).unwrap();
+        let turret = scene.create_entity("turret").unwrap();
+
+        let entity = scene.get_entity_mut(turret).unwrap();
+        let aim = entity
+            .create_attribute(
+                "aim",
+                AttributeType::EntityRef,
+                AttributeValue::EntityRef(Some(target)),
+            )
+            .unwrap();
+        let path = entity
+            .create_attribute(
+                "path",
+                AttributeType::List,
+                AttributeValue::List(vec![
+                    AttributeValue::EntityRef(Some(child)),
+                    AttributeValue::Vector3(1.0, 2.0, 3.0),
+                ]),
+            )
+            .unwrap();
+        entity
+            .create_attribute(
+                "tint",
+                AttributeType::Color,
+                AttributeValue::parse("#ff000080", &AttributeType::Color).unwrap(),
+            )
+            .unwrap();
+
+        // New variants survive the scene file round trip
+        let json = serde_json::to_string(&scene).unwrap();
+        let loaded: Scene = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded
+                .get_entity(turret)
+                .unwrap()
+                .get_attribute(path)
+                .unwrap()
+                .value,
+            scene
+                .get_entity(turret)
+                .unwrap()
+                .get_attribute(path)
+                .unwrap()
+                .value
+        );
+
+        // Deleting the target (and its child) clears every reference
+        scene.delete_entity(target).unwrap();
+        let entity = scene.get_entity(turret).unwrap();
+        assert_eq!(
+            entity.get_attribute(aim).unwrap().value,
+            AttributeValue::EntityRef(None)
+        );
+        assert_eq!(
+            entity.get_attribute(path).unwrap().value,
+            AttributeValue::List(vec![
+                AttributeValue::EntityRef(None),
+                AttributeValue::Vector3(1.0, 2.0, 3.0),
+            ])
+        );
+        assert_eq!(
+            entity.get_attribute_by_name("tint").unwrap().value,
+            AttributeValue::Color(1.0, 0.0, 0.0, 128.0 / 255.0)
+        );
+    }
+
+    #[test]
+    fn test_entity_queries() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let boss = scene.create_entity("boss_dragon").unwrap();
+        let grunt = scene.create_entity("grunt").unwrap();
+        let coin = scene.create_entity("coin").unwrap();
+
+        for (id, tag, x) in [
+            (boss, "enemy", 500.0),
+            (grunt, "enemy", 50.0),
+            (coin, "pickup", 60.0),
+        ] {
+            let entity = scene.get_entity_mut(id).unwrap();
+            entity.add_tag(tag).unwrap();
+            entity.set_x(x).unwrap();
+        }
+        assert!(scene
+            .get_entity_mut(grunt)
+            .unwrap()
+            .add_tag("enemy")
+            .is_err());
+        scene
+            .get_entity_mut(boss)
+            .unwrap()
+            .create_attribute("hp", AttributeType::Integer, AttributeValue::Integer(100))
+            .unwrap();
+        // The coin sits inside the region only through its parent's offset
+        scene.set_parent(coin, Some(grunt)).unwrap();
+        scene.get_entity_mut(coin).unwrap().set_x(-40.0).unwrap();
+
+        assert_eq!(scene.find_entities_with_tag("enemy"), vec![boss, grunt]);
+        assert_eq!(scene.find_entity_by_name("grunt"), Some(grunt));
+        assert_eq!(scene.find_entity_by_name("nobody"), None);
+        assert_eq!(scene.find_entities_by_name("boss_*"), vec![boss]);
+        assert_eq!(scene.find_entities_by_name("?oin"), vec![coin]);
+        assert_eq!(
+            scene.query(&EntityQuery::new().with_tag("enemy").with_attribute("hp")),
+            vec![boss]
+        );
+        assert_eq!(
+            scene.query(&EntityQuery::new().in_region((100.0, -1.0), (1.0, 1.0))),
+            vec![grunt, coin]
+        );
+        assert_eq!(
+            scene.all_tags(),
+            vec!["enemy".to_string(), "pickup".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_scene_records_entity_change_events() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        assert!(scene.drain_events().is_empty());
+
+        let id = scene.create_entity("player").unwrap();
+        let entity = scene.get_entity_mut(id).unwrap();
+        let hp = entity
+            .create_attribute("hp", AttributeType::Integer, AttributeValue::Integer(3))
+            .unwrap();
+        entity
+            .modify_attribute(hp, None, None, Some(AttributeValue::Integer(2)))
+            .unwrap();
+        // Writing the same value again is not a change
+        entity
+            .modify_attribute(hp, None, None, Some(AttributeValue::Integer(2)))
+            .unwrap();
+        entity
+            .modify_attribute(hp, Some("health".to_string()), None, None)
+            .unwrap();
+        entity.add_image("player.png".into()).unwrap();
+
+        let named = |name: &str| name.to_string();
+        assert_eq!(
+            scene.drain_events(),
+            vec![
+                SceneEvent::EntityCreated(id),
+                SceneEvent::AttributeCreated {
+                    entity_id: id,
+                    attribute_id: hp,
+                    name: named("hp"),
+                },
+                SceneEvent::AttributeChanged {
+                    entity_id: id,
+                    attribute_id: hp,
+                    name: named("hp"),
+                    old_value: AttributeValue::Integer(3),
+                    new_value: AttributeValue::Integer(2),
+                },
+                SceneEvent::AttributeDeleted {
+                    entity_id: id,
+                    attribute_id: hp,
+                    name: named("hp"),
+                },
+                SceneEvent::AttributeCreated {
+                    entity_id: id,
+                    attribute_id: hp,
+                    name: named("health"),
+                },
+                SceneEvent::ResourceAdded {
+                    entity_id: id,
+                    kind: ResourceKind::Image,
+                    path: "player.png".into(),
+                },
+            ]
+        );
+        assert!(scene.drain_events().is_empty());
+
+        // A deleted entity's last changes come before its deletion
+        scene.get_entity_mut(id).unwrap().set_x(5.0).unwrap();
+        scene.delete_entity(id).unwrap();
+        let events = scene.drain_events();
+        assert!(matches!(
+            &events[..],
+            [SceneEvent::AttributeChanged { name, .. }, SceneEvent::EntityDeleted(deleted)]
+                if name == "x" && *deleted == id
+        ));
+    }
+
+    #[test]
+    fn test_write_back_records_no_events_and_reports_every_failure() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let crate_id = scene.create_entity("crate").unwrap();
+        let barrel_id = scene.create_entity("barrel").unwrap();
+        let x_of = |scene: &Scene, id| {
+            scene
+                .get_entity(id)
+                .unwrap()
+                .get_attribute_by_name("x")
+                .unwrap()
+                .id
+        };
+        let (crate_x, barrel_x) = (x_of(&scene, crate_id), x_of(&scene, barrel_id));
+        let missing = uuid::Uuid::new_v4();
+        scene.drain_events();
+
+        let err = scene
+            .update_entity_attributes(vec![
+                (missing, crate_x, AttributeValue::Float(1.0)),
+                (crate_id, crate_x, AttributeValue::Float(2.0)),
+                (
+                    crate_id,
+                    crate_x,
+                    AttributeValue::String("left".to_string()),
+                ),
+                (barrel_id, barrel_x, AttributeValue::Float(3.0)),
+            ])
+            .unwrap_err();
+
+        // Both failures are reported and the good updates still landed
+        assert!(err.contains(&missing.to_string()));
+        assert!(err.contains(&crate_id.to_string()));
+        assert_eq!(scene.get_entity(crate_id).unwrap().get_x(), 2.0);
+        assert_eq!(scene.get_entity(barrel_id).unwrap().get_x(), 3.0);
+        assert!(scene.drain_events().is_empty());
+    }
+
+    // Render + physics write-back must stay linear in the entity count:
+    // per-entity cost at 10k entities shouldn't be far off the cost at 1k.
+    // Wall-clock timing, so not part of the default run
+    #[test]
+    #[ignore = "timing-sensitive; run with `cargo test -- --ignored`"]
+    fn test_frame_cost_scales_to_10k_entities() {
+        use rust_2d_game_engine::physics_engine::PhysicsEngine;
+        use rust_2d_game_engine::render_engine::{RenderEngine, TextureInfo};
+        use std::path::Path;
+        use std::time::{Duration, Instant};
+
+        fn frame_time(count: usize) -> Duration {
+            let texture = Path::new("bench.png");
+            let mut renderer = RenderEngine::new();
+            renderer.update_viewport_size(800.0, 600.0);
+            renderer.texture_cache.insert(
+                RenderEngine::path_to_uuid(texture),
+                TextureInfo {
+                    data: vec![255; 4],
+                    dimensions: (1, 1),
+                    aspect_ratio: 1.0,
+                },
+            );
+
+            let mut scene = Scene::new("bench").unwrap();
+            for i in 0..count {
+                let mut entity = Entity::new_physical(
+                    uuid::Uuid::new_v4(),
+                    &format!("entity_{}", i),
+                    ((i % 100) as f32 * 50.0, (i / 100) as f32 * 50.0, 0.0),
+                    PhysicsProperties::default(),
+                )
+                .unwrap();
+                entity.images.push(texture.to_path_buf());
+                scene.entities.insert(entity.id, entity);
+            }
+            let mut physics = PhysicsEngine::new();
+            physics.load_scene(&scene);
+
+            // Best of a few frames to keep scheduler noise out
+            (0..3)
+                .map(|_| {
+                    let start = Instant::now();
+                    let queue = renderer.render(&scene);
+                    let updates = physics.step(&mut scene);
+                    assert!(!queue.is_empty());
+                    // x, y and rotation
+                    assert_eq!(updates.len(), 3 * count);
+                    scene.update_entity_attributes(updates).unwrap();
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        }
+
+        let small = frame_time(1_000);
+        let large = frame_time(10_000);
+        let per_entity_small = small.as_secs_f64() / 1_000.0;
+        let per_entity_large = large.as_secs_f64() / 10_000.0;
+        assert!(
+            per_entity_large < per_entity_small * 4.0,
+            "per-entity frame cost grew from {:?} to {:?}",
+            Duration::from_secs_f64(per_entity_small),
+            Duration::from_secs_f64(per_entity_large)
+        );
+    }
 }
@@ -0,0 +1,329 @@
+/// Play-mode tests driving `GameRuntime::update` through a headless egui
+/// context (no window; 1/60 s per frame).
+#[cfg(test)]
+mod tests {
+    use rust_2d_game_engine::audio_engine::AudioEngine;
+    use rust_2d_game_engine::ecs::{
+        AttributeType, AttributeValue, PhysicsProperties, SceneManager,
+    };
+    use rust_2d_game_engine::game_runtime::{GameRuntime, SceneTransition};
+    use rust_2d_game_engine::input_handler::InputHandler;
+    use rust_2d_game_engine::physics_engine::PhysicsEngine;
+    use rust_2d_game_engine::render_engine::RenderEngine;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn run_frames(runtime: &mut GameRuntime, ctx: &egui::Context, frames: usize) {
+        for _ in 0..frames {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(800.0, 600.0),
+                )),
+                predicted_dt: 1.0 / 60.0,
+                ..Default::default()
+            };
+            let mut output = ctx.run_ui(input, |ui| {
+                let rect = ui.max_rect();
+                runtime.update(ctx, ui, rect);
+            });
+            // Nothing uploads the font atlas headlessly
+            output.textures_delta.clear();
+        }
+    }
+
+    fn write_script(dir: &std::path::Path, name: &str, source: &str) -> PathBuf {
+        let path = dir.join(format!("{}.lua", name));
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn attribute(runtime: &GameRuntime, scene_id: Uuid, entity_id: Uuid, name: &str) -> f32 {
+        let manager = runtime.get_scene_manager();
+        let entity = manager
+            .get_scene(scene_id)
+            .unwrap()
+            .get_entity(entity_id)
+            .unwrap();
+        match entity.get_attribute_by_name(name).map(|attr| &attr.value) {
+            Ok(AttributeValue::Float(value)) => *value,
+            _ => panic!("attribute {} missing", name),
+        }
+    }
+
+    #[test]
+    fn test_scripts_switch_scenes_with_hooks() {
+        let script_dir = std::env::temp_dir().join(format!(
+            "rust2d_engine_test_scene_switch_{}",
+            Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&script_dir).unwrap();
+
+        let mut manager = SceneManager::new();
+        let level_1 = manager.create_scene("level_1").unwrap();
+        let level_2 = manager.create_scene("level_2").unwrap();
+        manager.set_active_scene(level_1).unwrap();
+
+        // level_1 leaves for level_2 on its second frame
+        let exit_script = write_script(
+            &script_dir,
+            "exit",
+            r#"
+            function update(scene_id, entity_id)
+                local frames = get_attribute(scene_id, entity_id, "frames") + 1
+                set_attribute(scene_id, entity_id, "frames", frames)
+                if frames == 2 then load_scene("level_2", "fade", 0.2) end
+            end
+            function on_scene_exit(scene_id, entity_id)
+                script_state.state.exited = true
+            end
+            "#,
+        );
+        // level_2 records the order of its hooks
+        let enter_script = write_script(
+            &script_dir,
+            "enter",
+            r#"
+            function init(scene_id, entity_id)
+                set_attribute(scene_id, entity_id, "init_order", 1)
+            end
+            function on_scene_enter(scene_id, entity_id)
+                local order = get_attribute(scene_id, entity_id, "init_order") + 1
+                set_attribute(scene_id, entity_id, "enter_order", order)
+                if script_state.state.exited then
+                    set_attribute(scene_id, entity_id, "saw_exit", 1)
+                end
+            end
+            function update(scene_id, entity_id) end
+            "#,
+        );
+        let mut scripted = |scene_id: Uuid, name: &str, script: PathBuf, attrs: &[&str]| {
+            let scene = manager.get_scene_mut(scene_id).unwrap();
+            let id = scene.create_entity(name).unwrap();
+            let entity = scene.get_entity_mut(id).unwrap();
+            for attr in attrs {
+                entity
+                    .create_attribute(attr, AttributeType::Float, AttributeValue::Float(0.0))
+                    .unwrap();
+            }
+            entity.set_script(script).unwrap();
+            id
+        };
+        let leaver = scripted(level_1, "leaver", exit_script, &["frames"]);
+        let greeter = scripted(
+            level_2,
+            "greeter",
+            enter_script,
+            &["init_order", "enter_order", "saw_exit"],
+        );
+
+        let mut runtime = GameRuntime::new(
+            manager,
+            PhysicsEngine::new(),
+            RenderEngine::new(),
+            InputHandler::new(),
+            AudioEngine::new(),
+            60,
+        );
+        runtime.run().unwrap();
+        let ctx = egui::Context::default();
+
+        // Frame 2 requests the switch; the 0.2 s fade switches halfway
+        run_frames(&mut runtime, &ctx, 3);
+        assert!(runtime.is_transitioning());
+        assert_eq!(runtime.get_scene_manager().active_scene, Some(level_1));
+        assert_eq!(attribute(&runtime, level_1, leaver, "frames"), 2.0);
+
+        run_frames(&mut runtime, &ctx, 20);
+        assert!(!runtime.is_transitioning());
+        assert_eq!(runtime.get_scene_manager().active_scene, Some(level_2));
+        assert_eq!(attribute(&runtime, level_2, greeter, "init_order"), 1.0);
+        assert_eq!(attribute(&runtime, level_2, greeter, "enter_order"), 2.0);
+        assert_eq!(attribute(&runtime, level_2, greeter, "saw_exit"), 1.0);
+
+        // Loading a scene again starts it over from its pre-play state
+        runtime.load_scene(level_1, SceneTransition::Cut).unwrap();
+        run_frames(&mut runtime, &ctx, 1);
+        assert_eq!(runtime.get_scene_manager().active_scene, Some(level_1));
+        assert_eq!(attribute(&runtime, level_1, leaver, "frames"), 1.0);
+        assert!(runtime
+            .load_scene(Uuid::new_v4(), SceneTransition::Cut)
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(&script_dir);
+    }
+
+    #[test]
+    fn test_additive_scenes_play_alongside_the_active_scene() {
+        let script_dir =
+            std::env::temp_dir().join(format!("rust2d_engine_test_additive_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&script_dir).unwrap();
+
+        let mut manager = SceneManager::new();
+        let level_1 = manager.create_scene("level_1").unwrap();
+        let level_2 = manager.create_scene("level_2").unwrap();
+        let hud = manager.create_scene("hud").unwrap();
+        manager.set_active_scene(level_1).unwrap();
+        manager.load_scene_additive(hud).unwrap();
+        assert!(manager.load_scene_additive(level_1).is_err());
+        assert_eq!(manager.loaded_scenes(), vec![level_1, hud]);
+
+        // A falling player in each level
+        for level in [level_1, level_2] {
+            manager
+                .get_scene_mut(level)
+                .unwrap()
+                .create_physical_entity(
+                    "player",
+                    (0.0, 0.0, 0.0),
+                    PhysicsProperties {
+                        is_movable: true,
+                        affected_by_gravity: true,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        // The HUD counts its frames and mirrors the player of whichever
+        // level is playing underneath it
+        let hud_script = write_script(
+            &script_dir,
+            "hud",
+            r#"
+            function update(scene_id, entity_id)
+                local frames = get_attribute(scene_id, entity_id, "frames") + 1
+                set_attribute(scene_id, entity_id, "frames", frames)
+                local player_scene, player = find_entity_in_loaded_scenes("player")
+                if player then
+                    set_attribute(scene_id, entity_id, "player_y",
+                        get_attribute(player_scene, player, "y"))
+                end
+            end
+            "#,
+        );
+        let hud_scene = manager.get_scene_mut(hud).unwrap();
+        let counter = hud_scene.create_entity("counter").unwrap();
+        let entity = hud_scene.get_entity_mut(counter).unwrap();
+        for attr in ["frames", "player_y"] {
+            entity
+                .create_attribute(attr, AttributeType::Float, AttributeValue::Float(0.0))
+                .unwrap();
+        }
+        entity.set_script(hud_script).unwrap();
+
+        let mut runtime = GameRuntime::new(
+            manager,
+            PhysicsEngine::new(),
+            RenderEngine::new(),
+            InputHandler::new(),
+            AudioEngine::new(),
+            60,
+        );
+        runtime.run().unwrap();
+        let ctx = egui::Context::default();
+
+        run_frames(&mut runtime, &ctx, 10);
+        assert_eq!(attribute(&runtime, hud, counter, "frames"), 10.0);
+        let fallen_y = attribute(&runtime, hud, counter, "player_y");
+        assert!(fallen_y > 0.0);
+
+        // Switching levels leaves the HUD running
+        runtime.load_scene(level_2, SceneTransition::Cut).unwrap();
+        run_frames(&mut runtime, &ctx, 2);
+        assert_eq!(
+            runtime.get_scene_manager().loaded_scenes(),
+            vec![level_2, hud]
+        );
+        assert_eq!(attribute(&runtime, hud, counter, "frames"), 12.0);
+        assert!(attribute(&runtime, hud, counter, "player_y") < fallen_y);
+
+        // Unloaded, it stops; loaded again, it starts over
+        runtime.unload_scene(hud).unwrap();
+        run_frames(&mut runtime, &ctx, 2);
+        assert_eq!(attribute(&runtime, hud, counter, "frames"), 12.0);
+        assert!(runtime.unload_scene(level_2).is_err());
+        runtime.load_scene_additive(hud).unwrap();
+        run_frames(&mut runtime, &ctx, 1);
+        assert_eq!(attribute(&runtime, hud, counter, "frames"), 1.0);
+
+        let _ = std::fs::remove_dir_all(&script_dir);
+    }
+
+    #[test]
+    fn test_attribute_observers_and_live_physics_edits() {
+        let script_dir =
+            std::env::temp_dir().join(format!("rust2d_engine_test_observers_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&script_dir).unwrap();
+
+        let mut manager = SceneManager::new();
+        let level = manager.create_scene("level").unwrap();
+        manager.set_active_scene(level).unwrap();
+        let scene = manager.get_scene_mut(level).unwrap();
+        // Starts as static geometry
+        let crate_id = scene
+            .create_physical_entity(
+                "crate",
+                (0.0, 0.0, 0.0),
+                PhysicsProperties {
+                    is_movable: false,
+                    affected_by_gravity: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // Releases the crate on its second frame and watches it happen
+        let script = write_script(
+            &script_dir,
+            "release",
+            r#"
+            function init(scene_id, entity_id)
+                local crate = find_entity_by_name(scene_id, "crate")
+                on_attribute_changed(crate, "is_movable", function(id, name, value, old)
+                    if value and not old then
+                        set_attribute(scene_id, entity_id, "released", 1)
+                    end
+                end)
+            end
+            function update(scene_id, entity_id)
+                local frames = get_attribute(scene_id, entity_id, "frames") + 1
+                set_attribute(scene_id, entity_id, "frames", frames)
+                if frames == 2 then
+                    set_attribute(scene_id, find_entity_by_name(scene_id, "crate"), "is_movable", true)
+                end
+            end
+            "#,
+        );
+        let controller = scene.create_entity("controller").unwrap();
+        let entity = scene.get_entity_mut(controller).unwrap();
+        for attr in ["frames", "released"] {
+            entity
+                .create_attribute(attr, AttributeType::Float, AttributeValue::Float(0.0))
+                .unwrap();
+        }
+        entity.set_script(script).unwrap();
+
+        let mut runtime = GameRuntime::new(
+            manager,
+            PhysicsEngine::new(),
+            RenderEngine::new(),
+            InputHandler::new(),
+            AudioEngine::new(),
+            60,
+        );
+        runtime.run().unwrap();
+        let ctx = egui::Context::default();
+
+        run_frames(&mut runtime, &ctx, 1);
+        assert_eq!(attribute(&runtime, level, crate_id, "y"), 0.0);
+        assert_eq!(attribute(&runtime, level, controller, "released"), 0.0);
+
+        // The edit reaches the body without reloading the scene
+        run_frames(&mut runtime, &ctx, 10);
+        assert_eq!(attribute(&runtime, level, controller, "released"), 1.0);
+        assert!(attribute(&runtime, level, crate_id, "y") > 0.0);
+
+        let _ = std::fs::remove_dir_all(&script_dir);
+    }
+}
@@ -144,7 +144,9 @@ mod tests {
 #[cfg(test)]
 mod session_tests {
     use rust_2d_game_engine::audio_engine::AudioEngine;
-    use rust_2d_game_engine::ecs::SceneManager;
+    use rust_2d_game_engine::ecs::{
+        AttributeType, AttributeValue, PhysicsProperties, SceneManager,
+    };
     use rust_2d_game_engine::input_handler::InputHandler;
     use rust_2d_game_engine::lua_scripting::LuaScripting;
     use rust_2d_game_engine::physics_engine::PhysicsEngine;
@@ -550,6 +552,148 @@ mod session_tests {
         assert!(missing, "get_attribute on a missing attribute returns nil");
     }
 
+    #[test]
+    fn test_rich_attribute_values_from_lua() {
+        use rust_2d_game_engine::ecs::{AttributeType, AttributeValue};
+
+        let mut session = setup("rich_attributes");
+
+        let (entity_id, target_id) = {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            let target_id = scene.create_entity("target").unwrap();
+            let id = scene.create_entity("turret").unwrap();
+            let entity = scene.get_entity_mut(id).unwrap();
+            for data_type in [
+                AttributeType::Color,
+                AttributeType::Vector3,
+                AttributeType::EntityRef,
+                AttributeType::List,
+                AttributeType::Map,
+            ] {
+                let name = format!("{:?}", data_type).to_lowercase();
+                entity
+                    .create_attribute(
+                        &name,
+                        data_type.clone(),
+                        AttributeValue::default_for(&data_type),
+                    )
+                    .unwrap();
+            }
+            (id, target_id)
+        };
+
+        add_scripted_entity(
+            &session,
+            "rich",
+            &format!(
+                r#"
+            function update(scene_id, entity_id)
+                local turret = "{}"
+                set_attribute(scene_id, turret, "color", {{ r = 1, g = 0.5, b = 0 }})
+                set_attribute(scene_id, turret, "vector3", {{ 1, 2, 3 }})
+                set_attribute(scene_id, turret, "entityref", "{}")
+                set_attribute(scene_id, turret, "list", {{ 1, 2.5, "three" }})
+                set_attribute(scene_id, turret, "map", {{ hp = 3 }})
+
+                local color = get_attribute(scene_id, turret, "color")
+                local list = get_attribute(scene_id, turret, "list")
+                script_state.state.read_back = color.a == 1 and list[3] == "three"
+                    and get_attribute(scene_id, turret, "map").hp == 3
+                    and get_attribute(scene_id, turret, "entityref") == "{}"
+            end
+            "#,
+                entity_id, target_id, target_id
+            ),
+        );
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+
+        let read_back: bool = session
+            .lua
+            .lua
+            .load("return script_state.state.read_back == true")
+            .eval()
+            .unwrap();
+        assert!(read_back);
+
+        let manager = session.scene_manager.borrow();
+        let entity = manager
+            .get_scene(session.scene_id)
+            .unwrap()
+            .get_entity(entity_id)
+            .unwrap();
+        let value = |name: &str| entity.get_attribute_by_name(name).unwrap().value.clone();
+        assert_eq!(value("color"), AttributeValue::Color(1.0, 0.5, 0.0, 1.0));
+        assert_eq!(value("vector3"), AttributeValue::Vector3(1.0, 2.0, 3.0));
+        assert_eq!(
+            value("entityref"),
+            AttributeValue::EntityRef(Some(target_id))
+        );
+        assert_eq!(
+            value("list"),
+            AttributeValue::List(vec![
+                AttributeValue::Integer(1),
+                AttributeValue::Float(2.5),
+                AttributeValue::String("three".into()),
+            ])
+        );
+        assert_eq!(
+            value("map"),
+            AttributeValue::Map(
+                [("hp".to_string(), AttributeValue::Integer(3))]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn test_tags_and_queries_from_lua() {
+        let mut session = setup("tags_and_queries");
+
+        let (enemy_a, enemy_b) = {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            let a = scene.create_entity("enemy_a").unwrap();
+            let b = scene.create_entity("enemy_b").unwrap();
+            (a, b)
+        };
+
+        add_scripted_entity(
+            &session,
+            "spotter",
+            r#"
+            function update(scene_id, entity_id)
+                add_tag(scene_id, find_entity_by_name(scene_id, "enemy_a"), "enemy")
+                add_tag(scene_id, find_entity_by_name(scene_id, "enemy_b"), "enemy")
+                script_state.state.enemies = #find_entities_with_tag(scene_id, "enemy")
+                script_state.state.named = #find_entities(scene_id, { tag = "enemy", name = "*_b" })
+                script_state.state.missing = find_entity_by_name(scene_id, "nobody") == nil
+                script_state.state.removed = remove_tag(scene_id, find_entity_by_name(scene_id, "enemy_a"), "enemy")
+            end
+            "#,
+        );
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+
+        let (enemies, named, missing, removed): (i64, i64, bool, bool) = session
+            .lua
+            .lua
+            .load(
+                "return script_state.state.enemies, script_state.state.named, \
+                 script_state.state.missing, script_state.state.removed",
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((enemies, named), (2, 1));
+        assert!(missing);
+        assert!(removed);
+
+        let manager = session.scene_manager.borrow();
+        let scene = manager.get_scene(session.scene_id).unwrap();
+        assert!(!scene.get_entity(enemy_a).unwrap().has_tag("enemy"));
+        assert!(scene.get_entity(enemy_b).unwrap().has_tag("enemy"));
+    }
+
     #[test]
     fn test_set_attribute_type_mismatch_errors() {
         use rust_2d_game_engine::ecs::{AttributeType, AttributeValue};
@@ -565,16 +709,43 @@ mod session_tests {
                 .unwrap()
                 .create_attribute("speed", AttributeType::Float, AttributeValue::Float(1.0))
                 .unwrap();
+            scene
+                .get_entity_mut(id)
+                .unwrap()
+                .create_attribute("score", AttributeType::Integer, AttributeValue::Integer(0))
+                .unwrap();
+            scene
+                .get_entity_mut(id)
+                .unwrap()
+                .create_attribute("range", AttributeType::Float, AttributeValue::Float(0.0))
+                .unwrap();
+            scene
+                .get_entity_mut(id)
+                .unwrap()
+                .create_attribute(
+                    "label",
+                    AttributeType::String,
+                    AttributeValue::String(String::new()),
+                )
+                .unwrap();
             id
         };
 
+        // 16777217 is a whole number an f32 can't hold
         add_scripted_entity(
             &session,
             "bad_write",
             r#"
             function update(scene_id, entity_id)
-                local ok = pcall(set_attribute, scene_id, script_state.state.target, "speed", "fast")
-                script_state.state.write_rejected = not ok
+                local target = script_state.state.target
+                local ok = pcall(set_attribute, scene_id, target, "speed", "fast")
+                local rounded = pcall(set_attribute, scene_id, target, "speed", 16777217.0)
+                script_state.state.write_rejected = not ok and not rounded
+                set_attribute(scene_id, target, "score", 16777217.0)
+                -- Integers past i32 are fine where a Float holds them
+                set_attribute(scene_id, target, "range", 1 << 31)
+                local _, err = pcall(set_attribute, scene_id, target, "label", 1 << 40)
+                script_state.state.label_error = tostring(err)
             end
             "#,
         );
@@ -610,6 +781,37 @@ mod session_tests {
             AttributeValue::Float(1.0),
             "the attribute must be unchanged after a rejected write"
         );
+        // Lua numbers reach Integer attributes at full precision
+        assert_eq!(
+            scene
+                .get_entity(entity_id)
+                .unwrap()
+                .get_attribute_by_name("score")
+                .unwrap()
+                .value,
+            AttributeValue::Integer(16_777_217)
+        );
+        assert_eq!(
+            scene
+                .get_entity(entity_id)
+                .unwrap()
+                .get_attribute_by_name("range")
+                .unwrap()
+                .value,
+            AttributeValue::Float(2_147_483_648.0)
+        );
+        drop(manager);
+        let label_error: String = session
+            .lua
+            .lua
+            .load("return script_state.state.label_error")
+            .eval()
+            .unwrap();
+        assert!(
+            label_error.contains("Expected a String value"),
+            "{}",
+            label_error
+        );
     }
 
     #[test]
@@ -648,6 +850,104 @@ mod session_tests {
         assert!(ordered, "init() must run before the first update()");
     }
 
+    #[test]
+    fn test_disabled_entities_skip_update_and_fire_hooks() {
+        let mut session = setup("enable_hooks");
+        let entity_id = add_scripted_entity(
+            &session,
+            "toggled",
+            r#"
+            local state = script_state.state
+            function update(scene_id, entity_id)
+                state.updates = (state.updates or 0) + 1
+                state.enabled_flag = is_enabled(scene_id, entity_id)
+            end
+            function on_enable(scene_id, entity_id) state.enables = (state.enables or 0) + 1 end
+            function on_disable(scene_id, entity_id) state.disables = (state.disables or 0) + 1 end
+            "#,
+        );
+        let run = |session: &mut TestSession| {
+            session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+        };
+        let counts = |session: &TestSession| -> (i64, i64, i64) {
+            session
+                .lua
+                .lua
+                .load(
+                    "local s = script_state.state \
+                     return s.updates or 0, s.enables or 0, s.disables or 0",
+                )
+                .eval()
+                .unwrap()
+        };
+
+        run(&mut session);
+        assert_eq!(counts(&session), (1, 0, 0));
+
+        let set_enabled = |session: &TestSession, enabled: bool| {
+            let call = format!(
+                "set_enabled('{}', '{}', {})",
+                session.scene_id, entity_id, enabled
+            );
+            session.lua.lua.load(call).exec().unwrap();
+        };
+        set_enabled(&session, false);
+        run(&mut session);
+        run(&mut session);
+        assert_eq!(counts(&session), (1, 0, 1), "no updates while disabled");
+
+        set_enabled(&session, true);
+        run(&mut session);
+        assert_eq!(counts(&session), (2, 1, 1));
+        let flag: bool = session
+            .lua
+            .lua
+            .load("return script_state.state.enabled_flag")
+            .eval()
+            .unwrap();
+        assert!(flag);
+    }
+
+    #[test]
+    fn test_entity_enabled_later_runs_init_before_on_enable() {
+        let mut session = setup("enable_before_init");
+        let entity_id = add_scripted_entity(
+            &session,
+            "sleeper",
+            r#"
+            local state = script_state.state
+            local function log(name) state.calls = (state.calls or "") .. name .. " " end
+            function init(scene_id, entity_id) log("init") end
+            function on_enable(scene_id, entity_id) log("on_enable") end
+            function update(scene_id, entity_id) log("update") end
+            "#,
+        );
+        let set_enabled = |session: &TestSession, enabled: bool| {
+            let call = format!(
+                "set_enabled('{}', '{}', {})",
+                session.scene_id, entity_id, enabled
+            );
+            session.lua.lua.load(call).exec().unwrap();
+        };
+        let calls = |session: &TestSession| -> String {
+            session
+                .lua
+                .lua
+                .load("return script_state.state.calls or ''")
+                .eval()
+                .unwrap()
+        };
+
+        set_enabled(&session, false);
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+        assert_eq!(calls(&session), "", "disabled entities get no calls");
+
+        set_enabled(&session, true);
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+        assert_eq!(calls(&session), "init on_enable update update ");
+    }
+
     #[test]
     fn test_on_collision_fires_once_per_contact() {
         use rust_2d_game_engine::ecs::PhysicsProperties;
@@ -725,7 +1025,7 @@ mod session_tests {
             }
             session
                 .lua
-                .dispatch_collision_events(session.scene_id)
+                .dispatch_collision_events(&[session.scene_id])
                 .unwrap();
         }
 
@@ -742,6 +1042,102 @@ mod session_tests {
         assert_eq!(other, "obstacle");
     }
 
+    #[test]
+    fn test_trigger_hooks_fire_on_enter_and_exit() {
+        use rust_2d_game_engine::ecs::{AttributeType, AttributeValue, PhysicsProperties};
+
+        let mut session = setup("trigger_hooks");
+        let scripts = [
+            (
+                "coin",
+                r#"
+                function update(scene_id, entity_id) end
+
+                function on_trigger_enter(scene_id, entity_id, other_id)
+                    script_state.state.coin_touched_by = get_entity_name(scene_id, other_id)
+                end
+
+                function on_trigger_exit(scene_id, entity_id, other_id)
+                    script_state.state.coin_exits = (script_state.state.coin_exits or 0) + 1
+                end
+                "#,
+            ),
+            (
+                "player",
+                r#"
+                function update(scene_id, entity_id) end
+
+                function on_trigger_enter(scene_id, entity_id, other_id)
+                    script_state.state.player_entered = get_entity_name(scene_id, other_id)
+                end
+
+                function on_collision(scene_id, entity_id, other_id)
+                    script_state.state.collided = true
+                end
+                "#,
+            ),
+        ];
+
+        // A coin (sensor) and a kinematic player moving down through it
+        let mut ids = Vec::new();
+        {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            for ((name, source), (y, flag)) in scripts
+                .iter()
+                .zip([(100.0, "is_sensor"), (0.0, "is_kinematic")])
+            {
+                let script_path = session.script_dir.join(format!("{}.lua", name));
+                std::fs::write(&script_path, source).unwrap();
+                let id = scene
+                    .create_physical_entity(name, (0.0, y, 0.0), PhysicsProperties::default())
+                    .unwrap();
+                let entity = scene.get_entity_mut(id).unwrap();
+                entity.set_script(script_path).unwrap();
+                entity
+                    .create_attribute(flag, AttributeType::Boolean, AttributeValue::Boolean(true))
+                    .unwrap();
+                for attr in ["collider_width", "collider_height"] {
+                    entity
+                        .create_attribute(attr, AttributeType::Float, AttributeValue::Float(20.0))
+                        .unwrap();
+                }
+                ids.push(id);
+            }
+            let mut physics = session.physics.borrow_mut();
+            physics.load_scene(scene);
+            physics.set_velocity(&ids[1], rapier2d::prelude::Vector::new(0.0, 150.0));
+        }
+
+        for _ in 0..90 {
+            session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+            {
+                let mut manager = session.scene_manager.borrow_mut();
+                let scene = manager.get_scene_mut(session.scene_id).unwrap();
+                let updates = session.physics.borrow_mut().step(scene);
+                scene.update_entity_attributes(updates).unwrap();
+            }
+            session
+                .lua
+                .dispatch_collision_events(&[session.scene_id])
+                .unwrap();
+        }
+
+        let (touched_by, exits, player_entered, collided): (String, i64, String, bool) = session
+            .lua
+            .lua
+            .load(
+                "local s = script_state.state
+                return s.coin_touched_by, s.coin_exits, s.player_entered, s.collided == true",
+            )
+            .eval()
+            .unwrap();
+        assert_eq!(touched_by, "player");
+        assert_eq!(exits, 1);
+        assert_eq!(player_entered, "coin", "both sides get trigger hooks");
+        assert!(!collided, "sensors don't collide");
+    }
+
     #[test]
     fn test_audio_bindings_fail_gracefully() {
         let mut session = setup("audio_bindings");
@@ -807,4 +1203,311 @@ mod session_tests {
         assert_eq!(entity.get_y(), 34.0, "spawn y must be applied");
         assert_eq!(entity.get_z(), 2.0, "spawn z must be applied");
     }
+
+    #[test]
+    fn test_set_parent_from_lua() {
+        let mut session = setup("set_parent");
+        add_scripted_entity(
+            &session,
+            "builder",
+            r#"
+            function init(scene_id, entity_id)
+                set_position(scene_id, entity_id, 100.0, 50.0)
+                local child = add_entity(scene_id, "child")
+                set_position(scene_id, child, 110.0, 60.0)
+                set_parent(scene_id, child, entity_id)
+                script_state.state.child = child
+                script_state.state.parent = get_parent(scene_id, child)
+                script_state.state.local_x = get_attribute(scene_id, child, "x")
+                script_state.state.world_x, script_state.state.world_y =
+                    get_world_position(scene_id, child)
+                script_state.state.cycle_ok = pcall(set_parent, scene_id, entity_id, child)
+            end
+
+            function update(scene_id, entity_id) end
+            "#,
+        );
+
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+
+        let (parent, local_x, world_x, world_y, cycle_ok): (String, f32, f32, f32, bool) = session
+            .lua
+            .lua
+            .load(
+                "local s = script_state.state
+                return s.parent, s.local_x, s.world_x, s.world_y, s.cycle_ok",
+            )
+            .eval()
+            .unwrap();
+        let builder_id = {
+            let manager = session.scene_manager.borrow();
+            let scene = manager.get_scene(session.scene_id).unwrap();
+            scene
+                .entities
+                .values()
+                .find(|e| e.name == "builder")
+                .unwrap()
+                .id
+        };
+        assert_eq!(parent, builder_id.to_string());
+        assert_eq!(local_x, 10.0, "x becomes local to the parent");
+        assert_eq!((world_x, world_y), (110.0, 60.0), "world position is kept");
+        assert!(!cycle_ok, "parenting to a descendant must error");
+    }
+
+    #[test]
+    fn test_joints_from_lua() {
+        let mut session = setup("joints");
+        add_scripted_entity(
+            &session,
+            "builder",
+            r#"
+            function init(scene_id, entity_id)
+                local wheel = create_physical_entity(scene_id, "wheel", 0.0, 0.0, 0.0)
+                local axle = create_physical_entity(scene_id, "axle", 50.0, 0.0, 0.0)
+                add_entity_to_physics_engine(wheel)
+                add_entity_to_physics_engine(axle)
+                local s = script_state.state
+                s.joint = create_joint(wheel, axle, "revolute", { target_anchor = {-50.0, 0.0} })
+                set_joint_motor(s.joint, 2.0, 1000.0)
+                set_joint_limits(s.joint, -0.5, 0.5)
+                local rope = create_joint(axle, wheel, "rope", { length = 80.0 })
+                s.rope_motor_ok = pcall(set_joint_motor, rope, 1.0, 1.0)
+                s.removed = remove_joint(rope)
+                s.bad_type_ok = pcall(create_joint, wheel, axle, "glue")
+            end
+
+            function update(scene_id, entity_id) end
+            "#,
+        );
+
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+
+        let (joint, rope_motor_ok, removed, bad_type_ok): (String, bool, bool, bool) = session
+            .lua
+            .lua
+            .load(
+                "local s = script_state.state
+                return s.joint, s.rope_motor_ok, s.removed, s.bad_type_ok",
+            )
+            .eval()
+            .unwrap();
+        let joint = uuid::Uuid::parse_str(&joint).unwrap();
+        let physics = session.physics.borrow();
+        assert!(physics.has_joint(joint));
+        assert_eq!(physics.get_joint_data().len(), 1);
+        assert!(!rope_motor_ok, "rope joints have no motor");
+        assert!(removed);
+        assert!(!bad_type_ok, "unknown joint types must error");
+
+        // The joint is the first entity's joint_* attributes, motor and
+        // limits included, so loading the scene again rebuilds it
+        let manager = session.scene_manager.borrow();
+        let scene = manager.get_scene(session.scene_id).unwrap();
+        let wheel = scene.get_entity(joint).unwrap();
+        let axle = scene.find_entity_by_name("axle").unwrap();
+        let value = |name: &str| wheel.get_attribute_by_name(name).unwrap().value.clone();
+        assert_eq!(
+            value("joint_type"),
+            AttributeValue::String("revolute".into())
+        );
+        assert_eq!(value("joint_target"), AttributeValue::EntityRef(Some(axle)));
+        assert_eq!(
+            value("joint_target_anchor"),
+            AttributeValue::Vector2(-50.0, 0.0)
+        );
+        assert_eq!(value("joint_motor"), AttributeValue::Vector2(2.0, 1000.0));
+        assert_eq!(value("joint_limits"), AttributeValue::Vector2(-0.5, 0.5));
+        let axle = scene.get_entity(axle).unwrap();
+        assert!(
+            axle.get_attribute_by_name("joint_type").is_err(),
+            "removed joints stay removed"
+        );
+
+        let mut reloaded = PhysicsEngine::new();
+        reloaded.load_scene(scene);
+        assert!(reloaded.has_joint(joint));
+        assert_eq!(reloaded.get_joint_data().len(), 1);
+    }
+
+    #[test]
+    fn test_spatial_queries_from_lua() {
+        let mut session = setup("queries");
+        add_scripted_entity(
+            &session,
+            "spawner",
+            r#"
+            function init(scene_id, entity_id)
+                local s = script_state.state
+                s.shooter = create_physical_entity(scene_id, "shooter", 0.0, 0.0, 0.0)
+                s.target = create_physical_entity(scene_id, "target", 0.0, 100.0, 0.0)
+                add_entity_to_physics_engine(s.shooter)
+                add_entity_to_physics_engine(s.target)
+            end
+
+            function update(scene_id, entity_id) end
+            "#,
+        );
+        session.lua.run_scripts_for_scene(session.scene_id).unwrap();
+        // Queries see the world as of the last step
+        {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            session.physics.borrow_mut().step(scene);
+        }
+
+        let (hit_target, distance, normal_y, missed, under_point, bad_shape_ok): (
+            bool,
+            f32,
+            f32,
+            bool,
+            bool,
+            bool,
+        ) = session
+            .lua
+            .lua
+            .load(
+                r#"
+                local s = script_state.state
+                local hit = raycast(0.0, 0.0, 0.0, 1.0, 200.0, { exclude = s.shooter })
+                local missed = raycast(0.0, 0.0, 1.0, 0.0, 200.0, { exclude = { s.shooter } }) == nil
+                local under_point = intersect_point(0.0, 100.0)[1] == s.target
+                local bad_shape_ok = pcall(cast_shape, { side = 2.0 }, 0.0, 0.0, 0.0, 1.0, 10.0)
+                return hit.entity_id == s.target, hit.distance, hit.normal.y, missed,
+                    under_point, bad_shape_ok
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert!(hit_target);
+        assert!((distance - 99.5).abs() < 0.1, "distance was {}", distance);
+        assert!(normal_y < -0.99, "normal should face the shooter");
+        assert!(missed);
+        assert!(under_point);
+        assert!(!bad_shape_ok, "shapes need a radius or width and height");
+    }
+
+    #[test]
+    fn test_collision_layers_from_lua() {
+        let session = setup("collision_layers");
+        session.scene_manager.borrow_mut().collision_layers = vec![
+            "enemies".to_string(),
+            "walls".to_string(),
+            "bullets".to_string(),
+        ];
+        let (shot, bad_name_ok): (String, bool) = session
+            .lua
+            .lua
+            .load(
+                r#"
+                local scene_id = ...
+                local shot = create_physical_entity(scene_id, "shot", 0.0, 0.0, 0.0)
+                add_entity_to_physics_engine(shot)
+                set_collision_layers(shot, "bullets", { "enemies", "walls" })
+                local bad_name_ok = pcall(set_collision_layers, shot, "ghosts")
+                return shot, bad_name_ok
+                "#,
+            )
+            .call(session.scene_id.to_string())
+            .unwrap();
+        assert!(!bad_name_ok, "unknown layer names are errors");
+        let shot = uuid::Uuid::parse_str(&shot).unwrap();
+
+        // The change reaches the physics world like any attribute edit
+        {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            let entity = scene.get_entity(shot).unwrap();
+            assert_eq!(
+                entity
+                    .get_attribute_by_name("collision_layers")
+                    .unwrap()
+                    .value,
+                AttributeValue::Integer(0b100)
+            );
+            assert_eq!(
+                entity
+                    .get_attribute_by_name("collision_mask")
+                    .unwrap()
+                    .value,
+                AttributeValue::Integer(0b011)
+            );
+            let events = scene.drain_events();
+            let mut physics = session.physics.borrow_mut();
+            physics.apply_scene_events(&[&*scene], &events);
+            physics.step(scene);
+        }
+        let (as_bullet, as_wall, keeps_mask): (bool, bool, bool) = session
+            .lua
+            .lua
+            .load(
+                r#"
+                local scene_id, shot = ...
+                local as_bullet = #intersect_point(0.0, 0.0, { layers = "bullets" }) == 1
+                local as_wall = #intersect_point(0.0, 0.0, { layers = { "walls" } }) == 1
+                set_collision_layers(shot, 1)
+                return as_bullet, as_wall, get_attribute(scene_id, shot, "collision_mask") == 3
+                "#,
+            )
+            .call((session.scene_id.to_string(), shot.to_string()))
+            .unwrap();
+        assert!(as_bullet);
+        assert!(!as_wall);
+        assert!(keeps_mask, "leaving out the mask keeps it");
+    }
+
+    #[test]
+    fn test_move_character_from_lua() {
+        let session = setup("move_character");
+        let hero = {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            let hero = scene
+                .create_physical_entity("hero", (0.0, 0.0, 0.0), PhysicsProperties::default())
+                .unwrap();
+            scene
+                .get_entity_mut(hero)
+                .unwrap()
+                .create_attribute(
+                    "is_character",
+                    AttributeType::Boolean,
+                    AttributeValue::Boolean(true),
+                )
+                .unwrap();
+            session.physics.borrow_mut().load_scene(scene);
+            hero
+        };
+        let not_a_character_ok: bool = session
+            .lua
+            .lua
+            .load(
+                r#"
+                local hero = ...
+                move_character(hero, 3.0, 0.0)
+                move_character(hero, 2.0, 10.0)
+                return pcall(move_character, "00000000-0000-0000-0000-000000000000", 1.0, 0.0)
+                "#,
+            )
+            .call(hero.to_string())
+            .unwrap();
+        assert!(!not_a_character_ok);
+
+        // Moves add up until the step; nothing below, so not grounded
+        {
+            let mut manager = session.scene_manager.borrow_mut();
+            let scene = manager.get_scene_mut(session.scene_id).unwrap();
+            let updates = session.physics.borrow_mut().step(scene);
+            scene.update_entity_attributes(updates).unwrap();
+            let hero = scene.get_entity(hero).unwrap();
+            assert!((hero.get_x() - 5.0).abs() < 0.01);
+            assert!((hero.get_y() - 10.0).abs() < 0.01);
+        }
+        let grounded: bool = session
+            .lua
+            .lua
+            .load("return is_grounded(...)")
+            .call(hero.to_string())
+            .unwrap();
+        assert!(!grounded);
+    }
 }
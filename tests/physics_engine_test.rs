@@ -1,7 +1,32 @@
 #[cfg(test)]
 mod tests {
-    use rust_2d_game_engine::ecs::{PhysicsProperties, Scene};
-    use rust_2d_game_engine::physics_engine::PhysicsEngine;
+    use rust_2d_game_engine::ecs::{AttributeType, AttributeValue, PhysicsProperties, Scene};
+    use rust_2d_game_engine::physics_engine::{
+        auto_collider_cache_path, auto_collider_polygons, body_point_to_world,
+        generate_auto_collider, world_to_body_point, ContactEvent, JointKind, JointSpec,
+        PhysicsEngine, PhysicsMaterial, QueryOptions, QueryShape,
+    };
+    use uuid::Uuid;
+
+    // A physical entity with an explicit collider size
+    fn sized_box(
+        scene: &mut Scene,
+        name: &str,
+        (x, y): (f32, f32),
+        (width, height): (f32, f32),
+        props: PhysicsProperties,
+    ) -> Uuid {
+        let id = scene
+            .create_physical_entity(name, (x, y, 0.0), props)
+            .unwrap();
+        let entity = scene.get_entity_mut(id).unwrap();
+        for (attr, value) in [("collider_width", width), ("collider_height", height)] {
+            entity
+                .create_attribute(attr, AttributeType::Float, AttributeValue::Float(value))
+                .unwrap();
+        }
+        id
+    }
 
     #[test]
     fn test_initialization() {
@@ -259,4 +284,1349 @@ mod tests {
             heavy_y
         );
     }
+
+    #[test]
+    fn test_child_spawns_at_world_position() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let parent_id = scene.create_entity("parent").unwrap();
+        scene
+            .get_entity_mut(parent_id)
+            .unwrap()
+            .set_position(200.0, 100.0, 0.0)
+            .unwrap();
+
+        let props = PhysicsProperties {
+            is_movable: false,
+            affected_by_gravity: false,
+            has_collision: true,
+            ..Default::default()
+        };
+        let child_id = scene
+            .create_physical_entity("child", (200.0, 100.0, 0.0), props)
+            .unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        scene.get_entity_mut(child_id).unwrap().set_x(30.0).unwrap();
+
+        physics_engine.load_scene(&scene);
+
+        let updates = physics_engine.step(&mut scene);
+        scene.update_entity_attributes(updates).unwrap();
+
+        // Write-back keeps x/y local to the parent
+        let child = scene.get_entity(child_id).unwrap();
+        assert_eq!(child.get_x(), 30.0);
+        assert_eq!(child.get_y(), 0.0);
+
+        // Moving the parent drags the fixed child body along
+        scene
+            .get_entity_mut(parent_id)
+            .unwrap()
+            .set_position(0.0, 0.0, 0.0)
+            .unwrap();
+        let updates = physics_engine.step(&mut scene);
+        scene.update_entity_attributes(updates).unwrap();
+        let child = scene.get_entity(child_id).unwrap();
+        assert_eq!((child.get_x(), child.get_y()), (30.0, 0.0));
+        assert_eq!(scene.world_position(child_id).unwrap(), (30.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_child_write_back_uses_the_parents_current_rotation() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        // A spinning kinematic parent, turning about its center (110, 110)
+        let parent_id = sized_box(
+            &mut scene,
+            "parent",
+            (100.0, 100.0),
+            (20.0, 20.0),
+            PhysicsProperties {
+                can_rotate: true,
+                ..Default::default()
+            },
+        );
+        scene
+            .get_entity_mut(parent_id)
+            .unwrap()
+            .create_attribute(
+                "is_kinematic",
+                AttributeType::Boolean,
+                AttributeValue::Boolean(true),
+            )
+            .unwrap();
+        // A free-floating dynamic child 200px to the right of it
+        let child_id = scene
+            .create_physical_entity(
+                "child",
+                (0.0, 0.0, 0.0),
+                PhysicsProperties {
+                    is_movable: true,
+                    has_collision: false,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        scene
+            .get_entity_mut(child_id)
+            .unwrap()
+            .set_x(200.0)
+            .unwrap();
+
+        let start = scene.world_position(child_id).unwrap();
+
+        physics_engine.load_scene(&scene);
+        physics_engine.set_angular_velocity(&parent_id, std::f32::consts::FRAC_PI_2);
+        for _ in 0..30 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        // The parent turned ~45°; the child didn't move, so its local x/y
+        // must have been worked out against the turned parent
+        let rotation = scene.get_entity(parent_id).unwrap();
+        let rotation = match rotation.get_attribute_by_name("rotation").unwrap().value {
+            AttributeValue::Float(rotation) => rotation,
+            _ => panic!("rotation should be a Float"),
+        };
+        assert!(
+            (rotation - 45.0).abs() < 1.0,
+            "parent rotation: {}",
+            rotation
+        );
+        let (x, y, _) = scene.world_position(child_id).unwrap();
+        assert!(
+            (x - start.0).abs() < 0.01 && (y - start.1).abs() < 0.01,
+            "child should stay at {:?}: ({}, {})",
+            start,
+            x,
+            y
+        );
+    }
+
+    #[test]
+    fn test_disabled_entities_leave_and_rejoin_the_world() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let props = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            has_collision: true,
+            ..Default::default()
+        };
+        let parent_id = scene.create_entity("parent").unwrap();
+        let body_id = scene
+            .create_physical_entity("body", (0.0, 0.0, 0.0), props)
+            .unwrap();
+        scene.set_parent(body_id, Some(parent_id)).unwrap();
+
+        // Disabled before loading: never enters the world
+        scene.get_entity_mut(parent_id).unwrap().set_enabled(false);
+        physics_engine.load_scene(&scene);
+        assert!(!scene.is_active(body_id));
+        assert!(!physics_engine.has_rigid_body(&body_id));
+
+        // Disabled entities don't fall
+        let updates = physics_engine.step(&mut scene);
+        scene.update_entity_attributes(updates).unwrap();
+        assert_eq!(scene.get_entity(body_id).unwrap().get_y(), 0.0);
+
+        // Re-enabling the parent rebuilds the body on the next step
+        scene.get_entity_mut(parent_id).unwrap().set_enabled(true);
+        for _ in 0..10 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        assert!(physics_engine.has_rigid_body(&body_id));
+        assert!(scene.get_entity(body_id).unwrap().get_y() > 0.0);
+
+        // Disabling again removes the body without leaking it
+        scene.get_entity_mut(body_id).unwrap().set_enabled(false);
+        physics_engine.step(&mut scene);
+        assert!(!physics_engine.has_rigid_body(&body_id));
+        assert_eq!(physics_engine.rigid_body_count(), 0);
+    }
+
+    #[test]
+    fn test_declared_and_scripted_joints() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+
+        let pivot = scene
+            .create_physical_entity("pivot", (0.0, 0.0, 0.0), PhysicsProperties::default())
+            .unwrap();
+        let falling = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            ..Default::default()
+        };
+        let bob = scene
+            .create_physical_entity("bob", (100.0, 0.0, 0.0), falling)
+            .unwrap();
+        let sled = scene
+            .create_physical_entity(
+                "sled",
+                (0.0, 200.0, 0.0),
+                PhysicsProperties {
+                    is_movable: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // The bob hangs from the pivot on a rope as long as their distance
+        let entity = scene.get_entity_mut(bob).unwrap();
+        entity
+            .create_attribute(
+                "joint_type",
+                AttributeType::String,
+                AttributeValue::String("rope".to_string()),
+            )
+            .unwrap();
+        entity
+            .create_attribute(
+                "joint_target",
+                AttributeType::EntityRef,
+                AttributeValue::EntityRef(Some(pivot)),
+            )
+            .unwrap();
+        scene.drain_events();
+        physics_engine.load_scene(&scene);
+        assert!(physics_engine.has_joint(bob));
+        assert_eq!(physics_engine.get_joint_data().len(), 1);
+
+        // A scripted slider driven by its motor
+        let mut spec = JointSpec::new(JointKind::Prismatic);
+        spec.target_anchor = (0.0, 200.0);
+        let slider = physics_engine.create_joint(sled, pivot, spec).unwrap();
+        physics_engine.set_joint_motor(slider, 60.0, 1.0e6).unwrap();
+        assert!(physics_engine.set_joint_motor(bob, 1.0, 1.0).is_err());
+
+        for _ in 0..120 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        // Swung down and in, rather than falling 100 px straight down
+        let bob_entity = scene.get_entity(bob).unwrap();
+        let (x, y) = (bob_entity.get_x(), bob_entity.get_y());
+        assert!(y > 20.0 && x < 95.0, "bob at ({}, {})", x, y);
+        assert!((x * x + y * y).sqrt() < 101.0, "bob at ({}, {})", x, y);
+        let sled_entity = scene.get_entity(sled).unwrap();
+        assert!(
+            sled_entity.get_x() > 50.0,
+            "sled at {}",
+            sled_entity.get_x()
+        );
+        assert!((sled_entity.get_y() - 200.0).abs() < 0.5);
+
+        // Rebuilt bodies keep their joints; removing the attribute drops one
+        scene.get_entity_mut(pivot).unwrap().set_enabled(false);
+        physics_engine.step(&mut scene);
+        assert!(physics_engine.get_joint_data().is_empty());
+        scene.get_entity_mut(pivot).unwrap().set_enabled(true);
+        physics_engine.step(&mut scene);
+        assert_eq!(physics_engine.get_joint_data().len(), 2);
+
+        let entity = scene.get_entity_mut(bob).unwrap();
+        let joint_type = entity.get_attribute_by_name("joint_type").unwrap().id;
+        entity.delete_attribute(joint_type).unwrap();
+        let events = scene.drain_events();
+        physics_engine.apply_scene_events(&[&scene], &events);
+        assert!(!physics_engine.has_joint(bob));
+        assert!(physics_engine.remove_joint(slider));
+        assert!(physics_engine.get_joint_data().is_empty());
+    }
+
+    #[test]
+    fn test_joint_anchors_scale_with_their_entity() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let falling = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            can_rotate: true,
+            ..Default::default()
+        };
+        let hook = sized_box(
+            &mut scene,
+            "hook",
+            (0.0, 0.0),
+            (20.0, 20.0),
+            Default::default(),
+        );
+        let plank = sized_box(&mut scene, "plank", (100.0, 0.0), (40.0, 10.0), falling);
+        scene
+            .get_entity_mut(plank)
+            .unwrap()
+            .create_attribute(
+                "scale",
+                AttributeType::Vector2,
+                AttributeValue::Vector2(2.0, 2.0),
+            )
+            .unwrap();
+
+        // (10, 5) on the plank drawn twice as big is (120, 10) in the world,
+        // where the hook's anchor is; the plank swings about that point
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        let mut spec = JointSpec::new(JointKind::Revolute);
+        spec.anchor = (10.0, 5.0);
+        spec.target_anchor = (120.0, 10.0);
+        physics_engine.create_joint(plank, hook, spec).unwrap();
+        for _ in 0..60 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        let (anchor, target_anchor, _) = physics_engine.get_joint_data().remove(0);
+        for (x, y) in [anchor, target_anchor] {
+            assert!(
+                (x - 120.0).abs() < 0.5 && (y - 10.0).abs() < 0.5,
+                "anchor at ({}, {})",
+                x,
+                y
+            );
+        }
+        let rotation = scene
+            .get_entity(plank)
+            .unwrap()
+            .get_attribute_by_name("rotation")
+            .unwrap()
+            .value
+            .clone();
+        assert!(
+            matches!(rotation, AttributeValue::Float(degrees) if degrees > 20.0),
+            "plank turned by {:?}",
+            rotation
+        );
+    }
+
+    #[test]
+    fn test_raycasts_shape_casts_and_area_queries() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        let mut boxed = |name: &str, position, size| {
+            sized_box(
+                &mut scene,
+                name,
+                position,
+                size,
+                PhysicsProperties::default(),
+            )
+        };
+        // Ground spans x 0..200, y 100..120; the player x 50..70, y 0..20
+        let ground = boxed("ground", (0.0, 100.0), (200.0, 20.0));
+        let player = boxed("player", (50.0, 0.0), (20.0, 20.0));
+        physics_engine.load_scene(&scene);
+        physics_engine.step(&mut scene);
+
+        let everything = QueryOptions::default();
+        let hit = physics_engine
+            .raycast((100.0, 50.0), (0.0, 1.0), 200.0, &everything)
+            .unwrap();
+        assert_eq!(hit.entity, ground);
+        assert!((hit.distance - 50.0).abs() < 0.01);
+        assert!((hit.point.1 - 100.0).abs() < 0.01);
+        assert_eq!(hit.normal, (0.0, -1.0));
+        assert!(physics_engine
+            .raycast((100.0, 50.0), (0.0, 1.0), 40.0, &everything)
+            .is_none());
+
+        // "Is there ground below me", ignoring myself
+        let not_player = QueryOptions {
+            exclude: vec![player],
+            ..Default::default()
+        };
+        let hit = physics_engine
+            .raycast((60.0, 10.0), (0.0, 1.0), 200.0, &not_player)
+            .unwrap();
+        assert_eq!(hit.entity, ground);
+        assert!((hit.distance - 90.0).abs() < 0.01);
+
+        let hit = physics_engine
+            .cast_shape(
+                QueryShape::Circle(10.0),
+                (100.0, 50.0),
+                (0.0, 1.0),
+                200.0,
+                &everything,
+            )
+            .unwrap();
+        assert_eq!(hit.entity, ground);
+        assert!((hit.distance - 40.0).abs() < 0.01);
+
+        assert_eq!(
+            physics_engine.intersect_point((60.0, 10.0), &everything),
+            vec![player]
+        );
+        assert!(physics_engine
+            .intersect_point((60.0, 50.0), &everything)
+            .is_empty());
+        let mut both = physics_engine.intersect_aabb((0.0, 0.0), (200.0, 200.0), &everything);
+        both.sort();
+        let mut expected = vec![ground, player];
+        expected.sort();
+        assert_eq!(both, expected);
+        let no_layers = QueryOptions {
+            layers: Some(0),
+            ..Default::default()
+        };
+        assert!(physics_engine
+            .intersect_aabb((0.0, 0.0), (200.0, 200.0), &no_layers)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sensor_triggers_and_contact_events() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        let mut boxed = |name: &str, position, size| {
+            sized_box(
+                &mut scene,
+                name,
+                position,
+                size,
+                PhysicsProperties::default(),
+            )
+        };
+        // A kinematic mover passes through the zone (a sensor); a ball
+        // falls onto the ground next to it
+        let mover = boxed("mover", (40.0, 0.0), (20.0, 30.0));
+        let zone = boxed("zone", (0.0, 100.0), (100.0, 100.0));
+        let ball = boxed("ball", (240.0, 200.0), (20.0, 30.0));
+        let ground = boxed("ground", (200.0, 300.0), (100.0, 20.0));
+        let flags = [
+            (mover, "is_kinematic"),
+            (zone, "is_sensor"),
+            (ball, "is_movable"),
+            (ball, "has_gravity"),
+        ];
+        for (id, flag) in flags {
+            let entity = scene.get_entity_mut(id).unwrap();
+            let attr = entity.get_attribute_by_name(flag).map(|attr| attr.id);
+            match attr {
+                Ok(attr) => entity
+                    .modify_attribute(attr, None, None, Some(AttributeValue::Boolean(true)))
+                    .unwrap(),
+                Err(_) => entity
+                    .create_attribute(flag, AttributeType::Boolean, AttributeValue::Boolean(true))
+                    .map(|_| ())
+                    .unwrap(),
+            }
+        }
+        scene.drain_events();
+        physics_engine.load_scene(&scene);
+        physics_engine.set_velocity(&mover, rapier2d::prelude::Vector::new(0.0, 150.0));
+
+        let mut events = Vec::new();
+        for frame in 0..120 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+            events.extend(physics_engine.drain_contact_events());
+
+            // Rebuilding the mover inside the zone is no change
+            if frame == 50 {
+                assert_eq!(
+                    events,
+                    vec![ContactEvent::TriggerEntered(zone, mover)],
+                    "the mover is inside the zone"
+                );
+                let entity = scene.get_entity_mut(mover).unwrap();
+                let attr = entity.get_attribute_by_name("collider_width").unwrap().id;
+                entity
+                    .modify_attribute(attr, None, None, Some(AttributeValue::Float(24.0)))
+                    .unwrap();
+                let scene_events = scene.drain_events();
+                physics_engine.apply_scene_events(&[&scene], &scene_events);
+            }
+            if events
+                .iter()
+                .any(|event| matches!(event, ContactEvent::CollisionStarted(..)))
+            {
+                break;
+            }
+        }
+        let landed = events.pop().unwrap();
+        let (a, b) = landed.entities();
+        assert!(matches!(landed, ContactEvent::CollisionStarted(..)));
+        assert!([a, b].contains(&ball) && [a, b].contains(&ground));
+        assert_eq!(
+            events,
+            vec![
+                ContactEvent::TriggerEntered(zone, mover),
+                ContactEvent::TriggerExited(zone, mover)
+            ]
+        );
+
+        // Removing a body ends its contacts
+        physics_engine.remove_entity(ball);
+        physics_engine.step(&mut scene);
+        let events = physics_engine.drain_contact_events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ContactEvent::CollisionStopped(..)));
+        assert!(events[0].entities() == (a, b));
+    }
+
+    #[test]
+    fn test_collision_layers_and_masks() {
+        const ENEMIES: u32 = 1 << 0;
+        const WALLS: u32 = 1 << 1;
+        const BULLETS: u32 = 1 << 2;
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        let falling = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            ..Default::default()
+        };
+        let mut boxed = |name: &str, position, properties, (layers, mask)| {
+            let id = sized_box(&mut scene, name, position, (20.0, 20.0), properties);
+            let entity = scene.get_entity_mut(id).unwrap();
+            entity.set_collision_layers(layers, mask).unwrap();
+            id
+        };
+        // Enemies stand on walls but not on each other; bullets only hit
+        // enemies, so this one falls through the floor
+        let floor = boxed(
+            "floor",
+            (0.0, 100.0),
+            PhysicsProperties::default(),
+            (WALLS, u32::MAX),
+        );
+        let lower = boxed("lower", (0.0, 40.0), falling.clone(), (ENEMIES, WALLS));
+        let upper = boxed("upper", (0.0, 0.0), falling.clone(), (ENEMIES, WALLS));
+        let bullet = boxed("bullet", (60.0, 40.0), falling, (BULLETS, ENEMIES));
+        physics_engine.load_scene(&scene);
+
+        for _ in 0..120 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+        let y = |id| scene.get_entity(id).unwrap().get_y();
+        assert!((y(lower) - 80.0).abs() < 1.0, "lower enemy y: {}", y(lower));
+        assert!((y(upper) - 80.0).abs() < 1.0, "upper enemy y: {}", y(upper));
+        assert!(y(bullet) > 120.0, "bullet y: {}", y(bullet));
+
+        // Queries filter on the layers colliders are in
+        let walls_only = QueryOptions {
+            layers: Some(WALLS),
+            ..Default::default()
+        };
+        let hit = physics_engine
+            .raycast((10.0, 0.0), (0.0, 1.0), 200.0, &walls_only)
+            .unwrap();
+        assert_eq!(hit.entity, floor);
+        let enemies = QueryOptions {
+            layers: Some(ENEMIES),
+            ..Default::default()
+        };
+        let mut found = physics_engine.intersect_aabb((0.0, 0.0), (100.0, 120.0), &enemies);
+        found.sort();
+        let mut expected = vec![lower, upper];
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_capsule_polygon_polyline_and_compound_colliders() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        let points = |points: &[(f32, f32)]| {
+            AttributeValue::List(
+                points
+                    .iter()
+                    .map(|&(x, y)| AttributeValue::Vector2(x, y))
+                    .collect(),
+            )
+        };
+        let part = |shape: &str, (x, y): (f32, f32), (width, height): (f32, f32)| {
+            let mut part = indexmap::IndexMap::new();
+            part.insert("shape".to_string(), AttributeValue::String(shape.into()));
+            for (name, value) in [("x", x), ("y", y), ("width", width), ("height", height)] {
+                part.insert(name.to_string(), AttributeValue::Float(value));
+            }
+            AttributeValue::Map(part)
+        };
+        let mut add = |name: &str,
+                       position: (f32, f32),
+                       properties,
+                       attributes: Vec<(&str, AttributeValue)>| {
+            let id = scene
+                .create_physical_entity(name, (position.0, position.1, 0.0), properties)
+                .unwrap();
+            let entity = scene.get_entity_mut(id).unwrap();
+            for (attr, value) in attributes {
+                let data_type = match value {
+                    AttributeValue::String(_) => AttributeType::String,
+                    AttributeValue::List(_) => AttributeType::List,
+                    _ => AttributeType::Float,
+                };
+                entity.create_attribute(attr, data_type, value).unwrap();
+            }
+            id
+        };
+        let shape = |kind: &str| ("collider_shape", AttributeValue::String(kind.into()));
+
+        // A cart (a box on a wheel) lands on the flat start of a polyline
+        // terrain
+        let terrain = add(
+            "terrain",
+            (0.0, 100.0),
+            PhysicsProperties::default(),
+            vec![
+                shape("polyline"),
+                (
+                    "collider_points",
+                    points(&[(0.0, 0.0), (100.0, 0.0), (200.0, 50.0)]),
+                ),
+            ],
+        );
+        let cart = add(
+            "cart",
+            (20.0, 0.0),
+            PhysicsProperties {
+                is_movable: true,
+                affected_by_gravity: true,
+                ..Default::default()
+            },
+            vec![(
+                "collider_shapes",
+                AttributeValue::List(vec![
+                    part("rectangle", (0.0, 0.0), (20.0, 10.0)),
+                    part("circle", (5.0, 10.0), (10.0, 10.0)),
+                ]),
+            )],
+        );
+        // Points may also be [x, y] lists and {x, y} maps, as edited JSON
+        // and Lua tables give them
+        let gem = add(
+            "gem",
+            (300.0, 0.0),
+            PhysicsProperties::default(),
+            vec![
+                shape("polygon"),
+                (
+                    "collider_points",
+                    AttributeValue::List(vec![
+                        AttributeValue::Vector2(0.0, 0.0),
+                        AttributeValue::List(vec![
+                            AttributeValue::Integer(10),
+                            AttributeValue::Integer(-10),
+                        ]),
+                        AttributeValue::Map(indexmap::IndexMap::from([
+                            ("x".to_string(), AttributeValue::Float(20.0)),
+                            ("y".to_string(), AttributeValue::Integer(0)),
+                        ])),
+                        AttributeValue::Vector2(10.0, 10.0),
+                    ]),
+                ),
+            ],
+        );
+        let pill = add(
+            "pill",
+            (400.0, 0.0),
+            PhysicsProperties::default(),
+            vec![
+                shape("capsule"),
+                ("collider_width", AttributeValue::Float(10.0)),
+                ("collider_height", AttributeValue::Float(30.0)),
+            ],
+        );
+        physics_engine.load_scene(&scene);
+        for _ in 0..120 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        // The wheel's bottom rests on the flat part of the terrain
+        let cart_y = scene.get_entity(cart).unwrap().get_y();
+        assert!((cart_y - 80.0).abs() < 1.0, "cart y: {}", cart_y);
+        let options = QueryOptions::default();
+        let hit = physics_engine
+            .raycast((310.0, -50.0), (0.0, 1.0), 100.0, &options)
+            .unwrap();
+        assert_eq!(hit.entity, gem);
+        assert!((hit.distance - 40.0).abs() < 0.01);
+        let hit = physics_engine
+            .raycast((150.0, 0.0), (0.0, 1.0), 200.0, &options)
+            .unwrap();
+        assert_eq!(hit.entity, terrain);
+        assert!((hit.distance - 125.0).abs() < 0.01);
+        // Rounded ends: the capsule's top corner is empty
+        assert_eq!(
+            physics_engine.intersect_point((405.0, 1.0), &options),
+            vec![pill]
+        );
+        assert!(physics_engine
+            .intersect_point((401.0, 1.0), &options)
+            .is_empty());
+
+        // Debug outlines: one per compound part, outlines in world space
+        let mut names: Vec<String> = physics_engine
+            .get_collider_data()
+            .into_iter()
+            .map(|(_, _, name, _)| name)
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["Capsule", "Circle", "Polygon", "Polyline", "Rectangle"]
+        );
+        let (position, size, _, outline) = physics_engine
+            .get_collider_data()
+            .into_iter()
+            .find(|(_, _, name, _)| name == "Polyline")
+            .unwrap();
+        assert_eq!(outline, vec![(0.0, 100.0), (100.0, 100.0), (200.0, 150.0)]);
+        assert_eq!((position, size), ((100.0, 125.0), (200.0, 50.0)));
+    }
+
+    #[test]
+    fn test_auto_collider_traced_from_sprite_alpha() {
+        let dir =
+            std::env::temp_dir().join(format!("rust2d_auto_collider_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // An L-shaped sprite in a 64x64 image with transparent padding
+        let image_path = dir.join("boot.png");
+        image::RgbaImage::from_fn(64, 64, |x, y| {
+            let upright = (16..24).contains(&x) && (16..48).contains(&y);
+            let foot = (16..48).contains(&x) && (40..48).contains(&y);
+            image::Rgba([255, 0, 0, if upright || foot { 255 } else { 0 }])
+        })
+        .save(&image_path)
+        .unwrap();
+
+        let mut scene = Scene::new("test_scene").unwrap();
+        let boot = scene
+            .create_physical_entity("boot", (0.0, 0.0, 0.0), PhysicsProperties::default())
+            .unwrap();
+        let entity = scene.get_entity_mut(boot).unwrap();
+        entity.add_image(image_path.clone()).unwrap();
+        entity
+            .create_attribute(
+                "collider_shape",
+                AttributeType::String,
+                AttributeValue::String("auto".into()),
+            )
+            .unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        physics_engine.step(&mut scene);
+
+        // Traced once and cached next to the image
+        let cache_path = auto_collider_cache_path(&image_path);
+        assert_eq!(cache_path, dir.join("boot.png.collider.json"));
+        assert!(cache_path.exists());
+        assert_eq!(
+            auto_collider_polygons(&image_path).unwrap(),
+            generate_auto_collider(&image_path).unwrap()
+        );
+
+        // Rays stop at the opaque pixels, not the image bounds
+        let options = QueryOptions::default();
+        for (x, top) in [(20.0, 16.0), (40.0, 40.0)] {
+            let hit = physics_engine
+                .raycast((x, -50.0), (0.0, 1.0), 200.0, &options)
+                .unwrap();
+            assert_eq!(hit.entity, boot);
+            assert!((hit.point.1 - top).abs() < 1.5, "x {}: {:?}", x, hit);
+        }
+        assert!(physics_engine
+            .intersect_point((40.0, 20.0), &options)
+            .is_empty());
+        assert!(physics_engine
+            .raycast((60.0, -50.0), (0.0, 1.0), 200.0, &options)
+            .is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_character_controller_lands_walks_and_climbs_steps() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut add = |name: &str, position, size, is_character| {
+            let props = PhysicsProperties::default();
+            let id = sized_box(&mut scene, name, position, size, props);
+            let entity = scene.get_entity_mut(id).unwrap();
+            entity
+                .create_attribute(
+                    "collider_shape",
+                    AttributeType::String,
+                    AttributeValue::String("rectangle".into()),
+                )
+                .unwrap();
+            if is_character {
+                entity
+                    .create_attribute(
+                        "is_character",
+                        AttributeType::Boolean,
+                        AttributeValue::Boolean(true),
+                    )
+                    .unwrap();
+                entity
+                    .create_attribute(
+                        "character_step_height",
+                        AttributeType::Float,
+                        AttributeValue::Float(12.0),
+                    )
+                    .unwrap();
+            }
+            id
+        };
+        let floor = add("floor", (0.0, 100.0), (400.0, 20.0), false);
+        add("step", (200.0, 90.0), (40.0, 10.0), false);
+        add("wall", (300.0, 0.0), (20.0, 100.0), false);
+        let hero = add("hero", (50.0, 40.0), (20.0, 40.0), true);
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        assert!(physics_engine.is_character(&hero));
+        assert!(physics_engine.move_character(&floor, 1.0, 0.0).is_err());
+
+        let mut run = |physics_engine: &mut PhysicsEngine, frames, (dx, dy)| {
+            for _ in 0..frames {
+                physics_engine.move_character(&hero, dx, dy).unwrap();
+                let updates = physics_engine.step(&mut scene);
+                scene.update_entity_attributes(updates).unwrap();
+            }
+            let hero = scene.get_entity(hero).unwrap();
+            (hero.get_x(), hero.get_y())
+        };
+
+        // Falls onto the floor and stays grounded there
+        assert!(!physics_engine.is_grounded(&hero));
+        let (_, y) = run(&mut physics_engine, 30, (0.0, 4.0));
+        assert!((y - 60.0).abs() < 1.0, "landed at y {}", y);
+        assert!(physics_engine.is_grounded(&hero));
+
+        // Walks up onto the 10px step without losing speed
+        let (x, y) = run(&mut physics_engine, 50, (3.0, 4.0));
+        assert!((x - 200.0).abs() < 0.1, "walked to x {}", x);
+        assert!((y - 50.0).abs() < 1.0, "on the step at y {}", y);
+        assert!(physics_engine.is_grounded(&hero));
+
+        // Down the other side and on to the wall, which stops it
+        let (x, y) = run(&mut physics_engine, 40, (3.0, 4.0));
+        assert!((x - 280.0).abs() < 1.0, "stopped at x {}", x);
+        assert!((y - 60.0).abs() < 1.0, "back on the floor at y {}", y);
+        assert!(physics_engine.is_grounded(&hero));
+    }
+
+    #[test]
+    fn test_body_points_map_onto_the_collider() {
+        // A 20x10 triangle stretched to twice its width, under a parent
+        // turned a quarter turn
+        let mut scene = Scene::new("test_scene").unwrap();
+        let arm = scene.create_entity("arm").unwrap();
+        scene
+            .get_entity_mut(arm)
+            .unwrap()
+            .create_attribute(
+                "rotation",
+                AttributeType::Float,
+                AttributeValue::Float(90.0),
+            )
+            .unwrap();
+        let corners = [(0.0, 0.0), (20.0, 0.0), (20.0, 10.0)];
+        let wedge = sized_box(
+            &mut scene,
+            "wedge",
+            (30.0, 0.0),
+            (20.0, 10.0),
+            Default::default(),
+        );
+        scene.set_parent(wedge, Some(arm)).unwrap();
+        let entity = scene.get_entity_mut(wedge).unwrap();
+        entity
+            .create_attribute(
+                "scale",
+                AttributeType::Vector2,
+                AttributeValue::Vector2(2.0, 1.0),
+            )
+            .unwrap();
+        entity
+            .create_attribute(
+                "collider_shape",
+                AttributeType::String,
+                AttributeValue::String("polygon".into()),
+            )
+            .unwrap();
+        entity
+            .create_attribute(
+                "collider_points",
+                AttributeType::List,
+                AttributeValue::List(
+                    corners
+                        .iter()
+                        .map(|&(x, y)| AttributeValue::Vector2(x, y))
+                        .collect(),
+                ),
+            )
+            .unwrap();
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        let (_, _, _, outline) = physics_engine
+            .get_collider_data()
+            .into_iter()
+            .find(|(_, _, name, _)| name == "Polygon")
+            .unwrap();
+
+        let entity = scene.get_entity(wedge).unwrap();
+        let world = scene.world_transform(wedge).unwrap();
+        for corner in corners {
+            let (x, y) = body_point_to_world(entity, &world, corner);
+            assert!(
+                outline
+                    .iter()
+                    .any(|(ox, oy)| (ox - x).abs() < 0.01 && (oy - y).abs() < 0.01),
+                "{:?} at ({}, {}), not on {:?}",
+                corner,
+                x,
+                y,
+                outline
+            );
+            let (back_x, back_y) = world_to_body_point(entity, &world, (x, y));
+            assert!((back_x - corner.0).abs() < 0.01 && (back_y - corner.1).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_sprite_scale_and_rotation_carry_into_bodies() {
+        let dir = std::env::temp_dir().join(format!("rust2d_transform_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("plank.png");
+        image::RgbaImage::from_pixel(20, 10, image::Rgba([255, 255, 255, 255]))
+            .save(&image_path)
+            .unwrap();
+
+        let mut scene = Scene::new("test_scene").unwrap();
+        let set_rotation = |scene: &mut Scene, id, degrees: f32| {
+            let entity = scene.get_entity_mut(id).unwrap();
+            let attr_id = entity.get_attribute_by_name("rotation").unwrap().id;
+            entity
+                .modify_attribute(attr_id, None, None, Some(AttributeValue::Float(degrees)))
+                .unwrap();
+        };
+
+        // A 20x10 sprite drawn twice as big and turned a quarter turn about
+        // its center (120, 10): upright, over x 110..130 and y -10..30
+        let plank = scene
+            .create_physical_entity("plank", (100.0, 0.0, 0.0), PhysicsProperties::default())
+            .unwrap();
+        let entity = scene.get_entity_mut(plank).unwrap();
+        entity.add_image(image_path.clone()).unwrap();
+        entity
+            .create_attribute(
+                "scale",
+                AttributeType::Vector2,
+                AttributeValue::Vector2(2.0, 2.0),
+            )
+            .unwrap();
+        entity
+            .create_attribute(
+                "collider_shape",
+                AttributeType::String,
+                AttributeValue::String("rectangle".into()),
+            )
+            .unwrap();
+        set_rotation(&mut scene, plank, 90.0);
+
+        // A spinning 20x20 box without a sprite turns about its center too
+        let spinner = scene
+            .create_physical_entity(
+                "spinner",
+                (0.0, 200.0, 0.0),
+                PhysicsProperties {
+                    is_movable: true,
+                    can_rotate: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let entity = scene.get_entity_mut(spinner).unwrap();
+        for name in ["collider_width", "collider_height"] {
+            entity
+                .create_attribute(name, AttributeType::Float, AttributeValue::Float(20.0))
+                .unwrap();
+        }
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        physics_engine.set_angular_velocity(&spinner, 1.0);
+        for _ in 0..30 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        let options = QueryOptions::default();
+        let hit = physics_engine
+            .raycast((112.0, -50.0), (0.0, 1.0), 200.0, &options)
+            .unwrap();
+        assert_eq!(hit.entity, plank);
+        assert!((hit.point.1 + 10.0).abs() < 0.1, "{:?}", hit);
+        assert!(physics_engine
+            .raycast((135.0, -50.0), (0.0, 1.0), 200.0, &options)
+            .is_none());
+
+        // Written back where it started, turned as authored
+        let rotation = |scene: &Scene, id| match scene
+            .get_entity(id)
+            .unwrap()
+            .get_attribute_by_name("rotation")
+            .unwrap()
+            .value
+        {
+            AttributeValue::Float(degrees) => degrees,
+            _ => panic!("rotation should be a float"),
+        };
+        let entity = scene.get_entity(plank).unwrap();
+        assert!((entity.get_x() - 100.0).abs() < 0.01 && entity.get_y().abs() < 0.01);
+        assert!((rotation(&scene, plank) - 90.0).abs() < 0.01);
+
+        // Half a second at 1 rad/s, about the same center
+        let entity = scene.get_entity(spinner).unwrap();
+        let expected = 0.5_f32.to_degrees();
+        assert!((rotation(&scene, spinner) - expected).abs() < 1.0);
+        assert!((entity.get_x() - 0.0).abs() < 0.1 && (entity.get_y() - 200.0).abs() < 0.1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_physics_material_is_shared_and_reloaded_when_edited() {
+        let dir = std::env::temp_dir().join(format!("rust2d_material_{}", uuid::Uuid::new_v4()));
+        let ice = dir.join("ice.material");
+        PhysicsMaterial::default().save(&ice).unwrap();
+
+        // Two sliding blocks on ice, and one without a material
+        let mut scene = Scene::new("test_scene").unwrap();
+        let blocks: Vec<_> = (0..3)
+            .map(|i| {
+                let id = scene
+                    .create_physical_entity(
+                        &format!("block_{}", i),
+                        (0.0, i as f32 * 100.0, 0.0),
+                        PhysicsProperties {
+                            is_movable: true,
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap();
+                if i < 2 {
+                    scene
+                        .get_entity_mut(id)
+                        .unwrap()
+                        .create_attribute(
+                            "physics_material",
+                            AttributeType::AssetRef,
+                            AttributeValue::AssetRef(ice.clone()),
+                        )
+                        .unwrap();
+                }
+                id
+            })
+            .collect();
+
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.load_scene(&scene);
+        let slide = |physics_engine: &mut PhysicsEngine, scene: &mut Scene| {
+            for id in &blocks {
+                physics_engine.set_velocity(id, rapier2d::prelude::Vector::new(100.0, 0.0));
+            }
+            for _ in 0..30 {
+                physics_engine.step(scene);
+            }
+            blocks
+                .iter()
+                .map(|id| physics_engine.get_velocity(id).unwrap().x)
+                .collect::<Vec<_>>()
+        };
+        let speeds = slide(&mut physics_engine, &mut scene);
+        assert!(
+            speeds.iter().all(|speed| (speed - 100.0).abs() < 0.1),
+            "{:?}",
+            speeds
+        );
+
+        // Adding drag to the file slows down every block using it
+        PhysicsMaterial {
+            linear_damping: 5.0,
+            ..Default::default()
+        }
+        .save(&ice)
+        .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&ice)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(1))
+            .unwrap();
+        let speeds = slide(&mut physics_engine, &mut scene);
+        assert!(speeds[0] < 50.0 && speeds[1] < 50.0, "{:?}", speeds);
+        assert!((speeds[2] - 100.0).abs() < 0.1, "{:?}", speeds);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gravity_field_pulls_bodies_within_its_radius() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_gravity(0.0, 0.0);
+
+        let falling = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            has_collision: true,
+            ..Default::default()
+        };
+        // The source is placed by x/y only; its `position` stays at (0, 0)
+        let well = scene
+            .create_physical_entity(
+                "well",
+                (500.0, 0.0, 0.0),
+                PhysicsProperties {
+                    creates_gravity: true,
+                    has_collision: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let near = scene
+            .create_physical_entity("near", (300.0, 0.0, 0.0), falling.clone())
+            .unwrap();
+        let far = scene
+            .create_physical_entity("far", (-400.0, 0.0, 0.0), falling.clone())
+            .unwrap();
+        let floating = scene
+            .create_physical_entity(
+                "floating",
+                (400.0, 0.0, 0.0),
+                PhysicsProperties {
+                    affected_by_gravity: false,
+                    ..falling
+                },
+            )
+            .unwrap();
+        let settings = [
+            ("gravity_radius", AttributeValue::Float(300.0)),
+            ("gravity_strength", AttributeValue::Float(100.0)),
+            ("gravity_falloff", AttributeValue::String("constant".into())),
+        ];
+        for (name, value) in settings {
+            let attribute_type = match value {
+                AttributeValue::Float(_) => AttributeType::Float,
+                _ => AttributeType::String,
+            };
+            scene
+                .get_entity_mut(well)
+                .unwrap()
+                .create_attribute(name, attribute_type, value)
+                .unwrap();
+        }
+        physics_engine.load_scene(&scene);
+
+        // One step for the bodies to be found, then one second of pull
+        for _ in 0..61 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        // Constant 100px/s² toward the well for a second: ~50px
+        let near_x = scene.get_entity(near).unwrap().get_x();
+        assert!(
+            (340.0..360.0).contains(&near_x),
+            "should be pulled toward the well: {}",
+            near_x
+        );
+        let far_x = scene.get_entity(far).unwrap().get_x();
+        assert!((far_x + 400.0).abs() < 0.01, "out of reach: {}", far_x);
+        let floating_x = scene.get_entity(floating).unwrap().get_x();
+        assert!(
+            (floating_x - 400.0).abs() < 0.01,
+            "without has_gravity: {}",
+            floating_x
+        );
+        assert_eq!(scene.get_entity(well).unwrap().get_x(), 500.0);
+    }
+
+    #[test]
+    fn test_directional_gravity_zone_falls_off_linearly() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_gravity(0.0, 0.0);
+
+        let zone = scene
+            .create_physical_entity(
+                "wind",
+                (0.0, 0.0, 0.0),
+                PhysicsProperties {
+                    creates_gravity: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let settings = [
+            ("gravity_mode", AttributeValue::String("directional".into())),
+            ("gravity_falloff", AttributeValue::String("linear".into())),
+            ("gravity_direction", AttributeValue::Vector2(0.0, -1.0)),
+            ("gravity_radius", AttributeValue::Float(400.0)),
+            ("gravity_strength", AttributeValue::Float(200.0)),
+        ];
+        for (name, value) in settings {
+            let attribute_type = match value {
+                AttributeValue::Float(_) => AttributeType::Float,
+                AttributeValue::Vector2(..) => AttributeType::Vector2,
+                _ => AttributeType::String,
+            };
+            scene
+                .get_entity_mut(zone)
+                .unwrap()
+                .create_attribute(name, attribute_type, value)
+                .unwrap();
+        }
+        let props = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            has_collision: true,
+            ..Default::default()
+        };
+        let inner = scene
+            .create_physical_entity("inner", (-100.0, 0.0, 0.0), props.clone())
+            .unwrap();
+        let outer = scene
+            .create_physical_entity("outer", (300.0, 0.0, 0.0), props)
+            .unwrap();
+        physics_engine.load_scene(&scene);
+
+        for _ in 0..31 {
+            let updates = physics_engine.step(&mut scene);
+            scene.update_entity_attributes(updates).unwrap();
+        }
+
+        // Pushed up (the zone's direction), not toward its center; three
+        // quarters of the strength 100px out and a quarter 300px out
+        let inner = scene.get_entity(inner).unwrap();
+        let outer = scene.get_entity(outer).unwrap();
+        assert!((inner.get_x() + 100.0).abs() < 0.01);
+        assert!((outer.get_x() - 300.0).abs() < 0.01);
+        assert!(inner.get_y() < -10.0, "inner: {}", inner.get_y());
+        let ratio = inner.get_y() / outer.get_y();
+        assert!((2.5..3.5).contains(&ratio), "ratio: {}", ratio);
+    }
+
+    #[test]
+    fn test_snapshot_restore_replays_bit_identically() {
+        let mut scene = Scene::new("test_scene").unwrap();
+        let mut physics_engine = PhysicsEngine::new();
+        let falling = PhysicsProperties {
+            is_movable: true,
+            affected_by_gravity: true,
+            can_rotate: true,
+            restitution: 0.3,
+            ..Default::default()
+        };
+        // A pile of boxes tumbling onto a floor, two of them pinned
+        // together, and a gravity well off to the side
+        let mut bodies = vec![sized_box(
+            &mut scene,
+            "floor",
+            (0.0, 200.0),
+            (400.0, 20.0),
+            PhysicsProperties::default(),
+        )];
+        for i in 0..6 {
+            let x = 100.0 + (i % 3) as f32 * 15.0;
+            let y = i as f32 * -25.0;
+            bodies.push(sized_box(
+                &mut scene,
+                "box",
+                (x, y),
+                (20.0, 20.0),
+                falling.clone(),
+            ));
+        }
+        let pinned = sized_box(&mut scene, "pinned", (250.0, 0.0), (20.0, 20.0), falling);
+        sized_box(
+            &mut scene,
+            "well",
+            (350.0, 100.0),
+            (20.0, 20.0),
+            PhysicsProperties {
+                creates_gravity: true,
+                has_collision: false,
+                ..Default::default()
+            },
+        );
+        bodies.push(pinned);
+        physics_engine.load_scene(&scene);
+        let mut spec = JointSpec::new(JointKind::Revolute);
+        spec.anchor = (10.0, 10.0);
+        spec.target_anchor = (30.0, 10.0);
+        physics_engine
+            .create_joint(pinned, bodies[1], spec)
+            .unwrap();
+
+        // Positions (as written back) and velocities, as raw bits
+        let run = |engine: &mut PhysicsEngine, scene: &mut Scene, steps: usize| {
+            let mut trace = Vec::new();
+            for _ in 0..steps {
+                let mut updates: Vec<(String, u64)> = engine
+                    .step(scene)
+                    .into_iter()
+                    .map(|(entity, attr, value)| {
+                        let bits = match value {
+                            AttributeValue::Float(value) => value.to_bits() as u64,
+                            AttributeValue::Vector2(x, y) => {
+                                (x.to_bits() as u64) << 32 | y.to_bits() as u64
+                            }
+                            other => panic!("unexpected update {:?}", other),
+                        };
+                        (format!("{}/{}", entity, attr), bits)
+                    })
+                    .collect();
+                updates.sort();
+                let velocities: Vec<(u32, u32, u32)> = bodies
+                    .iter()
+                    .filter_map(|id| {
+                        let velocity = engine.get_velocity(id)?;
+                        let spin = engine.get_angular_velocity(id)?;
+                        Some((velocity.x.to_bits(), velocity.y.to_bits(), spin.to_bits()))
+                    })
+                    .collect();
+                trace.push((updates, velocities));
+            }
+            trace
+        };
+
+        run(&mut physics_engine, &mut scene, 40);
+        let snapshot = physics_engine.snapshot().unwrap();
+        let expected = run(&mut physics_engine, &mut scene, 60);
+
+        // Diverge: kick everything and let it run on
+        for id in &bodies {
+            physics_engine.apply_impulse(id, rapier2d::prelude::Vector::new(50.0, -80.0));
+        }
+        run(&mut physics_engine, &mut scene, 25);
+
+        physics_engine.restore(&snapshot).unwrap();
+        assert_eq!(run(&mut physics_engine, &mut scene, 60), expected);
+
+        // A fresh engine picks up from the same snapshot just the same
+        let mut other_engine = PhysicsEngine::new();
+        other_engine.restore(&snapshot).unwrap();
+        assert_eq!(run(&mut other_engine, &mut scene, 60), expected);
+
+        assert!(other_engine
+            .restore(&snapshot[..snapshot.len() / 2])
+            .is_err());
+        // Snapshots of another version are refused by their version
+        let mut bumped = snapshot.clone();
+        bumped[..4].copy_from_slice(&99u32.to_le_bytes());
+        let error = other_engine.restore(&bumped).unwrap_err();
+        assert!(error.contains("version 99 is not supported"), "{}", error);
+    }
 }
@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use rust_2d_game_engine::ecs::SceneManager;
-    use rust_2d_game_engine::project_manager::ProjectManager;
+    use rust_2d_game_engine::ecs::{AttributeType, AttributeValue, SceneManager};
+    use rust_2d_game_engine::physics_engine::PhysicsMaterial;
+    use rust_2d_game_engine::project_manager::{
+        ProjectManager, PROJECT_FORMAT_VERSION, SCENE_FORMAT_VERSION,
+    };
     use std::fs;
     use std::path::PathBuf;
 
@@ -35,7 +38,7 @@ mod tests {
         ProjectManager::save_scene_hierarchy(&project, &manager).unwrap();
 
         // On disk: paths must be relative with forward slashes
-        let json = fs::read_to_string(project.join("scenes/scene_manager.json")).unwrap();
+        let json = fs::read_to_string(project.join("scenes/main.scene.json")).unwrap();
         assert!(
             json.contains("\"assets/images/bird1.png\""),
             "image path should be stored relative, got: {}",
@@ -175,4 +178,418 @@ mod tests {
         stack.commit(&state_c);
         assert!(!stack.can_redo(), "commit must clear redo history");
     }
+
+    #[test]
+    fn test_prefab_edits_reach_saved_instances() {
+        let project = temp_project("prefab");
+
+        let mut manager = SceneManager::new();
+        let scene_id = manager.create_scene("main").unwrap();
+        let scene = manager.get_scene_mut(scene_id).unwrap();
+        let pipe = scene.create_entity("pipe").unwrap();
+        scene
+            .get_entity_mut(pipe)
+            .unwrap()
+            .add_image(project.join("assets/images/pipe.png"))
+            .unwrap();
+
+        let source = ProjectManager::prefab_path("pipe").unwrap();
+        assert_eq!(source, PathBuf::from("assets/prefabs/pipe.prefab"));
+        let prefab = scene.create_prefab(pipe, &source).unwrap();
+        ProjectManager::save_prefab(&project, &source, &prefab).unwrap();
+        let instance = scene
+            .instantiate_prefab(&prefab, Some(&source), (10.0, 0.0))
+            .unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &manager).unwrap();
+
+        // Prefab files store project-relative paths
+        let json = fs::read_to_string(project.join(&source)).unwrap();
+        assert!(json.contains("\"assets/images/pipe.png\""));
+        assert_eq!(ProjectManager::list_prefabs(&project), vec![source.clone()]);
+
+        // Edit the prefab on disk, then reload the project
+        let mut edited = ProjectManager::load_prefab(&project, &source).unwrap();
+        assert_eq!(
+            edited.entities[0].images[0],
+            project.join("assets/images/pipe.png")
+        );
+        edited.entities[0]
+            .create_attribute(
+                "speed",
+                rust_2d_game_engine::ecs::AttributeType::Float,
+                rust_2d_game_engine::ecs::AttributeValue::Float(50.0),
+            )
+            .unwrap();
+        ProjectManager::save_prefab(&project, &source, &edited).unwrap();
+
+        // (no active scene, so it is read on demand and synced then)
+        let mut loaded = ProjectManager::load_scene_hierarchy(&project).unwrap();
+        ProjectManager::load_all_scenes(&project, &mut loaded).unwrap();
+        let scene = loaded.get_scene(scene_id).unwrap();
+        for id in [pipe, instance] {
+            let entity = scene.get_entity(id).unwrap();
+            assert!(entity.get_attribute_by_name("speed").is_ok());
+        }
+        assert_eq!(scene.get_entity(instance).unwrap().get_x(), 10.0);
+    }
+
+    #[test]
+    fn test_unlinked_instance_saves_apart_from_its_prefab() {
+        let project = temp_project("unlink");
+
+        let mut scene = rust_2d_game_engine::ecs::Scene::new("main").unwrap();
+        let tower = scene.create_entity("tower").unwrap();
+        let flag = scene.create_entity("flag").unwrap();
+        scene.set_parent(flag, Some(tower)).unwrap();
+        let source = ProjectManager::prefab_path("tower").unwrap();
+        let prefab = scene.create_prefab(tower, &source).unwrap();
+        ProjectManager::save_prefab(&project, &source, &prefab).unwrap();
+
+        // Unlinking the root unlinks the whole instance
+        scene.unlink_prefab(tower).unwrap();
+        for id in [tower, flag] {
+            assert!(scene.get_entity(id).unwrap().prefab.is_none());
+        }
+
+        // So the child is saved as a prefab of its own (as the hierarchy's
+        // "Save as Prefab" does for unlinked entities), not over the tower
+        let flag_source = ProjectManager::prefab_path("flag").unwrap();
+        let flag_prefab = scene.create_prefab(flag, &flag_source).unwrap();
+        ProjectManager::save_prefab(&project, &flag_source, &flag_prefab).unwrap();
+        let tower_prefab = ProjectManager::load_prefab(&project, &source).unwrap();
+        assert_eq!(tower_prefab.entities.len(), 2);
+        assert_eq!(
+            ProjectManager::list_prefabs(&project),
+            vec![flag_source, source]
+        );
+    }
+
+    #[test]
+    fn test_physics_materials_are_project_assets() {
+        let project = temp_project("material");
+
+        let ice = ProjectManager::create_material(&project, "slippery ice").unwrap();
+        assert_eq!(ice, PathBuf::from("assets/materials/slippery_ice.material"));
+        assert!(ProjectManager::create_material(&project, "slippery ice").is_err());
+        assert_eq!(ProjectManager::list_materials(&project), vec![ice.clone()]);
+        assert_eq!(
+            PhysicsMaterial::load(&project.join(&ice)).unwrap(),
+            PhysicsMaterial::default()
+        );
+
+        // Entities refer to it relative to the project on disk
+        let mut manager = SceneManager::new();
+        let scene_id = manager.create_scene("main").unwrap();
+        manager.active_scene = Some(scene_id);
+        let scene = manager.get_scene_mut(scene_id).unwrap();
+        let puck = scene.create_entity("puck").unwrap();
+        scene
+            .get_entity_mut(puck)
+            .unwrap()
+            .create_attribute(
+                "physics_material",
+                AttributeType::AssetRef,
+                AttributeValue::AssetRef(project.join(&ice)),
+            )
+            .unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &manager).unwrap();
+        let scene_json = fs::read_dir(project.join("scenes"))
+            .unwrap()
+            .filter_map(|entry| fs::read_to_string(entry.unwrap().path()).ok())
+            .collect::<String>();
+        assert!(scene_json.contains("\"assets/materials/slippery_ice.material\""));
+
+        let loaded = ProjectManager::load_scene_hierarchy(&project).unwrap();
+        let value = &loaded
+            .get_scene(scene_id)
+            .unwrap()
+            .get_entity(puck)
+            .unwrap()
+            .get_attribute_by_name("physics_material")
+            .unwrap()
+            .value;
+        assert_eq!(value, &AttributeValue::AssetRef(project.join(&ice)));
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_scene_files_of_every_format_version_load() {
+        let scene_id = uuid::Uuid::parse_str("0b2f4c8e-1d3a-4f5b-9c6d-7e8f9a0b1c2d").unwrap();
+        let bird = uuid::Uuid::parse_str("22222222-2222-4222-8222-222222222222").unwrap();
+        let music = uuid::Uuid::parse_str("33333333-3333-4333-8333-333333333333").unwrap();
+
+        // v0/v1 are a single file, v2 and later an index plus a file per scene
+        for file in [
+            "scene_manager_v0.json",
+            "scene_manager_v1.json",
+            "scenes_v2",
+            "scenes_v3",
+        ] {
+            let project = temp_project("migrate_scenes");
+            if fixture(file).is_dir() {
+                for entry in fs::read_dir(fixture(file)).unwrap() {
+                    let path = entry.unwrap().path();
+                    fs::copy(
+                        &path,
+                        project.join("scenes").join(path.file_name().unwrap()),
+                    )
+                    .unwrap();
+                }
+            } else {
+                fs::copy(fixture(file), project.join("scenes/scene_manager.json")).unwrap();
+            }
+
+            let loaded = ProjectManager::load_scene_hierarchy(&project)
+                .unwrap_or_else(|e| panic!("{}: {}", file, e));
+            assert_eq!(loaded.active_scene, Some(scene_id), "{}", file);
+            let entity = loaded
+                .get_scene(scene_id)
+                .unwrap()
+                .get_entity(bird)
+                .unwrap();
+            assert_eq!(entity.get_x(), 120.0);
+            assert!(entity.enabled);
+            assert_eq!(entity.images, vec![project.join("assets/images/bird1.png")]);
+            assert_eq!(entity.script, Some(project.join("assets/scripts/bird.lua")));
+            // v0 (written by the original engine) predates List/AssetRef
+            if file != "scene_manager_v0.json" {
+                assert_eq!(
+                    entity.get_attribute_by_name("frames").unwrap().value,
+                    AttributeValue::List(vec![
+                        AttributeValue::AssetRef(project.join("assets/images/bird2.png")),
+                        AttributeValue::AssetRef(project.join("assets/images/bird3.png")),
+                    ]),
+                    "{}",
+                    file
+                );
+            }
+            // The v3 bird has a body but no rotation; v4 adds one
+            if file == "scenes_v3" {
+                assert_eq!(
+                    entity.get_attribute_by_name("rotation").unwrap().value,
+                    AttributeValue::Float(0.0)
+                );
+            }
+            assert_eq!(
+                loaded.get_shared_entity(music).unwrap().sounds,
+                vec![project.join("assets/sounds/theme.mp3")]
+            );
+
+            // Saving writes the current version
+            ProjectManager::save_scene_hierarchy(&project, &loaded).unwrap();
+            let json = fs::read_to_string(project.join("scenes/scene_manager.json")).unwrap();
+            assert!(json.contains(&format!("\"format_version\": {}", SCENE_FORMAT_VERSION)));
+            assert!(ProjectManager::load_scene_hierarchy(&project).is_ok());
+        }
+
+        // Files from a newer engine are refused rather than misread
+        let project = temp_project("migrate_newer");
+        let newer = fs::read_to_string(fixture("scene_manager_v1.json"))
+            .unwrap()
+            .replace("\"format_version\": 1", "\"format_version\": 99");
+        fs::write(project.join("scenes/scene_manager.json"), newer).unwrap();
+        let err = ProjectManager::load_scene_hierarchy(&project).unwrap_err();
+        assert!(err.contains("newer engine"), "{}", err);
+    }
+
+    #[test]
+    fn test_project_files_of_every_format_version_load() {
+        for file in ["project_v0.epm", "project_v1.epm"] {
+            let project = temp_project("migrate_project");
+            fs::copy(fixture(file), project.join("project.epm")).unwrap();
+
+            let metadata = ProjectManager::load_project(&project)
+                .unwrap_or_else(|e| panic!("{}: {}", file, e));
+            assert_eq!(metadata.format_version, PROJECT_FORMAT_VERSION);
+            assert_eq!(metadata.project_name, "flappy");
+            assert_eq!(metadata.project_path, project.to_str().unwrap());
+            // Loading never rewrites the file
+            assert_eq!(
+                fs::read_to_string(project.join("project.epm")).unwrap(),
+                fs::read_to_string(fixture(file)).unwrap()
+            );
+        }
+
+        let project = temp_project("migrate_project_newer");
+        let newer = fs::read_to_string(fixture("project_v1.epm"))
+            .unwrap()
+            .replace("\"format_version\": 1", "\"format_version\": 99");
+        fs::write(project.join("project.epm"), newer).unwrap();
+        let err = ProjectManager::load_project(&project).unwrap_err();
+        assert!(err.contains("newer engine"), "{}", err);
+    }
+
+    #[test]
+    fn test_scenes_saved_one_file_each_and_read_lazily() {
+        let project = temp_project("scene_files");
+
+        let mut manager = SceneManager::new();
+        let level_1 = manager.create_scene("Level 1").unwrap();
+        let level_2 = manager.create_scene("level_2").unwrap();
+        manager.set_active_scene(level_1).unwrap();
+        let scene = manager.get_scene_mut(level_2).unwrap();
+        let goal = scene.create_entity("goal").unwrap();
+        let speed = scene
+            .get_entity_mut(goal)
+            .unwrap()
+            .create_attribute("speed", AttributeType::Float, AttributeValue::Float(3.0))
+            .unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &manager).unwrap();
+
+        let scene_file = |name: &str| project.join("scenes").join(name);
+        assert!(scene_file("level_1.scene.json").exists());
+        let level_2_json = fs::read_to_string(scene_file("level_2.scene.json")).unwrap();
+
+        // Only the active scene is read up front
+        let mut loaded = ProjectManager::load_scene_hierarchy(&project).unwrap();
+        assert!(loaded.get_scene(level_1).is_some());
+        assert!(loaded.is_scene_deferred(level_2));
+        assert_eq!(loaded.get_scene_id_by_name("level_2"), Some(level_2));
+
+        // Saving an edit to one scene leaves the other's file as it was
+        let camera = loaded.get_scene(level_1).unwrap().default_camera.unwrap();
+        loaded
+            .get_scene_mut(level_1)
+            .unwrap()
+            .get_entity_mut(camera)
+            .unwrap()
+            .set_x(50.0)
+            .unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &loaded).unwrap();
+        assert_eq!(
+            fs::read_to_string(scene_file("level_2.scene.json")).unwrap(),
+            level_2_json
+        );
+
+        // Read on demand, with entity and attribute ids preserved
+        ProjectManager::load_deferred_scene(&project, &mut loaded, level_2).unwrap();
+        let entity = loaded.get_scene(level_2).unwrap().get_entity(goal).unwrap();
+        assert_eq!(entity.get_attribute_by_name("speed").unwrap().id, speed);
+
+        // Renamed scenes keep their file; deleted ones lose it
+        loaded.get_scene_mut(level_2).unwrap().name = "finale".to_string();
+        ProjectManager::save_scene_hierarchy(&project, &loaded).unwrap();
+        assert!(scene_file("level_2.scene.json").exists());
+        assert!(!scene_file("finale.scene.json").exists());
+        loaded.delete_scene(level_2).unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &loaded).unwrap();
+        assert!(!scene_file("level_2.scene.json").exists());
+        assert_eq!(
+            ProjectManager::load_scene_hierarchy(&project)
+                .unwrap()
+                .get_scene(level_1)
+                .unwrap()
+                .get_entity(camera)
+                .unwrap()
+                .get_x(),
+            50.0
+        );
+    }
+
+    #[test]
+    fn test_scene_files_are_canonical_and_merge_three_way() {
+        let project = temp_project("merge");
+        let scene_json = |manager: &SceneManager| {
+            ProjectManager::save_scene_hierarchy(&project, manager).unwrap();
+            fs::read_to_string(project.join("scenes/main.scene.json")).unwrap()
+        };
+        let float = |manager: &SceneManager, entity: uuid::Uuid, name: &str| {
+            let scene = manager.scenes.values().next().unwrap();
+            let attribute = scene
+                .get_entity(entity)
+                .unwrap()
+                .get_attribute_by_name(name);
+            attribute.unwrap().value.clone()
+        };
+
+        let mut base = SceneManager::new();
+        let scene_id = base.create_scene("main").unwrap();
+        base.set_active_scene(scene_id).unwrap();
+        let scene = base.get_scene_mut(scene_id).unwrap();
+        let bird = scene.create_entity("bird").unwrap();
+        let entity = scene.get_entity_mut(bird).unwrap();
+        for (name, value) in [("speed", 1.0), ("gravity", 9.81), ("drag", 0.1)] {
+            entity
+                .create_attribute(name, AttributeType::Float, AttributeValue::Float(value))
+                .unwrap();
+        }
+        let base_json = scene_json(&base);
+
+        // Keyed by name, floats without f32 noise, same bytes every save
+        assert!(base_json.contains("\"bird\": {"), "{}", base_json);
+        assert!(base_json.contains("\"gravity\": {"), "{}", base_json);
+        assert!(base_json.contains("9.81"), "{}", base_json);
+        assert!(!base_json.contains("9.8100004"), "{}", base_json);
+        assert_eq!(scene_json(&base), base_json);
+
+        // Ours: faster bird, less drag. Theirs: a pipe, more gravity, no drag
+        let edit = |f: &dyn Fn(&mut rust_2d_game_engine::ecs::Scene)| {
+            let mut manager = base.clone();
+            f(manager.get_scene_mut(scene_id).unwrap());
+            scene_json(&manager)
+        };
+        let set = |scene: &mut rust_2d_game_engine::ecs::Scene, name: &str, value: f32| {
+            let entity = scene.get_entity_mut(bird).unwrap();
+            let id = entity.get_attribute_by_name(name).unwrap().id;
+            entity
+                .modify_attribute(id, None, None, Some(AttributeValue::Float(value)))
+                .unwrap();
+        };
+        let ours = edit(&|scene| {
+            set(scene, "speed", 2.0);
+            set(scene, "drag", 0.05);
+        });
+        let theirs = edit(&|scene| {
+            scene.create_entity("pipe").unwrap();
+            set(scene, "gravity", 12.0);
+            let entity = scene.get_entity_mut(bird).unwrap();
+            let drag = entity.get_attribute_by_name("drag").unwrap().id;
+            entity.delete_attribute(drag).unwrap();
+        });
+
+        let (merged, conflicts) =
+            ProjectManager::merge_scene_files(&base_json, &ours, &theirs).unwrap();
+        fs::write(project.join("scenes/main.scene.json"), &merged).unwrap();
+        let loaded = ProjectManager::load_scene_hierarchy(&project).unwrap();
+        assert_eq!(float(&loaded, bird, "speed"), AttributeValue::Float(2.0));
+        assert_eq!(float(&loaded, bird, "gravity"), AttributeValue::Float(12.0));
+        let scene = loaded.get_scene(scene_id).unwrap();
+        assert!(scene.find_entity_by_name("pipe").is_some());
+
+        // Drag was changed by us and deleted by them: reported, ours kept
+        assert_eq!(float(&loaded, bird, "drag"), AttributeValue::Float(0.05));
+        assert_eq!(conflicts.len(), 1, "{:?}", conflicts);
+        assert_eq!(
+            conflicts[0].location,
+            "scene \"main\" / entity \"bird\" / attribute \"drag\""
+        );
+    }
+
+    #[test]
+    fn test_scene_files_carry_their_format_version() {
+        let project = temp_project("scene_file_version");
+        let mut manager = SceneManager::new();
+        let scene_id = manager.create_scene("main").unwrap();
+        manager.set_active_scene(scene_id).unwrap();
+        ProjectManager::save_scene_hierarchy(&project, &manager).unwrap();
+        let scene_file = project.join("scenes/main.scene.json");
+        let json = fs::read_to_string(&scene_file).unwrap();
+        let version = format!("\"format_version\": {}", SCENE_FORMAT_VERSION);
+        assert!(json.starts_with(&format!("{{\n  {},", version)), "{}", json);
+
+        // A scene file from a newer engine is refused on load and in merges
+        let newer = json.replace(&version, "\"format_version\": 99");
+        fs::write(&scene_file, &newer).unwrap();
+        let err = ProjectManager::load_scene_hierarchy(&project).unwrap_err();
+        assert!(err.contains("newer engine"), "{}", err);
+        let err = ProjectManager::merge_scene_files(&json, &json, &newer).unwrap_err();
+        assert!(err.contains("newer engine"), "{}", err);
+        let (merged, _) = ProjectManager::merge_scene_files(&json, &json, &json).unwrap();
+        assert_eq!(merged, json);
+    }
 }
@@ -119,4 +119,38 @@ mod tests {
 
         assert_eq!(renderer.get_memory_usage(), 1024);
     }
+
+    #[test]
+    fn test_render_uses_parent_transform() {
+        let mut renderer = RenderEngine::new();
+        renderer.update_viewport_size(800.0, 600.0);
+
+        let test_path = Path::new("child.png");
+        renderer.texture_cache.insert(
+            RenderEngine::path_to_uuid(test_path),
+            TextureInfo {
+                data: vec![255; 4 * 10 * 10],
+                dimensions: (10, 10),
+                aspect_ratio: 1.0,
+            },
+        );
+
+        let mut scene = Scene::new("test_scene").unwrap();
+        let parent_id = scene.create_entity("parent").unwrap();
+        let child_id = scene.create_entity("child").unwrap();
+        scene
+            .get_entity_mut(parent_id)
+            .unwrap()
+            .set_position(100.0, 200.0, 5.0)
+            .unwrap();
+        scene.set_parent(child_id, Some(parent_id)).unwrap();
+        let child = scene.get_entity_mut(child_id).unwrap();
+        child.set_position(20.0, 30.0, 1.0).unwrap();
+        child.images.push(test_path.to_path_buf());
+
+        let queue = renderer.render(&scene);
+        let entry = queue.iter().find(|e| e.entity_id == child_id).unwrap();
+        assert_eq!(entry.screen_pos, (120.0, 230.0));
+        assert_eq!(entry.z, 6.0);
+    }
 }